@@ -0,0 +1,238 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, Timelike};
+use librqbit::limits::LimitsConfig;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+use crate::config::{BandwidthRule, RqbitDesktopConfigBandwidthSchedule, parse_hh_mm};
+use crate::error::CommandError;
+use crate::torrent_server::{self, State};
+
+/// How often the schedule is re-evaluated
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Manual override of the schedule
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BandwidthMode {
+    /// No limits at all
+    Turbo,
+    /// The configured slow limits
+    Slow,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BandwidthOverride {
+    pub mode: BandwidthMode,
+    /// Unix timestamp after which the override expires, None to keep it until cleared
+    pub until: Option<i64>,
+}
+
+/// Where the currently applied limits come from
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitsSource {
+    Override,
+    Schedule,
+    Default,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BandwidthStatus {
+    #[serde(rename = "override")]
+    pub override_: Option<BandwidthOverride>,
+    pub source: LimitsSource,
+    pub limits: LimitsConfig,
+}
+
+/// Runtime state of the bandwidth scheduler
+#[derive(Default)]
+pub struct BandwidthScheduler {
+    override_: RwLock<Option<BandwidthOverride>>,
+    /// Limits last applied, so the session is only touched on changes
+    applied: Mutex<Option<LimitsConfig>>,
+}
+
+impl BandwidthScheduler {
+    fn current_override(&self, now: i64) -> Option<BandwidthOverride> {
+        let mut g = self.override_.write();
+        if g.as_ref()
+            .and_then(|o| o.until)
+            .is_some_and(|until| until <= now)
+        {
+            info!("Bandwidth override expired");
+            *g = None;
+        }
+        g.clone()
+    }
+
+    /// Limits that should be in effect right now
+    fn effective(
+        &self,
+        base: &LimitsConfig,
+        schedule: &RqbitDesktopConfigBandwidthSchedule,
+        now: DateTime<Local>,
+    ) -> BandwidthStatus {
+        let override_ = self.current_override(now.timestamp());
+        let (source, limits) = match override_.as_ref().map(|o| o.mode) {
+            Some(BandwidthMode::Turbo) => (LimitsSource::Override, LimitsConfig::default()),
+            Some(BandwidthMode::Slow) => (LimitsSource::Override, schedule.slow_limits),
+            None => match schedule
+                .enabled
+                .then(|| schedule.rules.iter().find(|r| rule_matches(r, now)))
+                .flatten()
+            {
+                Some(rule) => (LimitsSource::Schedule, rule.limits),
+                None => (LimitsSource::Default, *base),
+            },
+        };
+        BandwidthStatus {
+            override_,
+            source,
+            limits,
+        }
+    }
+
+    /// Forget what was applied, so the next [`Self::apply`] sets limits on a new session
+    pub(crate) fn reset(&self) {
+        *self.applied.lock() = None;
+    }
+
+    /// Apply the effective limits to the running session if they changed
+    pub(crate) fn apply(&self, state: &State) -> Option<BandwidthStatus> {
+        let (base, schedule) = {
+            let g = state.shared.read();
+            let shared = g.as_ref()?;
+            (
+                shared.config.ratelimits,
+                shared.config.bandwidth_schedule.clone(),
+            )
+        };
+        let api = state.api().ok()?;
+        let status = self.effective(&base, &schedule, Local::now());
+
+        let mut applied = self.applied.lock();
+        if *applied != Some(status.limits) {
+            info!(
+                source = ?status.source,
+                upload_bps = ?status.limits.upload_bps,
+                download_bps = ?status.limits.download_bps,
+                "Applying rate limits"
+            );
+            torrent_server::apply_rate_limits(&api, &status.limits);
+            *applied = Some(status.limits);
+        }
+        Some(status)
+    }
+}
+
+/// Whether a rule covers the given local time
+fn rule_matches(rule: &BandwidthRule, now: DateTime<Local>) -> bool {
+    let (Some(start), Some(end)) = (parse_hh_mm(&rule.start), parse_hh_mm(&rule.end)) else {
+        return false;
+    };
+    let minute = now.hour() * 60 + now.minute();
+    let today = now.weekday().num_days_from_monday() as u8;
+    let yesterday = (today + 6) % 7;
+    let on_day = |day: u8| rule.weekdays.is_empty() || rule.weekdays.contains(&day);
+
+    if start <= end {
+        on_day(today) && (start..end).contains(&minute)
+    } else {
+        // Spans midnight: started today, or started yesterday and still running
+        (on_day(today) && minute >= start) || (on_day(yesterday) && minute < end)
+    }
+}
+
+/// Spawn the background task applying scheduled limits to the session
+pub fn spawn_bandwidth_scheduler(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let state = app.state::<State>();
+            state.bandwidth.apply(&state);
+        }
+    });
+}
+
+/// Get the active rate limits and where they come from
+#[tauri::command]
+pub fn get_bandwidth_status(
+    state: tauri::State<'_, State>,
+) -> Result<BandwidthStatus, CommandError> {
+    state
+        .bandwidth
+        .apply(&state)
+        .ok_or_else(|| CommandError::NotConfigured("Torrent session not configured".to_string()))
+}
+
+/// Set a manual "turbo"/"slow" override, optionally for a number of minutes.
+/// Pass no mode to return to the schedule.
+#[tauri::command]
+pub fn set_bandwidth_override(
+    state: tauri::State<'_, State>,
+    mode: Option<BandwidthMode>,
+    duration_minutes: Option<u32>,
+) -> Result<BandwidthStatus, CommandError> {
+    let until = duration_minutes.map(|m| Local::now().timestamp() + m as i64 * 60);
+    *state.bandwidth.override_.write() = mode.map(|mode| BandwidthOverride { mode, until });
+    info!("Bandwidth override set to {:?} until {:?}", mode, until);
+    get_bandwidth_status(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn rule(weekdays: Vec<u8>, start: &str, end: &str) -> BandwidthRule {
+        BandwidthRule {
+            weekdays,
+            start: start.to_owned(),
+            end: end.to_owned(),
+            limits: LimitsConfig::default(),
+        }
+    }
+
+    /// 2024-01-01 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 1, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rule_matches_same_day() {
+        let working_hours = rule(vec![0, 1, 2, 3, 4], "09:00", "17:30");
+        assert!(rule_matches(&working_hours, at(1, 9, 0)));
+        assert!(rule_matches(&working_hours, at(1, 17, 29)));
+        assert!(!rule_matches(&working_hours, at(1, 17, 30)));
+        assert!(!rule_matches(&working_hours, at(1, 8, 59)));
+        // Saturday
+        assert!(!rule_matches(&working_hours, at(6, 12, 0)));
+    }
+
+    #[test]
+    fn test_rule_matches_across_midnight() {
+        // Friday night into Saturday morning
+        let night = rule(vec![4], "22:00", "06:00");
+        assert!(rule_matches(&night, at(5, 23, 0)));
+        assert!(rule_matches(&night, at(6, 5, 59)));
+        assert!(!rule_matches(&night, at(6, 6, 0)));
+        assert!(!rule_matches(&night, at(6, 23, 0)));
+        // Friday morning belongs to Thursday night, which isn't in the rule
+        assert!(!rule_matches(&night, at(5, 1, 0)));
+
+        let every_night = rule(vec![], "22:00", "06:00");
+        assert!(rule_matches(&every_night, at(1, 0, 30)));
+    }
+
+    #[test]
+    fn test_rule_with_invalid_time_never_matches() {
+        assert!(!rule_matches(&rule(vec![], "25:00", "06:00"), at(1, 3, 0)));
+    }
+}
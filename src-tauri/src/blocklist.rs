@@ -0,0 +1,459 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, bail};
+use librqbit::ApiError;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Url};
+use tracing::{info, warn};
+
+use crate::config::{RqbitDesktopConfig, RqbitDesktopConfigBlocklist};
+use crate::error::CommandError;
+use crate::torrent_server::State;
+
+/// How often sources are checked for being due a refresh
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// An inclusive range of blocked addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    pub start: IpAddr,
+    pub end: IpAddr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlocklistSourceStatus {
+    pub source: String,
+    pub ranges: usize,
+    /// Unix timestamp of the cached copy
+    pub updated_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BlocklistStatus {
+    pub enabled: bool,
+    /// Ranges loaded into the session, including manually banned IPs
+    pub ranges: usize,
+    pub sources: Vec<BlocklistSourceStatus>,
+    /// Peers rejected by the session, if it reports them
+    pub blocked_peers: Option<u64>,
+    /// Bans or sources changed since the session started; applied by [`apply_blocklist`]
+    pub pending: bool,
+}
+
+fn blocklist_dir() -> anyhow::Result<PathBuf> {
+    Ok(directories::ProjectDirs::from("com", "rqbit", "desktop")
+        .context("directories::ProjectDirs::from")?
+        .data_dir()
+        .join("blocklists"))
+}
+
+/// 64-bit FNV-1a, so cache file names don't change between Rust releases
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn cache_path(source: &str) -> anyhow::Result<PathBuf> {
+    Ok(blocklist_dir()?.join(format!("{:016x}.txt", fnv1a(source.as_bytes()))))
+}
+
+/// Parse a blocklist in eMule `.dat`, PeerGuardian `.p2p` or CIDR format; formats may be mixed.
+/// Unparseable lines are skipped.
+pub fn parse_blocklist(contents: &str) -> Vec<IpRange> {
+    contents.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<IpRange> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+        return None;
+    }
+
+    // eMule: "001.002.003.000 - 001.002.003.255 , 000 , Description"
+    if let Some((range, rest)) = line.split_once(',')
+        && let Some(range) = parse_range(range)
+    {
+        // Access level 127 and above means allowed
+        let level = rest.split(',').next()?.trim().parse::<u32>().unwrap_or(0);
+        return (level < 127).then_some(range);
+    }
+
+    // PeerGuardian: "Description:1.2.3.0-1.2.3.255"
+    if let Some((_, range)) = line.rsplit_once(':')
+        && let Some(range) = parse_range(range)
+    {
+        return Some(range);
+    }
+
+    // CIDR or single address, IPv4 or IPv6
+    if let Some((addr, prefix)) = line.split_once('/') {
+        return cidr(parse_ip(addr)?, prefix.trim().parse().ok()?);
+    }
+    parse_range(line).or_else(|| {
+        let ip = parse_ip(line)?;
+        Some(IpRange { start: ip, end: ip })
+    })
+}
+
+fn parse_range(range: &str) -> Option<IpRange> {
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (parse_ip(start)?, parse_ip(end)?);
+    (start.is_ipv4() == end.is_ipv4() && start <= end).then_some(IpRange { start, end })
+}
+
+/// Like `IpAddr::from_str`, but accepts zero-padded IPv4 octets as used by eMule lists
+fn parse_ip(s: &str) -> Option<IpAddr> {
+    let s = s.trim();
+    if let Ok(ip) = s.parse() {
+        return Some(ip);
+    }
+    let octets: Vec<u8> = s
+        .split('.')
+        .map(|o| o.parse::<u8>().ok())
+        .collect::<Option<_>>()?;
+    let octets: [u8; 4] = octets.try_into().ok()?;
+    Some(IpAddr::V4(Ipv4Addr::from(octets)))
+}
+
+fn cidr(ip: IpAddr, prefix: u32) -> Option<IpRange> {
+    match ip {
+        IpAddr::V4(ip) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            let start = u32::from(ip) & mask;
+            Some(IpRange {
+                start: IpAddr::V4(start.into()),
+                end: IpAddr::V4((start | !mask).into()),
+            })
+        }
+        IpAddr::V6(ip) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            let start = u128::from(ip) & mask;
+            Some(IpRange {
+                start: IpAddr::V6(Ipv6Addr::from(start)),
+                end: IpAddr::V6(Ipv6Addr::from(start | !mask)),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Merge banned IPs and cached blocklists into one PeerGuardian file for the session.
+/// Returns the `file://` URL to pass as the session blocklist, or None if nothing is blocked,
+/// along with what was loaded per source.
+///
+/// The PeerGuardian format separates the description with a colon, so IPv6 ranges can't be
+/// written unambiguously and are left out.
+pub(crate) fn write_session_blocklist(
+    config: &RqbitDesktopConfig,
+) -> anyhow::Result<(Option<String>, BlocklistStatus)> {
+    let mut ranges: Vec<IpRange> = config
+        .connections
+        .banned_ips
+        .iter()
+        .map(|ip| IpRange {
+            start: *ip,
+            end: *ip,
+        })
+        .collect();
+
+    let mut sources = Vec::new();
+    if config.blocklist.enabled {
+        for source in &config.blocklist.sources {
+            let status = match cache_path(source).and_then(|path| {
+                let contents = std::fs::read_to_string(&path)?;
+                let updated_at = std::fs::metadata(&path)?
+                    .modified()?
+                    .duration_since(SystemTime::UNIX_EPOCH)?
+                    .as_secs() as i64;
+                Ok((parse_blocklist(&contents), updated_at))
+            }) {
+                Ok((parsed, updated_at)) => {
+                    let status = BlocklistSourceStatus {
+                        source: source.clone(),
+                        ranges: parsed.len(),
+                        updated_at: Some(updated_at),
+                        error: None,
+                    };
+                    ranges.extend(parsed);
+                    status
+                }
+                Err(_) => BlocklistSourceStatus {
+                    source: source.clone(),
+                    ranges: 0,
+                    updated_at: None,
+                    error: Some("Not downloaded yet".to_owned()),
+                },
+            };
+            sources.push(status);
+        }
+    }
+
+    let skipped = ranges.iter().filter(|r| r.start.is_ipv6()).count();
+    if skipped > 0 {
+        warn!("Skipping {} IPv6 blocklist ranges", skipped);
+        ranges.retain(|r| r.start.is_ipv4());
+    }
+    let status = BlocklistStatus {
+        enabled: config.blocklist.enabled,
+        ranges: ranges.len(),
+        sources,
+        blocked_peers: None,
+        pending: false,
+    };
+    if ranges.is_empty() {
+        return Ok((None, status));
+    }
+
+    let dir = blocklist_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("session.p2p");
+    let contents: String = ranges
+        .iter()
+        .map(|r| format!("blocked:{}-{}\n", r.start, r.end))
+        .collect();
+    std::fs::write(&path, contents)?;
+    info!("Loaded {} blocked IP ranges", ranges.len());
+    let url = Url::from_file_path(&path)
+        .map_err(|_| anyhow::anyhow!("Invalid blocklist path {}", path.display()))?;
+    Ok((Some(url.to_string()), status))
+}
+
+/// Download or copy a source into the cache. Returns true if its contents changed.
+async fn fetch_source(state: &State, source: &str) -> anyhow::Result<bool> {
+    let contents = if source.starts_with("http://") || source.starts_with("https://") {
+        let response = crate::proxy::client_builder(state)
+            .build()?
+            .get(source)
+            .send()
+            .await
+            .context("Failed to download blocklist")?
+            .error_for_status()?;
+        response.bytes().await?.to_vec()
+    } else {
+        tokio::fs::read(source)
+            .await
+            .with_context(|| format!("Failed to read {}", source))?
+    };
+    if contents.starts_with(&[0x1f, 0x8b]) || contents.starts_with(b"PK") {
+        bail!("Compressed blocklists are not supported, use the uncompressed list");
+    }
+    let text = String::from_utf8_lossy(&contents);
+    if parse_blocklist(&text).is_empty() {
+        bail!("No IP ranges found, unsupported format?");
+    }
+
+    let path = cache_path(source)?;
+    if tokio::fs::read(&path).await.ok().as_deref() == Some(contents.as_slice()) {
+        // Touch so the refresh timer restarts
+        std::fs::File::options()
+            .append(true)
+            .open(&path)?
+            .set_modified(SystemTime::now())?;
+        return Ok(false);
+    }
+    tokio::fs::create_dir_all(blocklist_dir()?).await?;
+    tokio::fs::write(&path, &contents).await?;
+    Ok(true)
+}
+
+fn is_due(source: &str, config: &RqbitDesktopConfigBlocklist) -> bool {
+    cache_path(source)
+        .and_then(|p| Ok(std::fs::metadata(p)?.modified()?.elapsed()?))
+        .map_or(true, |age| age >= config.refresh_interval)
+}
+
+/// Refresh due (or all, if `force`) sources and mark the blocklist pending if any changed
+async fn refresh(app: &AppHandle, force: bool) -> Vec<(String, anyhow::Error)> {
+    let state = app.state::<State>();
+    let Some(config) = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.blocklist.clone())
+    else {
+        return Vec::new();
+    };
+    if !config.enabled {
+        return Vec::new();
+    }
+
+    let mut changed = false;
+    let mut errors = Vec::new();
+    for source in &config.sources {
+        if !force && !is_due(source, &config) {
+            continue;
+        }
+        match fetch_source(&state, source).await {
+            Ok(c) => changed |= c,
+            Err(e) => {
+                warn!("Failed to refresh blocklist {}: {:#}", source, e);
+                errors.push((source.clone(), e));
+            }
+        }
+    }
+
+    // Reloading would interrupt playback at random times, the user applies it instead
+    if changed && let Some(status) = state.blocklist_status.lock().as_mut() {
+        info!("Blocklist changed, applied at the next session restart");
+        status.pending = true;
+    }
+    errors
+}
+
+/// Spawn the task that keeps blocklist sources up to date
+pub fn spawn_blocklist_updater(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            refresh(&app, false).await;
+        }
+    });
+}
+
+/// Get loaded blocklist ranges per source and how many peers were rejected
+#[tauri::command]
+pub fn get_blocklist_status(state: tauri::State<'_, State>) -> BlocklistStatus {
+    blocklist_status(&state)
+}
+
+pub(crate) fn blocklist_status(state: &State) -> BlocklistStatus {
+    let mut status = state.blocklist_status.lock().clone().unwrap_or_default();
+    if let Ok(api) = state.api() {
+        let stats = serde_json::to_value(api.api_session_stats()).unwrap_or_default();
+        let counters = &stats["counters"];
+        status.blocked_peers = match (
+            counters["blocked_incoming"].as_u64(),
+            counters["blocked_outgoing"].as_u64(),
+        ) {
+            (None, None) => None,
+            (incoming, outgoing) => Some(incoming.unwrap_or(0) + outgoing.unwrap_or(0)),
+        };
+    }
+    status
+}
+
+/// Re-download all blocklist sources now. Changes are applied with [`apply_blocklist`].
+#[tauri::command]
+pub async fn refresh_blocklist(app: AppHandle) -> Result<BlocklistStatus, CommandError> {
+    let errors = refresh(&app, true).await;
+    let mut status = blocklist_status(&app.state::<State>());
+    for (source, error) in errors {
+        if let Some(s) = status.sources.iter_mut().find(|s| s.source == source) {
+            s.error = Some(format!("{:#}", error));
+        }
+    }
+    Ok(status)
+}
+
+/// Restart the session to load pending bans and blocklist updates. Running torrents
+/// and streams are interrupted, so this is left to the user.
+#[tauri::command]
+pub async fn apply_blocklist(state: tauri::State<'_, State>) -> Result<BlocklistStatus, ApiError> {
+    if blocklist_status(&state).pending {
+        state.reload_session().await?;
+    }
+    Ok(blocklist_status(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v4(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_blocklist_formats() {
+        let contents = "\
+# comment
+// another comment
+
+001.002.003.000 - 001.002.003.255 , 000 , eMule entry
+005.006.007.000 - 005.006.007.255 , 200 , allowed entry
+Some org:10.0.0.0-10.0.0.255
+192.168.1.0/24
+172.16.0.1 - 172.16.0.9
+8.8.8.8
+not an address
+";
+        assert_eq!(
+            parse_blocklist(contents),
+            vec![
+                IpRange {
+                    start: v4("1.2.3.0"),
+                    end: v4("1.2.3.255"),
+                },
+                IpRange {
+                    start: v4("10.0.0.0"),
+                    end: v4("10.0.0.255"),
+                },
+                IpRange {
+                    start: v4("192.168.1.0"),
+                    end: v4("192.168.1.255"),
+                },
+                IpRange {
+                    start: v4("172.16.0.1"),
+                    end: v4("172.16.0.9"),
+                },
+                IpRange {
+                    start: v4("8.8.8.8"),
+                    end: v4("8.8.8.8"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_blocklist_rejects_bad_ranges() {
+        assert!(parse_blocklist("10.0.0.9-10.0.0.1").is_empty());
+        assert!(parse_blocklist("10.0.0.1-::1").is_empty());
+        assert!(parse_blocklist("10.0.0.0/33").is_empty());
+    }
+
+    #[test]
+    fn test_cidr() {
+        assert_eq!(
+            cidr(v4("10.1.2.3"), 8),
+            Some(IpRange {
+                start: v4("10.0.0.0"),
+                end: v4("10.255.255.255"),
+            })
+        );
+        assert_eq!(
+            cidr(v4("10.1.2.3"), 32),
+            Some(IpRange {
+                start: v4("10.1.2.3"),
+                end: v4("10.1.2.3"),
+            })
+        );
+        assert_eq!(
+            cidr(v4("10.1.2.3"), 0),
+            Some(IpRange {
+                start: v4("0.0.0.0"),
+                end: v4("255.255.255.255"),
+            })
+        );
+        assert_eq!(
+            cidr("2001:db8::1".parse().unwrap(), 32),
+            Some(IpRange {
+                start: "2001:db8::".parse().unwrap(),
+                end: "2001:db8:ffff:ffff:ffff:ffff:ffff:ffff".parse().unwrap(),
+            })
+        );
+        assert_eq!(cidr("::1".parse().unwrap(), 129), None);
+    }
+
+    #[test]
+    fn test_cache_path_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
     path::{Path, PathBuf},
     time::Duration,
 };
@@ -11,6 +11,8 @@ use librqbit::{
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
+use crate::quality::{Resolution, Source};
+
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
 pub struct RqbitDesktopConfigDht {
@@ -44,10 +46,21 @@ pub struct RqbitDesktopConfigConnections {
     pub peer_connect_timeout: Duration,
     #[serde_as(as = "serde_with::DurationSeconds")]
     pub peer_read_write_timeout: Duration,
+
+    /// Peer IPs banned from all torrents
+    pub banned_ips: Vec<IpAddr>,
+
+    /// Interface name (e.g. "wg0") or local IP to bind torrent traffic to, empty for any
+    pub bind_interface: String,
+    /// Pause all torrents while the bind interface is down
+    pub kill_switch: bool,
 }
 
 impl RqbitDesktopConfigConnections {
-    pub fn as_listener_and_connect_opts(&self) -> (Option<ListenerOptions>, ConnectionOptions) {
+    pub fn as_listener_and_connect_opts(
+        &self,
+        bind_ip: Option<IpAddr>,
+    ) -> (Option<ListenerOptions>, ConnectionOptions) {
         let mode = match (self.enable_tcp_listen, self.enable_utp) {
             (true, true) => Some(ListenerMode::TcpAndUtp),
             (true, false) => Some(ListenerMode::TcpOnly),
@@ -56,7 +69,11 @@ impl RqbitDesktopConfigConnections {
         };
         let listener_opts = mode.map(|mode| ListenerOptions {
             mode,
-            listen_addr: (Ipv4Addr::UNSPECIFIED, self.listen_port).into(),
+            listen_addr: (
+                bind_ip.unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                self.listen_port,
+            )
+                .into(),
             enable_upnp_port_forwarding: self.enable_upnp_port_forward,
             ..Default::default()
         });
@@ -88,6 +105,9 @@ impl Default for RqbitDesktopConfigConnections {
             socks_proxy: String::new(),
             peer_connect_timeout: Duration::from_secs(2),
             peer_read_write_timeout: Duration::from_secs(10),
+            banned_ips: Vec::new(),
+            bind_interface: String::new(),
+            kill_switch: false,
         }
     }
 }
@@ -164,6 +184,481 @@ pub struct RqbitDesktopConfigUpnp {
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(default)]
+pub struct RqbitDesktopConfigPostProcess {
+    /// Run the pipeline when a torrent finishes downloading
+    pub enabled: bool,
+    /// Transmux non-MP4 video files for HTTP streaming
+    pub transmux: bool,
+    /// Extract text subtitle streams to WebVTT sidecar files
+    pub extract_subtitles: bool,
+    /// Move completed downloads into the organised library layout
+    pub move_to_library: bool,
+    /// Root of the library (`Movies/Title (Year)/`, `Shows/Name/Season 01/`)
+    pub library_folder: PathBuf,
+}
+
+impl Default for RqbitDesktopConfigPostProcess {
+    fn default() -> Self {
+        let library_folder = directories::ProjectDirs::from("com", "rqbit", "desktop")
+            .expect("directories::ProjectDirs::from")
+            .data_dir()
+            .join("library");
+
+        Self {
+            enabled: false,
+            transmux: true,
+            extract_subtitles: true,
+            move_to_library: false,
+            library_folder,
+        }
+    }
+}
+
+/// What to do with a torrent once a seeding limit is reached
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedingAction {
+    #[default]
+    Pause,
+    Forget,
+    DeleteWithFiles,
+}
+
+/// Limits after which a completed torrent stops seeding. Used both globally
+/// and as a per-torrent override stored in the torrent database.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SeedingPolicy {
+    /// Stop once uploaded / downloaded bytes reaches this ratio
+    pub ratio_limit: Option<f64>,
+    /// Stop this many hours after the download completed
+    pub time_limit_hours: Option<u64>,
+    /// Stop when free space on the download disk drops below this many bytes
+    pub min_free_disk_bytes: Option<u64>,
+    pub action: SeedingAction,
+}
+
+impl SeedingPolicy {
+    pub fn is_unlimited(&self) -> bool {
+        self.ratio_limit.is_none()
+            && self.time_limit_hours.is_none()
+            && self.min_free_disk_bytes.is_none()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigStorage {
+    /// Maximum bytes used by downloads and their transmux outputs
+    pub quota_bytes: Option<u64>,
+    /// Remove files of titles marked watched in history
+    pub auto_cleanup_watched: bool,
+    /// Days to keep files after a title was marked watched
+    pub watched_grace_days: u32,
+}
+
+impl Default for RqbitDesktopConfigStorage {
+    fn default() -> Self {
+        Self {
+            quota_bytes: None,
+            auto_cleanup_watched: false,
+            watched_grace_days: 7,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigQueue {
+    pub enabled: bool,
+    /// Maximum downloading torrents, 0 for unlimited
+    pub max_active_downloads: usize,
+    /// Maximum seeding torrents, 0 for unlimited
+    pub max_active_seeds: usize,
+}
+
+impl Default for RqbitDesktopConfigQueue {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_active_downloads: 3,
+            max_active_seeds: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyKind {
+    #[default]
+    None,
+    Socks5,
+    Http,
+}
+
+/// Proxy for peer, tracker and web traffic. Supersedes `connections.socks_proxy`.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigProxy {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    /// Empty for no authentication
+    pub username: String,
+    pub password: String,
+    /// Resolve hostnames through the proxy (SOCKS5 only)
+    pub remote_dns: bool,
+    /// Route peer and tracker connections through the proxy. SOCKS5 only, must be off for HTTP.
+    pub peers: bool,
+    /// Route Nacho Server, TMDB and indexer requests through the proxy
+    pub web_requests: bool,
+}
+
+impl Default for RqbitDesktopConfigProxy {
+    fn default() -> Self {
+        Self {
+            kind: ProxyKind::None,
+            host: String::new(),
+            port: 1080,
+            username: String::new(),
+            password: String::new(),
+            remote_dns: true,
+            peers: true,
+            web_requests: true,
+        }
+    }
+}
+
+impl RqbitDesktopConfigProxy {
+    fn url(&self, scheme: &str) -> Option<String> {
+        let mut url =
+            reqwest::Url::parse(&format!("{}://{}:{}", scheme, self.host, self.port)).ok()?;
+        if !self.username.is_empty() {
+            url.set_username(&self.username).ok()?;
+            url.set_password(Some(&self.password)).ok()?;
+        }
+        Some(url.to_string())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.kind == ProxyKind::None {
+            return Ok(());
+        }
+        if self.host.trim().is_empty() || self.port == 0 {
+            anyhow::bail!("proxy host and port are required.")
+        }
+        if self.url("socks5").is_none() {
+            anyhow::bail!("invalid proxy host {:?}", self.host)
+        }
+        if self.kind == ProxyKind::Http && self.peers {
+            // The session can only tunnel peer connections through SOCKS5
+            anyhow::bail!(
+                "HTTP proxies can't carry peer connections. Use SOCKS5 or turn off proxying peers."
+            )
+        }
+        Ok(())
+    }
+
+    /// Proxy URL for the torrent session (peers and trackers)
+    pub fn peer_proxy_url(&self) -> Option<String> {
+        match self.kind {
+            ProxyKind::Socks5 if self.peers => self.url("socks5"),
+            _ => None,
+        }
+    }
+
+    /// Proxy URL for the app's own HTTP requests
+    pub fn http_proxy_url(&self) -> Option<String> {
+        if !self.web_requests {
+            return None;
+        }
+        match self.kind {
+            ProxyKind::None => None,
+            ProxyKind::Socks5 if self.remote_dns => self.url("socks5h"),
+            ProxyKind::Socks5 => self.url("socks5"),
+            ProxyKind::Http => self.url("http"),
+        }
+    }
+}
+
+/// How search results are scored and filtered. Terms match whole words of the
+/// release title, case-insensitively, and may span several words ("web dl").
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct QualityProfile {
+    pub name: String,
+    /// Acceptable resolutions, best first. Empty allows any.
+    pub resolutions: Vec<Resolution>,
+    /// Results from these sources are rejected, whatever the title calls them
+    pub rejected_sources: Vec<Source>,
+    /// Each matching term adds to the score
+    pub preferred_terms: Vec<String>,
+    /// Results must match all of these
+    pub required_terms: Vec<String>,
+    /// Results matching any of these are rejected
+    pub rejected_terms: Vec<String>,
+    pub preferred_codecs: Vec<String>,
+    pub preferred_languages: Vec<String>,
+    pub prefer_hdr: bool,
+    /// Size bounds in MB per minute of runtime, only applied when the runtime is known
+    pub min_mb_per_minute: Option<f64>,
+    pub max_mb_per_minute: Option<f64>,
+}
+
+impl Default for QualityProfile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_owned(),
+            resolutions: vec![Resolution::P1080, Resolution::P2160, Resolution::P720],
+            rejected_sources: vec![Source::Cam, Source::Telesync],
+            preferred_terms: Vec::new(),
+            required_terms: Vec::new(),
+            rejected_terms: Vec::new(),
+            preferred_codecs: vec!["x265".to_owned()],
+            preferred_languages: Vec::new(),
+            prefer_hdr: false,
+            min_mb_per_minute: Some(5.0),
+            max_mb_per_minute: Some(400.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexerKind {
+    /// Prowlarr search API, across all its indexers
+    Prowlarr,
+    /// Jackett JSON API, across all its indexers
+    Jackett,
+    /// Any Torznab feed
+    #[default]
+    Torznab,
+}
+
+/// An indexer queried directly, without the Nacho Server
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct IndexerConfig {
+    pub name: String,
+    pub kind: IndexerKind,
+    /// Base URL for Prowlarr and Jackett, feed URL for Torznab
+    pub url: String,
+    pub api_key: String,
+    pub enabled: bool,
+    /// Restrict searches to these categories instead of the movie/TV defaults
+    pub categories: Vec<u32>,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: IndexerKind::default(),
+            url: String::new(),
+            api_key: String::new(),
+            enabled: true,
+            categories: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigSearch {
+    pub profiles: Vec<QualityProfile>,
+    /// Name of the profile used when none is given
+    pub default_profile: String,
+    /// Search through the Nacho Server's Prowlarr when it is set up
+    pub use_nacho_server: bool,
+    pub indexers: Vec<IndexerConfig>,
+    /// Fetch `.torrent` files of results without an info hash so duplicates can be merged.
+    /// Off by default, as indexers count every fetch as a grab.
+    pub resolve_info_hashes: bool,
+}
+
+impl Default for RqbitDesktopConfigSearch {
+    fn default() -> Self {
+        Self {
+            profiles: vec![QualityProfile::default()],
+            default_profile: "Default".to_owned(),
+            use_nacho_server: true,
+            indexers: Vec::new(),
+            resolve_info_hashes: false,
+        }
+    }
+}
+
+impl RqbitDesktopConfigSearch {
+    /// Profile by name, falling back to the default profile
+    pub fn profile(&self, name: Option<&str>) -> QualityProfile {
+        let name = name.unwrap_or(&self.default_profile);
+        self.profiles
+            .iter()
+            .find(|p| p.name == name)
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|p| p.name == self.default_profile)
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Peer blocklists in eMule `.dat`, PeerGuardian `.p2p` or CIDR format
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigBlocklist {
+    pub enabled: bool,
+    /// Local file paths or http(s) URLs
+    pub sources: Vec<String>,
+    #[serde_as(as = "serde_with::DurationSeconds")]
+    pub refresh_interval: Duration,
+}
+
+impl Default for RqbitDesktopConfigBlocklist {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sources: Vec::new(),
+            refresh_interval: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// Folders scanned for dropped `.torrent` and `.magnet` files
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigWatchFolders {
+    pub enabled: bool,
+    pub folders: Vec<PathBuf>,
+    /// Where added files are moved, defaults to "processed" inside the watch folder
+    pub processed_folder: Option<PathBuf>,
+    /// Where files that failed to add are moved, defaults to "failed" inside the watch folder
+    pub failed_folder: Option<PathBuf>,
+    #[serde_as(as = "serde_with::DurationSeconds")]
+    pub poll_interval: Duration,
+}
+
+impl Default for RqbitDesktopConfigWatchFolders {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folders: Vec::new(),
+            processed_folder: None,
+            failed_folder: None,
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Automatic download of newly aired episodes of shows on the watchlist
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigWatchlist {
+    pub auto_download: bool,
+    #[serde_as(as = "serde_with::DurationSeconds")]
+    pub check_interval: Duration,
+    /// Quality profile for automatic downloads, defaults to the search default
+    pub profile: Option<String>,
+    /// Don't download episodes that are already in the watch history
+    pub skip_watched: bool,
+}
+
+impl Default for RqbitDesktopConfigWatchlist {
+    fn default() -> Self {
+        Self {
+            auto_download: false,
+            check_interval: Duration::from_secs(6 * 60 * 60),
+            profile: None,
+            skip_watched: true,
+        }
+    }
+}
+
+/// A torrent RSS or Atom feed. Regexes match item titles case-insensitively.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RssFeedConfig {
+    pub name: String,
+    pub url: String,
+    pub enabled: bool,
+    /// Items must match this regex
+    pub include: Option<String>,
+    /// Items matching this regex are skipped
+    pub exclude: Option<String>,
+    /// Items must be accepted by this quality profile
+    pub profile: Option<String>,
+    /// Tag added torrents with this TMDB ID
+    pub tmdb_id: Option<u64>,
+    /// "movie" or "tv", for the TMDB tag
+    pub media_type: Option<String>,
+}
+
+impl Default for RssFeedConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            enabled: true,
+            include: None,
+            exclude: None,
+            profile: None,
+            tmdb_id: None,
+            media_type: None,
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigRss {
+    pub enabled: bool,
+    pub feeds: Vec<RssFeedConfig>,
+    #[serde_as(as = "serde_with::DurationSeconds")]
+    pub poll_interval: Duration,
+}
+
+impl Default for RqbitDesktopConfigRss {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            feeds: Vec::new(),
+            poll_interval: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+/// Rate limits applied on the given weekdays between `start` and `end` local time.
+/// A rule with `start` after `end` spans midnight; `start` and `end` must differ.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BandwidthRule {
+    /// Days the rule starts on, 0 = Monday ... 6 = Sunday. Empty means every day.
+    #[serde(default)]
+    pub weekdays: Vec<u8>,
+    /// "HH:MM"
+    pub start: String,
+    /// "HH:MM"
+    pub end: String,
+    pub limits: LimitsConfig,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RqbitDesktopConfigBandwidthSchedule {
+    pub enabled: bool,
+    /// First matching rule wins; outside all rules `ratelimits` applies
+    pub rules: Vec<BandwidthRule>,
+    /// Limits used by the manual "slow" override
+    pub slow_limits: LimitsConfig,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct RqbitDesktopConfig {
     pub default_download_location: PathBuf,
 
@@ -179,6 +674,39 @@ pub struct RqbitDesktopConfig {
 
     #[serde(default)]
     pub ratelimits: LimitsConfig,
+
+    #[serde(default)]
+    pub bandwidth_schedule: RqbitDesktopConfigBandwidthSchedule,
+
+    #[serde(default)]
+    pub post_process: RqbitDesktopConfigPostProcess,
+
+    #[serde(default)]
+    pub seeding: SeedingPolicy,
+
+    #[serde(default)]
+    pub storage: RqbitDesktopConfigStorage,
+
+    #[serde(default)]
+    pub queue: RqbitDesktopConfigQueue,
+
+    #[serde(default)]
+    pub watch_folders: RqbitDesktopConfigWatchFolders,
+
+    #[serde(default)]
+    pub blocklist: RqbitDesktopConfigBlocklist,
+
+    #[serde(default)]
+    pub proxy: RqbitDesktopConfigProxy,
+
+    #[serde(default)]
+    pub search: RqbitDesktopConfigSearch,
+
+    #[serde(default)]
+    pub watchlist: RqbitDesktopConfigWatchlist,
+
+    #[serde(default)]
+    pub rss: RqbitDesktopConfigRss,
 }
 
 impl Default for RqbitDesktopConfig {
@@ -200,6 +728,17 @@ impl Default for RqbitDesktopConfig {
             persistence: Default::default(),
             http_api: Default::default(),
             ratelimits: Default::default(),
+            bandwidth_schedule: Default::default(),
+            post_process: Default::default(),
+            seeding: Default::default(),
+            storage: Default::default(),
+            queue: Default::default(),
+            watch_folders: Default::default(),
+            blocklist: Default::default(),
+            proxy: Default::default(),
+            search: Default::default(),
+            watchlist: Default::default(),
+            rss: Default::default(),
             #[cfg(feature = "disable-upload")]
             disable_upload: false,
         }
@@ -218,6 +757,124 @@ impl RqbitDesktopConfig {
                 )
             }
         }
+        for rule in &self.bandwidth_schedule.rules {
+            for time in [&rule.start, &rule.end] {
+                if parse_hh_mm(time).is_none() {
+                    anyhow::bail!("invalid bandwidth schedule time {:?}, expected HH:MM", time)
+                }
+            }
+            if parse_hh_mm(&rule.start) == parse_hh_mm(&rule.end) {
+                anyhow::bail!(
+                    "bandwidth schedule rule starts and ends at {}, so it never applies",
+                    rule.start
+                )
+            }
+            if rule.weekdays.iter().any(|d| *d > 6) {
+                anyhow::bail!("bandwidth schedule weekdays must be between 0 (Monday) and 6")
+            }
+        }
+        self.proxy.validate()?;
+        for profile in &self.search.profiles {
+            if let (Some(min), Some(max)) = (profile.min_mb_per_minute, profile.max_mb_per_minute)
+                && min > max
+            {
+                anyhow::bail!(
+                    "quality profile {:?} has a minimum size above its maximum",
+                    profile.name
+                )
+            }
+        }
+        for indexer in &self.search.indexers {
+            if reqwest::Url::parse(&indexer.url).is_err() {
+                anyhow::bail!("indexer {:?} has an invalid URL", indexer.name)
+            }
+        }
+        if self.watchlist.check_interval < Duration::from_secs(15 * 60) {
+            anyhow::bail!("the watchlist check interval must be at least 15 minutes.")
+        }
+        if self.rss.poll_interval < Duration::from_secs(5 * 60) {
+            anyhow::bail!("the RSS poll interval must be at least 5 minutes.")
+        }
+        for feed in &self.rss.feeds {
+            if reqwest::Url::parse(&feed.url).is_err() {
+                anyhow::bail!("RSS feed {:?} has an invalid URL", feed.name)
+            }
+            for pattern in [&feed.include, &feed.exclude].into_iter().flatten() {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    anyhow::bail!("RSS feed {:?} has an invalid filter: {}", feed.name, e)
+                }
+            }
+            if feed.tmdb_id.is_some() && !matches!(feed.media_type.as_deref(), Some("movie" | "tv"))
+            {
+                anyhow::bail!(
+                    "RSS feed {:?} needs a media type of \"movie\" or \"tv\" for its TMDB ID",
+                    feed.name
+                )
+            }
+        }
+        if self.connections.kill_switch && self.connections.bind_interface.trim().is_empty() {
+            anyhow::bail!("the kill switch needs a network interface to bind to.")
+        }
         Ok(())
     }
 }
+
+/// Parse "HH:MM" into minutes since midnight
+pub fn parse_hh_mm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hh_mm() {
+        assert_eq!(parse_hh_mm("00:00"), Some(0));
+        assert_eq!(parse_hh_mm(" 9:05 "), Some(9 * 60 + 5));
+        assert_eq!(parse_hh_mm("23:59"), Some(23 * 60 + 59));
+        assert_eq!(parse_hh_mm("24:00"), None);
+        assert_eq!(parse_hh_mm("12:60"), None);
+        assert_eq!(parse_hh_mm("1200"), None);
+        assert_eq!(parse_hh_mm("ab:cd"), None);
+    }
+
+    #[test]
+    fn test_validate_bandwidth_rules() {
+        let mut config = RqbitDesktopConfig::default();
+        config.bandwidth_schedule.rules.push(BandwidthRule {
+            weekdays: vec![],
+            start: "22:00".to_owned(),
+            end: "06:00".to_owned(),
+            limits: LimitsConfig::default(),
+        });
+        assert!(config.validate().is_ok());
+
+        config.bandwidth_schedule.rules[0].end = "22:00".to_owned();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_proxy() {
+        let mut proxy = RqbitDesktopConfigProxy {
+            kind: ProxyKind::Socks5,
+            host: "127.0.0.1".to_owned(),
+            ..Default::default()
+        };
+        assert!(proxy.validate().is_ok());
+        assert!(proxy.peer_proxy_url().is_some());
+
+        proxy.kind = ProxyKind::Http;
+        assert!(proxy.validate().is_err());
+
+        proxy.peers = false;
+        assert!(proxy.validate().is_ok());
+        assert_eq!(proxy.peer_proxy_url(), None);
+        assert_eq!(
+            proxy.http_proxy_url().as_deref(),
+            Some("http://127.0.0.1:1080/")
+        );
+    }
+}
@@ -0,0 +1,425 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, bail};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tracing::{info, warn};
+
+use crate::error::CommandError;
+use crate::settings_manager::{self, AppSettings};
+
+/// Name of the Tauri event carrying [`DeepLinkEvent`] payloads to the frontend
+pub const DEEP_LINK_EVENT: &str = "deep-link-event";
+
+/// The OS may deliver the same link more than once, e.g. on startup and via the open handler
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// A validated incoming deep link
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLink {
+    /// `magnet:?xt=urn:btih:...`
+    Magnet { uri: String },
+    /// `nacho-time://movie/{tmdb}`
+    Movie { tmdb_id: u64 },
+    /// `nacho-time://show/{tmdb}` or `nacho-time://show/{tmdb}/{season}/{episode}`
+    Show {
+        tmdb_id: u64,
+        episode: Option<(u32, u32)>,
+    },
+    /// `nacho-time://auth?token=...&server=...`
+    LinkServer { token: String, server: String },
+}
+
+/// Result of handling a deep link, for the frontend to act on
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DeepLinkEvent {
+    /// A magnet link to add once the user confirms it
+    MagnetReceived {
+        uri: String,
+        name: Option<String>,
+    },
+    OpenMovie {
+        tmdb_id: u64,
+    },
+    OpenShow {
+        tmdb_id: u64,
+        season: Option<u32>,
+        episode: Option<u32>,
+    },
+    ServerLinking {
+        server: String,
+    },
+    ServerLinked {
+        server: String,
+        username: Option<String>,
+    },
+    Error {
+        url: String,
+        error: String,
+    },
+}
+
+/// A [`DeepLinkEvent`] with an id, so the frontend can drop one it already got both
+/// through [`DEEP_LINK_EVENT`] and [`take_pending_deep_link_events`]
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkMessage {
+    pub id: u64,
+    #[serde(flatten)]
+    pub event: DeepLinkEvent,
+}
+
+/// Parse and validate a deep link URL
+pub fn parse_deep_link(url: &str) -> anyhow::Result<DeepLink> {
+    let parsed = Url::parse(url.trim()).context("Invalid URL")?;
+    match parsed.scheme() {
+        "magnet" => parse_magnet(&parsed),
+        "nacho-time" => parse_nacho_time(&parsed),
+        other => bail!("Unsupported scheme {:?}", other),
+    }
+}
+
+fn parse_magnet(url: &Url) -> anyhow::Result<DeepLink> {
+    let valid = url.query_pairs().any(|(k, v)| {
+        if k != "xt" {
+            return false;
+        }
+        if let Some(hash) = v.strip_prefix("urn:btih:") {
+            (hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+                || (hash.len() == 32
+                    && hash
+                        .chars()
+                        .all(|c| matches!(c.to_ascii_uppercase(), 'A'..='Z' | '2'..='7')))
+        } else if let Some(hash) = v.strip_prefix("urn:btmh:") {
+            !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit())
+        } else {
+            false
+        }
+    });
+    if !valid {
+        bail!("Magnet link has no valid info hash");
+    }
+    Ok(DeepLink::Magnet {
+        uri: url.to_string(),
+    })
+}
+
+/// Display name (`dn`) of a magnet link
+fn magnet_name(uri: &str) -> Option<String> {
+    Url::parse(uri)
+        .ok()?
+        .query_pairs()
+        .find(|(k, _)| k == "dn")
+        .map(|(_, v)| v.trim().to_owned())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_nacho_time(url: &Url) -> anyhow::Result<DeepLink> {
+    let host = url.host_str().context("Missing link type")?;
+    let segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    let number = |s: &str, what: &str| -> anyhow::Result<u64> {
+        s.parse::<u64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .with_context(|| format!("Invalid {} {:?}", what, s))
+    };
+
+    match (host, segments.as_slice()) {
+        ("movie", [tmdb]) => Ok(DeepLink::Movie {
+            tmdb_id: number(tmdb, "TMDB id")?,
+        }),
+        ("show", [tmdb]) => Ok(DeepLink::Show {
+            tmdb_id: number(tmdb, "TMDB id")?,
+            episode: None,
+        }),
+        ("show", [tmdb, season, episode]) => {
+            let tmdb_id = number(tmdb, "TMDB id")?;
+            // Season 0 holds specials
+            let season: u32 = season
+                .parse()
+                .ok()
+                .with_context(|| format!("Invalid season {:?}", season))?;
+            let episode = number(episode, "episode")? as u32;
+            Ok(DeepLink::Show {
+                tmdb_id,
+                episode: Some((season, episode)),
+            })
+        }
+        ("auth", []) => {
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.trim().to_owned())
+                    .filter(|v| !v.is_empty())
+            };
+            let token = param("token").context("Missing token")?;
+            let server = param("server").context("Missing server URL")?;
+            let server_url = Url::parse(&server).context("Invalid server URL")?;
+            if !matches!(server_url.scheme(), "http" | "https") {
+                bail!("Server URL must be http or https");
+            }
+            Ok(DeepLink::LinkServer {
+                token,
+                server: server.trim_end_matches('/').to_owned(),
+            })
+        }
+        ("movie" | "show" | "auth", _) => bail!("Malformed {} link", host),
+        _ => bail!("Unknown link type {:?}", host),
+    }
+}
+
+/// Deep link state, managed by Tauri
+pub struct DeepLinkRouter {
+    /// Events produced before the frontend asked for them
+    pending: Mutex<Option<Vec<DeepLinkMessage>>>,
+    last: Mutex<Option<(String, Instant)>>,
+    next_id: AtomicU64,
+}
+
+impl DeepLinkRouter {
+    fn new() -> Self {
+        Self {
+            pending: Mutex::new(Some(Vec::new())),
+            last: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn is_duplicate(&self, url: &str) -> bool {
+        let mut last = self.last.lock();
+        let duplicate = last
+            .as_ref()
+            .is_some_and(|(u, at)| u == url && at.elapsed() < DUPLICATE_WINDOW);
+        *last = Some((url.to_owned(), Instant::now()));
+        duplicate
+    }
+}
+
+/// Route deep links received at startup and while running
+pub fn register(app: &AppHandle) {
+    app.manage(DeepLinkRouter::new());
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            let app = handle.clone();
+            tauri::async_runtime::spawn(async move { handle_url(&app, url.as_str()).await });
+        }
+    });
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            for url in urls {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { handle_url(&app, url.as_str()).await });
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!(error=?e, "Failed to get startup deep links"),
+    }
+}
+
+/// Events from links that arrived before the frontend was listening. After the
+/// first call events are only delivered through [`DEEP_LINK_EVENT`]. Call this after
+/// starting to listen; an event sent in between comes through both, with the same id.
+#[tauri::command]
+pub fn take_pending_deep_link_events(
+    router: tauri::State<'_, DeepLinkRouter>,
+) -> Vec<DeepLinkMessage> {
+    router.pending.lock().take().unwrap_or_default()
+}
+
+/// Handle a deep link as if the OS had opened it
+#[tauri::command]
+pub async fn open_deep_link(app: AppHandle, url: String) -> Result<(), CommandError> {
+    parse_deep_link(&url).map_err(|e| CommandError::InvalidInput(format!("{:#}", e)))?;
+    handle_url(&app, &url).await;
+    Ok(())
+}
+
+async fn handle_url(app: &AppHandle, url: &str) {
+    if app.state::<DeepLinkRouter>().is_duplicate(url) {
+        info!("Ignoring duplicate deep link");
+        return;
+    }
+    // The frontend shows the window itself once loaded, only bring it forward if already up
+    if let Some(window) = app.get_webview_window("main")
+        && window.is_visible().unwrap_or(false)
+    {
+        window.set_focus().ok();
+    }
+
+    let link = match parse_deep_link(url) {
+        Ok(link) => link,
+        Err(e) => {
+            warn!("Rejected deep link: {:#}", e);
+            emit(app, error_event(url, e));
+            return;
+        }
+    };
+
+    let event = match link {
+        // Any web page can open a magnet link, so the user confirms it before it's added
+        DeepLink::Magnet { uri } => DeepLinkEvent::MagnetReceived {
+            name: magnet_name(&uri),
+            uri,
+        },
+        DeepLink::Movie { tmdb_id } => DeepLinkEvent::OpenMovie { tmdb_id },
+        DeepLink::Show { tmdb_id, episode } => DeepLinkEvent::OpenShow {
+            tmdb_id,
+            season: episode.map(|(s, _)| s),
+            episode: episode.map(|(_, e)| e),
+        },
+        DeepLink::LinkServer { token, server } => {
+            emit(
+                app,
+                DeepLinkEvent::ServerLinking {
+                    server: server.clone(),
+                },
+            );
+            match link_server(app, &server, &token).await {
+                Ok(username) => DeepLinkEvent::ServerLinked { server, username },
+                Err(e) => error_event(url, e),
+            }
+        }
+    };
+    emit(app, event);
+}
+
+/// Verify the token against the server and save both on success
+async fn link_server(app: &AppHandle, server: &str, token: &str) -> anyhow::Result<Option<String>> {
+    let user = settings_manager::fetch_nacho_user(app, server, token).await?;
+    let username = Some(user.username);
+
+    settings_manager::save_settings(
+        app.clone(),
+        AppSettings {
+            nacho_server_url: Some(server.to_owned()),
+            nacho_auth_token: Some(token.to_owned()),
+        },
+    )
+    .map_err(anyhow::Error::msg)?;
+    info!("Linked Nacho server {}", server);
+    Ok(username)
+}
+
+fn error_event(url: &str, error: anyhow::Error) -> DeepLinkEvent {
+    // Auth links carry a token, keep it out of logs and the UI
+    let url = match Url::parse(url) {
+        Ok(mut parsed) if parsed.query().is_some() && parsed.scheme() == "nacho-time" => {
+            parsed.set_query(None);
+            parsed.to_string()
+        }
+        _ => url.to_owned(),
+    };
+    DeepLinkEvent::Error {
+        url,
+        error: format!("{:#}", error),
+    }
+}
+
+fn emit(app: &AppHandle, event: DeepLinkEvent) {
+    let router = app.state::<DeepLinkRouter>();
+    let message = DeepLinkMessage {
+        id: router.next_id.fetch_add(1, Ordering::Relaxed),
+        event,
+    };
+    if let Some(pending) = router.pending.lock().as_mut() {
+        pending.push(message.clone());
+    }
+    if let Err(e) = app.emit(DEEP_LINK_EVENT, &message) {
+        warn!(error=?e, "Failed to emit deep link event");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_magnet() {
+        let uri = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056&dn=Test";
+        assert_eq!(
+            parse_deep_link(uri).unwrap(),
+            DeepLink::Magnet {
+                uri: uri.to_owned()
+            }
+        );
+        assert!(parse_deep_link("magnet:?xt=urn:btih:MFRGGZDFMZTWQ2LKNNWG23TPOBYXE43U").is_ok());
+    }
+
+    #[test]
+    fn reads_magnet_name() {
+        assert_eq!(
+            magnet_name(
+                "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056&dn=Big+Buck%20Bunny"
+            )
+            .as_deref(),
+            Some("Big Buck Bunny")
+        );
+        assert_eq!(
+            magnet_name("magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_bad_magnet() {
+        assert!(parse_deep_link("magnet:?dn=NoHash").is_err());
+        assert!(parse_deep_link("magnet:?xt=urn:btih:xyz").is_err());
+    }
+
+    #[test]
+    fn parses_movie_and_show() {
+        assert_eq!(
+            parse_deep_link("nacho-time://movie/603").unwrap(),
+            DeepLink::Movie { tmdb_id: 603 }
+        );
+        assert_eq!(
+            parse_deep_link("nacho-time://show/1399/").unwrap(),
+            DeepLink::Show {
+                tmdb_id: 1399,
+                episode: None
+            }
+        );
+        assert_eq!(
+            parse_deep_link("nacho-time://show/1399/0/3").unwrap(),
+            DeepLink::Show {
+                tmdb_id: 1399,
+                episode: Some((0, 3))
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_titles() {
+        assert!(parse_deep_link("nacho-time://movie/abc").is_err());
+        assert!(parse_deep_link("nacho-time://movie/0").is_err());
+        assert!(parse_deep_link("nacho-time://show/1399/1").is_err());
+        assert!(parse_deep_link("nacho-time://show/1399/1/0").is_err());
+        assert!(parse_deep_link("nacho-time://unknown/1").is_err());
+        assert!(parse_deep_link("https://example.com/movie/1").is_err());
+    }
+
+    #[test]
+    fn parses_server_link() {
+        assert_eq!(
+            parse_deep_link("nacho-time://auth?token=abc&server=https%3A%2F%2Fnacho.example%2F")
+                .unwrap(),
+            DeepLink::LinkServer {
+                token: "abc".to_owned(),
+                server: "https://nacho.example".to_owned()
+            }
+        );
+        assert!(parse_deep_link("nacho-time://auth?server=https://nacho.example").is_err());
+        assert!(parse_deep_link("nacho-time://auth?token=abc&server=ftp://nacho.example").is_err());
+    }
+}
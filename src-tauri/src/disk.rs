@@ -0,0 +1,76 @@
+use std::path::Path;
+
+/// Free space in bytes available to the current user on the filesystem holding `path`.
+/// If `path` doesn't exist yet, its closest existing ancestor is used.
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    available_space_impl(closest_existing(path)?)
+}
+
+/// Whether two paths are on the same filesystem, so moving between them is a rename.
+/// Paths that don't exist yet are checked through their closest existing ancestor.
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    match (closest_existing(a), closest_existing(b)) {
+        (Ok(a), Ok(b)) => same_filesystem_impl(a, b),
+        _ => false,
+    }
+}
+
+fn closest_existing(path: &Path) -> std::io::Result<&Path> {
+    path.ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no existing ancestor"))
+}
+
+#[cfg(unix)]
+fn same_filesystem_impl(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+#[cfg(windows)]
+fn same_filesystem_impl(a: &Path, b: &Path) -> bool {
+    // Compare drive letters or UNC shares
+    a.components().next() == b.components().next()
+}
+
+#[cfg(unix)]
+fn available_space_impl(path: &Path) -> std::io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stat is a valid out pointer
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn available_space_impl(path: &Path) -> std::io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available: u64 = 0;
+    // SAFETY: wide is NUL-terminated and the out pointer is valid for the call
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(available)
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use reqwest::StatusCode;
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+
+/// Error returned by commands, so the frontend can tell failures apart.
+/// Serialized as `{ code, message, status, retryable }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// A required setting, like the Nacho Server URL, is missing
+    NotConfigured(String),
+    /// The server rejected the credentials
+    Unauthorized(String),
+    NotFound(String),
+    RateLimited(String),
+    /// The server couldn't be reached or didn't answer in time
+    Network(String),
+    /// The server answered with an error status
+    Server {
+        status: u16,
+        message: String,
+    },
+    /// The server's answer couldn't be understood
+    InvalidResponse(String),
+    InvalidInput(String),
+    /// The download's contents look unsafe, it is only added when confirmed
+    SuspiciousDownload(String),
+    /// A local failure, e.g. reading the settings file
+    Internal(String),
+}
+
+impl CommandError {
+    /// Machine-readable kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotConfigured(_) => "not_configured",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::NotFound(_) => "not_found",
+            Self::RateLimited(_) => "rate_limited",
+            Self::Network(_) => "network",
+            Self::Server { .. } => "server",
+            Self::InvalidResponse(_) => "invalid_response",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::SuspiciousDownload(_) => "suspicious_download",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// HTTP status best describing the error; the upstream status for server errors
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::NotConfigured(_) => 412,
+            Self::Unauthorized(_) => 401,
+            Self::NotFound(_) => 404,
+            Self::RateLimited(_) => 429,
+            Self::Network(_) => 503,
+            Self::Server { status, .. } => *status,
+            Self::InvalidResponse(_) => 502,
+            Self::InvalidInput(_) => 400,
+            Self::SuspiciousDownload(_) => 422,
+            Self::Internal(_) => 500,
+        }
+    }
+
+    /// Whether trying again later may succeed without changing anything
+    pub fn retryable(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::RateLimited(_) => true,
+            Self::Server { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotConfigured(m)
+            | Self::Unauthorized(m)
+            | Self::NotFound(m)
+            | Self::RateLimited(m)
+            | Self::Network(m)
+            | Self::Server { message: m, .. }
+            | Self::InvalidResponse(m)
+            | Self::InvalidInput(m)
+            | Self::SuspiciousDownload(m)
+            | Self::Internal(m) => m,
+        }
+    }
+
+    /// The same kind of error with another message
+    pub fn with_message(self, message: String) -> Self {
+        match self {
+            Self::NotConfigured(_) => Self::NotConfigured(message),
+            Self::Unauthorized(_) => Self::Unauthorized(message),
+            Self::NotFound(_) => Self::NotFound(message),
+            Self::RateLimited(_) => Self::RateLimited(message),
+            Self::Network(_) => Self::Network(message),
+            Self::Server { status, .. } => Self::Server { status, message },
+            Self::InvalidResponse(_) => Self::InvalidResponse(message),
+            Self::InvalidInput(_) => Self::InvalidInput(message),
+            Self::SuspiciousDownload(_) => Self::SuspiciousDownload(message),
+            Self::Internal(_) => Self::Internal(message),
+        }
+    }
+
+    /// The same kind of error with its message prefixed with `context`
+    pub fn context(self, context: &str) -> Self {
+        let message = format!("{}: {}", context, self.message());
+        self.with_message(message)
+    }
+
+    /// Error for an unsuccessful HTTP response
+    pub fn from_status(status: StatusCode, message: String) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Unauthorized(message),
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited(message),
+            status => Self::Server {
+                status: status.as_u16(),
+                message,
+            },
+        }
+    }
+
+    /// Error for a failed request, prefixed with `context`
+    pub fn request(context: &str, e: reqwest::Error) -> Self {
+        Self::from_reqwest(&e, format!("{}: {}", context, e))
+    }
+
+    fn from_reqwest(e: &reqwest::Error, message: String) -> Self {
+        match e.status() {
+            Some(status) => Self::from_status(status, message),
+            None if e.is_decode() => Self::InvalidResponse(message),
+            None if e.is_builder() => Self::Internal(message),
+            None => Self::Network(message),
+        }
+    }
+
+    fn from_api_error(e: &librqbit::ApiError, message: String) -> Self {
+        match e.status() {
+            StatusCode::FAILED_DEPENDENCY => Self::NotConfigured(message),
+            StatusCode::NOT_FOUND => Self::NotFound(message),
+            StatusCode::BAD_REQUEST => Self::InvalidInput(message),
+            // The kill switch holds torrents while the network interface is down
+            StatusCode::SERVICE_UNAVAILABLE => Self::Network(message),
+            _ => Self::Internal(message),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("CommandError", 4)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("status", &self.status())?;
+        s.serialize_field("retryable", &self.retryable())?;
+        s.end()
+    }
+}
+
+/// Errors from the torrent session, e.g. "not configured" before the first setup
+impl From<librqbit::ApiError> for CommandError {
+    fn from(e: librqbit::ApiError) -> Self {
+        Self::from_api_error(&e, e.to_string())
+    }
+}
+
+/// Errors from helpers using anyhow keep the kind of a wrapped [`CommandError`] or
+/// request failure, with the full context as the message
+impl From<anyhow::Error> for CommandError {
+    fn from(e: anyhow::Error) -> Self {
+        let message = format!("{:#}", e);
+        if let Some(inner) = e.downcast_ref::<CommandError>() {
+            return inner.clone().with_message(message);
+        }
+        if let Some(api) = e.downcast_ref::<librqbit::ApiError>() {
+            return Self::from_api_error(api, message);
+        }
+        match e.chain().find_map(|c| c.downcast_ref::<reqwest::Error>()) {
+            Some(request) => Self::from_reqwest(request, message),
+            None => Self::Internal(message),
+        }
+    }
+}
+
+/// Errors from helpers that still report plain strings
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::Internal(message)
+    }
+}
+
+/// For callers that still report plain strings
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize() {
+        let e = CommandError::from_status(StatusCode::BAD_GATEWAY, "Upstream failed".to_owned());
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({
+                "code": "server",
+                "message": "Upstream failed",
+                "status": 502,
+                "retryable": true,
+            })
+        );
+        let e = CommandError::from_status(StatusCode::FORBIDDEN, "Bad token".to_owned());
+        assert_eq!(e.code(), "unauthorized");
+        assert!(!e.retryable());
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        let e: CommandError = anyhow::Error::new(CommandError::NotConfigured("No URL".to_owned()))
+            .context("Failed to fetch feed")
+            .into();
+        assert_eq!(
+            e,
+            CommandError::NotConfigured("Failed to fetch feed: No URL".to_owned())
+        );
+        let e: CommandError = anyhow::anyhow!("Disk full").into();
+        assert_eq!(e.code(), "internal");
+    }
+}
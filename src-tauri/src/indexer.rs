@@ -0,0 +1,926 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use anyhow::{Context, bail};
+use parking_lot::Mutex;
+use quick_xml::events::{BytesStart, Event};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+use crate::config::{IndexerConfig, IndexerKind};
+use crate::error::CommandError;
+use crate::settings_manager;
+use crate::torrent_search::{TorrentResult, magnet_info_hash, normalize_info_hash};
+use crate::torrent_server::State;
+
+/// Newznab category for movies, including all its subcategories
+pub const MOVIE_CATEGORY: u32 = 2000;
+/// Newznab category for TV, including all its subcategories
+pub const TV_CATEGORY: u32 = 5000;
+
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SearchKind {
+    #[default]
+    Search,
+    Movie,
+    Tv,
+}
+
+/// What to search for. Indexers use whichever fields they support.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SearchRequest {
+    pub kind: SearchKind,
+    pub query: Option<String>,
+    /// With the "tt" prefix
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u64>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// Newznab categories. Empty uses the default for the kind.
+    pub categories: Vec<u32>,
+}
+
+impl SearchRequest {
+    fn categories(&self) -> Vec<u32> {
+        if !self.categories.is_empty() {
+            return self.categories.clone();
+        }
+        match self.kind {
+            SearchKind::Search => Vec::new(),
+            SearchKind::Movie => vec![MOVIE_CATEGORY],
+            SearchKind::Tv => vec![TV_CATEGORY],
+        }
+    }
+}
+
+pub type IndexerFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<TorrentResult>, CommandError>> + Send + 'a>>;
+
+/// A source of torrent search results
+pub trait Indexer: Send + Sync {
+    fn name(&self) -> &str;
+    fn search<'a>(&'a self, request: &'a SearchRequest) -> IndexerFuture<'a>;
+}
+
+/// Result format of the Prowlarr search API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProwlarrResponse {
+    title: String,
+    size: Option<u64>,
+    seeders: Option<u32>,
+    peers: Option<u32>,
+    download_url: Option<String>,
+    magnet_url: Option<String>,
+    indexer: Option<String>,
+    publish_date: Option<String>,
+    info_hash: Option<String>,
+}
+
+/// Result format of the Jackett JSON API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JackettResponse {
+    results: Vec<JackettResult>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JackettResult {
+    title: String,
+    size: Option<u64>,
+    seeders: Option<u32>,
+    peers: Option<u32>,
+    link: Option<String>,
+    magnet_uri: Option<String>,
+    tracker: Option<String>,
+    publish_date: Option<String>,
+    info_hash: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn torrent_result(
+    title: String,
+    size: Option<u64>,
+    seeders: Option<u32>,
+    peers: Option<u32>,
+    download_url: Option<String>,
+    magnet_url: Option<String>,
+    indexer: String,
+    publish_date: Option<String>,
+    info_hash: Option<String>,
+) -> Option<TorrentResult> {
+    // Need either a download URL or a magnet link
+    let download_url = download_url
+        .filter(|u| !u.is_empty())
+        .or_else(|| magnet_url.clone())?;
+    let info_hash = info_hash
+        .as_deref()
+        .and_then(normalize_info_hash)
+        .or_else(|| magnet_url.as_deref().and_then(magnet_info_hash))
+        .or_else(|| magnet_info_hash(&download_url));
+    Some(TorrentResult {
+        title,
+        size: size.unwrap_or(0),
+        seeders: seeders.unwrap_or(0),
+        peers: peers.unwrap_or(0),
+        download_url,
+        magnet_url,
+        indexers: vec![indexer.clone()],
+        indexer,
+        publish_date,
+        info_hash,
+    })
+}
+
+/// Query parameters for the Prowlarr search API. IDs are passed as query tokens.
+fn prowlarr_params(request: &SearchRequest) -> Vec<(&'static str, String)> {
+    let mut query = request.query.clone().unwrap_or_default();
+    if let Some(imdb_id) = &request.imdb_id
+        && request.tvdb_id.is_none()
+    {
+        query.push_str(&format!("{{ImdbId:{}}}", imdb_id));
+    }
+    if let Some(tvdb_id) = request.tvdb_id {
+        query.push_str(&format!("{{TvdbId:{}}}", tvdb_id));
+    }
+    if request.kind == SearchKind::Tv {
+        if let Some(season) = request.season {
+            query.push_str(&format!("{{Season:{:02}}}", season));
+        }
+        if let Some(episode) = request.episode {
+            query.push_str(&format!("{{Episode:{:02}}}", episode));
+        }
+    }
+
+    let kind = match request.kind {
+        SearchKind::Search => "search",
+        SearchKind::Movie => "movie",
+        SearchKind::Tv => "tvsearch",
+    };
+    let mut params = vec![("query", query), ("type", kind.to_string())];
+    params.extend(
+        request
+            .categories()
+            .into_iter()
+            .map(|c| ("categories", c.to_string())),
+    );
+    params
+}
+
+async fn prowlarr_search(
+    request: reqwest::RequestBuilder,
+    search: &SearchRequest,
+    fallback_indexer: &str,
+) -> Result<Vec<TorrentResult>, CommandError> {
+    let response = request
+        .query(&prowlarr_params(search))
+        .timeout(SEARCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| CommandError::request("Failed to connect", e))?;
+    check_status(&response)?;
+    let results: Vec<ProwlarrResponse> = response
+        .json()
+        .await
+        .map_err(|e| CommandError::request("Failed to parse response", e))?;
+    Ok(results
+        .into_iter()
+        .filter_map(|r| {
+            torrent_result(
+                r.title,
+                r.size,
+                r.seeders,
+                r.peers,
+                r.download_url,
+                r.magnet_url,
+                r.indexer.unwrap_or_else(|| fallback_indexer.to_owned()),
+                r.publish_date,
+                r.info_hash,
+            )
+        })
+        .collect())
+}
+
+/// Prowlarr behind a Nacho Server
+pub struct NachoProxyIndexer {
+    pub server_url: String,
+    pub auth_token: String,
+    pub client: reqwest::Client,
+}
+
+impl Indexer for NachoProxyIndexer {
+    fn name(&self) -> &str {
+        "Nacho Server"
+    }
+
+    fn search<'a>(&'a self, request: &'a SearchRequest) -> IndexerFuture<'a> {
+        Box::pin(async move {
+            let url = format!(
+                "{}/api/prowlarr/api/v1/search",
+                self.server_url.trim_end_matches('/')
+            );
+            prowlarr_search(
+                self.client
+                    .get(url)
+                    .header("X-Nacho-Auth", &self.auth_token),
+                request,
+                "Unknown",
+            )
+            .await
+        })
+    }
+}
+
+/// A Prowlarr instance queried directly
+pub struct ProwlarrIndexer {
+    pub config: IndexerConfig,
+    pub client: reqwest::Client,
+}
+
+impl Indexer for ProwlarrIndexer {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn search<'a>(&'a self, request: &'a SearchRequest) -> IndexerFuture<'a> {
+        Box::pin(async move {
+            let url = format!("{}/api/v1/search", self.config.url.trim_end_matches('/'));
+            let mut search = request.clone();
+            search.categories = with_configured_categories(request, &self.config);
+            prowlarr_search(
+                self.client
+                    .get(url)
+                    .header("X-Api-Key", &self.config.api_key),
+                &search,
+                &self.config.name,
+            )
+            .await
+        })
+    }
+}
+
+/// A Jackett instance queried through its JSON API, across all its indexers
+pub struct JackettIndexer {
+    pub config: IndexerConfig,
+    pub client: reqwest::Client,
+}
+
+impl Indexer for JackettIndexer {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn search<'a>(&'a self, request: &'a SearchRequest) -> IndexerFuture<'a> {
+        Box::pin(async move {
+            // The JSON API only takes text, fall back to the IMDB ID which most trackers index
+            let Some(query) = request.query.clone().or_else(|| request.imdb_id.clone()) else {
+                return Ok(Vec::new());
+            };
+            let mut params = vec![("apikey", self.config.api_key.clone()), ("Query", query)];
+            params.extend(
+                with_configured_categories(request, &self.config)
+                    .into_iter()
+                    .map(|c| ("Category[]", c.to_string())),
+            );
+
+            let url = format!(
+                "{}/api/v2.0/indexers/all/results",
+                self.config.url.trim_end_matches('/')
+            );
+            let response = self
+                .client
+                .get(url)
+                .query(&params)
+                .timeout(SEARCH_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| CommandError::request("Failed to connect", e))?;
+            check_status(&response)?;
+            let response: JackettResponse = response
+                .json()
+                .await
+                .map_err(|e| CommandError::request("Failed to parse response", e))?;
+            Ok(response
+                .results
+                .into_iter()
+                .filter_map(|r| {
+                    torrent_result(
+                        r.title,
+                        r.size,
+                        r.seeders,
+                        r.peers,
+                        r.link,
+                        r.magnet_uri,
+                        r.tracker.unwrap_or_else(|| self.config.name.clone()),
+                        r.publish_date,
+                        r.info_hash,
+                    )
+                })
+                .collect())
+        })
+    }
+}
+
+/// Search modes and categories an indexer supports, from `t=caps`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TorznabCaps {
+    pub search: bool,
+    /// Supported parameters, None if the mode isn't available
+    pub tv_search: Option<Vec<String>>,
+    pub movie_search: Option<Vec<String>>,
+    pub categories: Vec<TorznabCategory>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TorznabCategory {
+    pub id: u32,
+    pub name: String,
+}
+
+impl TorznabCaps {
+    fn supports_category(&self, category: u32) -> bool {
+        self.categories.is_empty()
+            || self
+                .categories
+                .iter()
+                .any(|c| c.id == category || c.id == category / 1000 * 1000)
+    }
+}
+
+/// Caps per feed URL and API key. Kept on [`State`] and cleared when indexers are reconfigured.
+pub type CapsCache = Arc<Mutex<HashMap<(String, String), TorznabCaps>>>;
+
+/// A generic Torznab feed, e.g. a single Jackett or Prowlarr indexer
+pub struct TorznabIndexer {
+    pub config: IndexerConfig,
+    pub client: reqwest::Client,
+    pub caps_cache: CapsCache,
+}
+
+impl TorznabIndexer {
+    /// The feed's API endpoint. Feed URLs are often copied without the trailing "api".
+    fn api_url(&self) -> String {
+        let url = self.config.url.trim_end_matches('/');
+        if url.ends_with("/api") {
+            url.to_owned()
+        } else {
+            format!("{}/api", url)
+        }
+    }
+
+    async fn get(&self, params: &[(&str, String)]) -> Result<String, CommandError> {
+        let mut request = self.client.get(self.api_url()).query(params);
+        if !self.config.api_key.is_empty() {
+            request = request.query(&[("apikey", &self.config.api_key)]);
+        }
+        let response = request
+            .timeout(SEARCH_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| CommandError::request("Failed to connect", e))?;
+        check_status(&response)?;
+        response
+            .text()
+            .await
+            .map_err(|e| CommandError::request("Failed to read response", e))
+    }
+
+    pub async fn caps(&self) -> Result<TorznabCaps, CommandError> {
+        let key = (self.api_url(), self.config.api_key.clone());
+        if let Some(caps) = self.caps_cache.lock().get(&key) {
+            return Ok(caps.clone());
+        }
+        let caps = parse_torznab_caps(&self.get(&[("t", "caps".to_owned())]).await?)
+            .map_err(|e| CommandError::InvalidResponse(format!("{:#}", e)))?;
+        self.caps_cache.lock().insert(key, caps.clone());
+        Ok(caps)
+    }
+
+    async fn search_impl(
+        &self,
+        request: &SearchRequest,
+    ) -> Result<Vec<TorrentResult>, CommandError> {
+        let caps = self.caps().await?;
+        let supports = |params: &Option<Vec<String>>, param: &str| {
+            params
+                .as_ref()
+                .is_some_and(|p| p.iter().any(|s| s == param))
+        };
+
+        let mut params: Vec<(&str, String)> = Vec::new();
+        let ids = match request.kind {
+            SearchKind::Tv if caps.tv_search.is_some() => {
+                params.push(("t", "tvsearch".to_owned()));
+                Some(&caps.tv_search)
+            }
+            SearchKind::Movie if caps.movie_search.is_some() => {
+                params.push(("t", "movie".to_owned()));
+                Some(&caps.movie_search)
+            }
+            _ => {
+                params.push(("t", "search".to_owned()));
+                None
+            }
+        };
+        let mut has_criteria = false;
+        if let Some(query) = &request.query {
+            params.push(("q", query.clone()));
+            has_criteria = true;
+        }
+        if let Some(ids) = ids {
+            if let Some(imdb_id) = &request.imdb_id
+                && supports(ids, "imdbid")
+            {
+                params.push(("imdbid", imdb_id.trim_start_matches("tt").to_owned()));
+                has_criteria = true;
+            }
+            if let Some(tvdb_id) = request.tvdb_id
+                && supports(ids, "tvdbid")
+            {
+                params.push(("tvdbid", tvdb_id.to_string()));
+                has_criteria = true;
+            }
+            if let Some(season) = request.season
+                && supports(ids, "season")
+            {
+                params.push(("season", season.to_string()));
+            }
+            if let Some(episode) = request.episode
+                && supports(ids, "ep")
+            {
+                params.push(("ep", episode.to_string()));
+            }
+        }
+        if !has_criteria {
+            // Nothing this feed can search by
+            return Ok(Vec::new());
+        }
+
+        let categories: Vec<String> = with_configured_categories(request, &self.config)
+            .into_iter()
+            .filter(|c| caps.supports_category(*c))
+            .map(|c| c.to_string())
+            .collect();
+        if !categories.is_empty() {
+            params.push(("cat", categories.join(",")));
+        }
+        params.push(("extended", "1".to_owned()));
+
+        let xml = self.get(&params).await?;
+        parse_torznab_results(&xml, &self.config.name)
+            .map_err(|e| CommandError::InvalidResponse(format!("{:#}", e)))
+    }
+}
+
+impl Indexer for TorznabIndexer {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn search<'a>(&'a self, request: &'a SearchRequest) -> IndexerFuture<'a> {
+        Box::pin(self.search_impl(request))
+    }
+}
+
+/// Error for an unsuccessful response, keeping its status
+fn check_status(response: &reqwest::Response) -> Result<(), CommandError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(CommandError::from_status(
+            status,
+            format!("Returned error: {}", status),
+        ))
+    }
+}
+
+/// Categories configured on the indexer take precedence over the request's defaults
+fn with_configured_categories(request: &SearchRequest, config: &IndexerConfig) -> Vec<u32> {
+    if config.categories.is_empty() {
+        request.categories()
+    } else {
+        config.categories.clone()
+    }
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+fn parse_torznab_caps(xml: &str) -> anyhow::Result<TorznabCaps> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut caps = TorznabCaps::default();
+    loop {
+        match reader.read_event().context("Invalid caps XML")? {
+            Event::Start(e) | Event::Empty(e) => {
+                let available = attr(&e, b"available").as_deref() == Some("yes");
+                let params = || {
+                    attr(&e, b"supportedParams")
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(|p| p.trim().to_owned())
+                        .filter(|p| !p.is_empty())
+                        .collect::<Vec<_>>()
+                };
+                match e.local_name().as_ref() {
+                    b"error" => bail!(
+                        "{}",
+                        attr(&e, b"description").unwrap_or_else(|| "Indexer error".to_owned())
+                    ),
+                    b"search" => caps.search = available,
+                    b"tv-search" if available => caps.tv_search = Some(params()),
+                    b"movie-search" if available => caps.movie_search = Some(params()),
+                    b"category" | b"subcat" => {
+                        if let (Some(id), Some(name)) = (attr(&e, b"id"), attr(&e, b"name"))
+                            && let Ok(id) = id.parse()
+                        {
+                            caps.categories.push(TorznabCategory { id, name });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(caps)
+}
+
+/// Parse a Torznab RSS feed
+pub fn parse_torznab_results(xml: &str, indexer: &str) -> anyhow::Result<Vec<TorrentResult>> {
+    #[derive(Default)]
+    struct Item {
+        fields: HashMap<String, String>,
+        attrs: HashMap<String, String>,
+        enclosure_url: Option<String>,
+        enclosure_length: Option<u64>,
+    }
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut results = Vec::new();
+    let mut item: Option<Item> = None;
+    let mut current = String::new();
+
+    loop {
+        let event = reader.read_event().context("Invalid feed XML")?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                match (name.as_str(), item.as_mut()) {
+                    ("error", _) => bail!(
+                        "{}",
+                        attr(e, b"description").unwrap_or_else(|| "Indexer error".to_owned())
+                    ),
+                    ("item", _) => item = Some(Item::default()),
+                    ("enclosure", Some(item)) => {
+                        item.enclosure_url = attr(e, b"url");
+                        item.enclosure_length = attr(e, b"length").and_then(|l| l.parse().ok());
+                    }
+                    ("attr", Some(item)) => {
+                        if let (Some(name), Some(value)) = (attr(e, b"name"), attr(e, b"value")) {
+                            item.attrs.insert(name.to_lowercase(), value);
+                        }
+                    }
+                    _ => {}
+                }
+                if matches!(event, Event::Start(_)) {
+                    current = name;
+                }
+            }
+            Event::Text(text) => {
+                if let Some(item) = item.as_mut() {
+                    let text = text.unescape().context("Invalid feed XML")?;
+                    item.fields
+                        .entry(current.clone())
+                        .or_default()
+                        .push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(item) = item.as_mut() {
+                    item.fields
+                        .entry(current.clone())
+                        .or_default()
+                        .push_str(&String::from_utf8_lossy(data));
+                }
+            }
+            Event::End(e) => {
+                current.clear();
+                if e.local_name().as_ref() == b"item"
+                    && let Some(item) = item.take()
+                {
+                    let link = item.fields.get("link").cloned();
+                    let magnet_url = item
+                        .attrs
+                        .get("magneturl")
+                        .cloned()
+                        .or_else(|| link.clone().filter(|l| l.starts_with("magnet:")));
+                    let size = item
+                        .fields
+                        .get("size")
+                        .or_else(|| item.attrs.get("size"))
+                        .and_then(|s| s.trim().parse().ok())
+                        .or(item.enclosure_length);
+                    // Aggregating feeds name the actual indexer of each item
+                    let indexer = item
+                        .fields
+                        .get("jackettindexer")
+                        .or_else(|| item.fields.get("prowlarrindexer"))
+                        .cloned()
+                        .unwrap_or_else(|| indexer.to_owned());
+                    let result = torrent_result(
+                        item.fields.get("title").cloned().unwrap_or_default(),
+                        size,
+                        item.attrs.get("seeders").and_then(|s| s.parse().ok()),
+                        item.attrs.get("peers").and_then(|s| s.parse().ok()),
+                        item.enclosure_url.or(link),
+                        magnet_url,
+                        indexer,
+                        item.fields.get("pubDate").cloned(),
+                        item.attrs.get("infohash").cloned(),
+                    );
+                    results.extend(result);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(results)
+}
+
+fn build_indexer(
+    config: IndexerConfig,
+    client: reqwest::Client,
+    caps_cache: CapsCache,
+) -> Arc<dyn Indexer> {
+    match config.kind {
+        IndexerKind::Prowlarr => Arc::new(ProwlarrIndexer { config, client }),
+        IndexerKind::Jackett => Arc::new(JackettIndexer { config, client }),
+        IndexerKind::Torznab => Arc::new(TorznabIndexer {
+            config,
+            client,
+            caps_cache,
+        }),
+    }
+}
+
+/// The Nacho Server URL and token, if both are configured
+pub fn nacho_credentials(app: &AppHandle) -> Result<Option<(String, String)>, CommandError> {
+    let url = settings_manager::get_nacho_server_url(app.clone())?;
+    let token = settings_manager::get_nacho_auth_token(app.clone())?;
+    Ok(match (url, token) {
+        (Some(url), Some(token)) if !url.is_empty() && !token.is_empty() => Some((url, token)),
+        _ => None,
+    })
+}
+
+/// All indexers to search: the Nacho Server (if set up and enabled) and enabled direct indexers
+pub fn configured_indexers(app: &AppHandle) -> Result<Vec<Arc<dyn Indexer>>, CommandError> {
+    let state = app.state::<State>();
+    let client = crate::proxy::http_client(&state)?;
+    let search = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.search.clone())
+        .unwrap_or_default();
+
+    let mut indexers: Vec<Arc<dyn Indexer>> = Vec::new();
+    if search.use_nacho_server
+        && let Some((server_url, auth_token)) = nacho_credentials(app)?
+    {
+        indexers.push(Arc::new(NachoProxyIndexer {
+            server_url,
+            auth_token,
+            client: client.clone(),
+        }));
+    }
+    indexers.extend(
+        search
+            .indexers
+            .into_iter()
+            .filter(|i| i.enabled)
+            .map(|i| build_indexer(i, client.clone(), state.caps_cache.clone())),
+    );
+    if indexers.is_empty() {
+        return Err(CommandError::NotConfigured(
+            "No indexers configured. Set up a Nacho Server or add an indexer in Settings."
+                .to_string(),
+        ));
+    }
+    info!("Searching {} indexers", indexers.len());
+    Ok(indexers)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexerTestResult {
+    pub results: usize,
+    /// Torznab feeds only
+    pub caps: Option<TorznabCaps>,
+}
+
+/// Check an indexer config with a test search before saving it
+#[tauri::command]
+pub async fn test_indexer(
+    state: tauri::State<'_, State>,
+    indexer: IndexerConfig,
+) -> Result<IndexerTestResult, CommandError> {
+    let client = crate::proxy::http_client(&state)?;
+    // Always ask the indexer, but only once for the caps and the search
+    let caps_cache = CapsCache::default();
+    let caps = if indexer.kind == IndexerKind::Torznab {
+        let torznab = TorznabIndexer {
+            config: indexer.clone(),
+            client: client.clone(),
+            caps_cache: caps_cache.clone(),
+        };
+        Some(torznab.caps().await?)
+    } else {
+        None
+    };
+    let request = SearchRequest {
+        query: Some("test".to_owned()),
+        ..Default::default()
+    };
+    let results = build_indexer(indexer, client, caps_cache)
+        .search(&request)
+        .await?;
+    Ok(IndexerTestResult {
+        results: results.len(),
+        caps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAPS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<caps>
+  <searching>
+    <search available="yes" supportedParams="q"/>
+    <tv-search available="yes" supportedParams="q,season,ep,tvdbid"/>
+    <movie-search available="no" supportedParams="q"/>
+  </searching>
+  <categories>
+    <category id="5000" name="TV"><subcat id="5040" name="TV/HD"/></category>
+  </categories>
+</caps>"#;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <item>
+      <title>Show S01E02 1080p WEB-DL</title>
+      <link>http://localhost/dl/1.torrent</link>
+      <pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate>
+      <size>1500000000</size>
+      <jackettindexer id="a">Tracker A</jackettindexer>
+      <enclosure url="http://localhost/dl/1.torrent" length="1500000000" type="application/x-bittorrent"/>
+      <torznab:attr name="seeders" value="42"/>
+      <torznab:attr name="peers" value="50"/>
+      <torznab:attr name="infohash" value="C9E15763F722F23E98A29DECDFAE341B98D53056"/>
+    </item>
+    <item>
+      <title><![CDATA[Show S01E02 720p & more]]></title>
+      <link>magnet:?xt=urn:btih:ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW</link>
+      <torznab:attr name="seeders" value="3"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    fn config(url: String) -> IndexerConfig {
+        IndexerConfig {
+            name: "Mock".to_owned(),
+            kind: IndexerKind::Torznab,
+            url,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_caps() {
+        let caps = parse_torznab_caps(CAPS).unwrap();
+        assert!(caps.search);
+        assert_eq!(
+            caps.tv_search,
+            Some(vec![
+                "q".to_owned(),
+                "season".to_owned(),
+                "ep".to_owned(),
+                "tvdbid".to_owned()
+            ])
+        );
+        assert!(caps.movie_search.is_none());
+        assert!(caps.supports_category(5040));
+        assert!(!caps.supports_category(2000));
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let results = parse_torznab_results(FEED, "Mock").unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].indexer, "Tracker A");
+        assert_eq!(results[0].seeders, 42);
+        assert_eq!(results[0].size, 1_500_000_000);
+        assert_eq!(results[1].title, "Show S01E02 720p & more");
+        assert_eq!(results[1].indexer, "Mock");
+        // Both hash forms resolve to the same release
+        assert_eq!(results[0].info_hash, results[1].info_hash);
+    }
+
+    #[test]
+    fn test_rejects_error_response() {
+        let xml = r#"<error code="100" description="Incorrect user credentials"/>"#;
+        assert!(parse_torznab_results(xml, "Mock").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_against_mock() {
+        use axum::{Router, extract::Query, routing::get};
+
+        async fn api(Query(params): Query<HashMap<String, String>>) -> String {
+            match params.get("t").map(String::as_str) {
+                Some("caps") => CAPS.to_owned(),
+                Some("tvsearch") => {
+                    assert_eq!(params.get("tvdbid").map(String::as_str), Some("1234"));
+                    assert_eq!(params.get("season").map(String::as_str), Some("1"));
+                    assert_eq!(params.get("cat").map(String::as_str), Some("5000"));
+                    FEED.to_owned()
+                }
+                _ => r#"<error code="201" description="Unexpected search"/>"#.to_owned(),
+            }
+        }
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, Router::new().route("/api", get(api)))
+                .await
+                .unwrap();
+        });
+
+        let indexer = TorznabIndexer {
+            config: config(format!("http://{}/", addr)),
+            client: reqwest::Client::new(),
+            caps_cache: Default::default(),
+        };
+        let request = SearchRequest {
+            kind: SearchKind::Tv,
+            tvdb_id: Some(1234),
+            season: Some(1),
+            episode: Some(2),
+            ..Default::default()
+        };
+        let results = indexer.search(&request).await.unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_keeps_status() {
+        use axum::{Router, http::StatusCode, routing::get};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let app = Router::new()
+                .route("/api/v1/search", get(|| async { StatusCode::UNAUTHORIZED }))
+                .route(
+                    "/api/v2.0/indexers/all/results",
+                    get(|| async { StatusCode::BAD_GATEWAY }),
+                );
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let request = SearchRequest {
+            query: Some("test".to_owned()),
+            ..Default::default()
+        };
+        let prowlarr = ProwlarrIndexer {
+            config: config(format!("http://{}", addr)),
+            client: reqwest::Client::new(),
+        };
+        let e = prowlarr.search(&request).await.unwrap_err();
+        assert!(matches!(e, CommandError::Unauthorized(_)));
+
+        let jackett = JackettIndexer {
+            config: config(format!("http://{}", addr)),
+            client: reqwest::Client::new(),
+        };
+        let e = jackett.search(&request).await.unwrap_err();
+        assert_eq!(e.status(), 502);
+        assert!(e.retryable());
+    }
+}
@@ -1,17 +1,37 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod bandwidth;
+mod blocklist;
 mod config;
+mod deep_link;
+mod disk;
+mod error;
 mod file_server;
+mod indexer;
+mod network;
+mod play_best;
+mod post_process;
+mod proxy;
+mod quality;
+mod queue;
+mod rss;
+mod seeding;
 mod settings_manager;
+mod storage;
 mod tmdb;
+mod torrent_create;
 mod torrent_db;
+mod torrent_events;
+mod torrent_peers;
 mod torrent_search;
 mod torrent_server;
 mod transmux;
+mod watch_folder;
 mod watch_history;
+mod watchlist;
 
-use config::RqbitDesktopConfig;
+use config::{RqbitDesktopConfig, SeedingPolicy};
 use librqbit::{
     AddTorrentOptions, ApiError,
     api::{
@@ -21,6 +41,11 @@ use librqbit::{
     session_stats::snapshot::SessionStatsSnapshot,
     tracing_subscriber_config_utils::{InitLoggingOptions, init_logging},
 };
+use queue::QueueItem;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use torrent_db::QueuePriority;
+use torrent_peers::{PeerInfo, TrackerInfo};
 use torrent_server::State;
 use tracing::{info, warn};
 
@@ -113,6 +138,64 @@ async fn torrent_action_start(
     torrent_server::torrent_action_start(&state, id).await
 }
 
+#[tauri::command]
+fn torrent_trackers(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<Vec<TrackerInfo>, ApiError> {
+    torrent_peers::torrent_trackers(&state, id)
+}
+
+#[tauri::command]
+async fn torrent_action_set_trackers(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    tiers: Vec<Vec<String>>,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    torrent_peers::torrent_action_set_trackers(&state, id, tiers).await
+}
+
+#[tauri::command]
+async fn torrent_action_reannounce(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<EmptyJsonResponse, ApiError> {
+    torrent_peers::torrent_action_reannounce(&state, id).await
+}
+
+#[tauri::command]
+fn torrent_peers(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<Vec<PeerInfo>, ApiError> {
+    torrent_peers::torrent_peers(&state, id)
+}
+
+#[tauri::command]
+fn torrent_action_add_peers(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    peers: Vec<SocketAddr>,
+) -> Result<EmptyJsonResponse, ApiError> {
+    torrent_peers::torrent_action_add_peers(&state, id, peers)
+}
+
+#[tauri::command]
+async fn torrent_action_ban_peers(
+    state: tauri::State<'_, State>,
+    ips: Vec<IpAddr>,
+) -> Result<blocklist::BlocklistStatus, ApiError> {
+    torrent_peers::torrent_action_ban_peers(&state, ips).await
+}
+
+#[tauri::command]
+async fn torrent_action_unban_peers(
+    state: tauri::State<'_, State>,
+    ips: Vec<IpAddr>,
+) -> Result<blocklist::BlocklistStatus, ApiError> {
+    torrent_peers::torrent_action_unban_peers(&state, ips).await
+}
+
 #[tauri::command]
 async fn torrent_action_configure(
     state: tauri::State<'_, State>,
@@ -122,6 +205,17 @@ async fn torrent_action_configure(
     torrent_server::torrent_action_configure(&state, id, only_files).await
 }
 
+#[tauri::command]
+async fn torrent_action_move_storage(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    folder: PathBuf,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    torrent_server::relocate_torrent(&state, id, folder, torrent_server::emit_move_progress(&app))
+        .await
+}
+
 #[tauri::command]
 async fn stats(state: tauri::State<'_, State>) -> Result<SessionStatsSnapshot, ApiError> {
     torrent_server::stats(&state).await
@@ -265,6 +359,62 @@ fn get_all_library_imdb_codes(state: tauri::State<'_, State>) -> Result<Vec<Stri
     torrent_server::get_all_library_imdb_codes(&state)
 }
 
+#[tauri::command]
+fn get_torrent_seeding_policy(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<Option<SeedingPolicy>, ApiError> {
+    seeding::get_torrent_seeding_policy(&state, id)
+}
+
+#[tauri::command]
+fn set_torrent_seeding_policy(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    policy: Option<SeedingPolicy>,
+) -> Result<EmptyJsonResponse, ApiError> {
+    seeding::set_torrent_seeding_policy(&state, id, policy)
+}
+
+#[tauri::command]
+fn torrent_queue_list(state: tauri::State<'_, State>) -> Result<Vec<QueueItem>, ApiError> {
+    queue::torrent_queue_list(&state)
+}
+
+#[tauri::command]
+async fn torrent_queue_move_up(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<EmptyJsonResponse, ApiError> {
+    queue::torrent_queue_move(&state, id, true).await
+}
+
+#[tauri::command]
+async fn torrent_queue_move_down(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+) -> Result<EmptyJsonResponse, ApiError> {
+    queue::torrent_queue_move(&state, id, false).await
+}
+
+#[tauri::command]
+async fn torrent_queue_set_priority(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    priority: QueuePriority,
+) -> Result<EmptyJsonResponse, ApiError> {
+    queue::torrent_queue_set_priority(&state, id, priority).await
+}
+
+#[tauri::command]
+async fn torrent_queue_force_start(
+    state: tauri::State<'_, State>,
+    id: TorrentIdOrHash,
+    force: bool,
+) -> Result<EmptyJsonResponse, ApiError> {
+    queue::torrent_queue_force_start(&state, id, force).await
+}
+
 #[tauri::command]
 fn get_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
@@ -314,6 +464,21 @@ pub async fn start() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .manage(state)
+        .setup(|app| {
+            deep_link::register(app.handle());
+            torrent_events::spawn_session_watcher(app.handle().clone());
+            post_process::spawn_post_processor(app.handle().clone());
+            seeding::spawn_seeding_enforcer(app.handle().clone());
+            storage::spawn_storage_manager(app.handle().clone());
+            bandwidth::spawn_bandwidth_scheduler(app.handle().clone());
+            queue::spawn_queue_manager(app.handle().clone());
+            watch_folder::spawn_watch_folders(app.handle().clone());
+            blocklist::spawn_blocklist_updater(app.handle().clone());
+            network::spawn_kill_switch(app.handle().clone());
+            watchlist::spawn_watchlist_scheduler(app.handle().clone());
+            rss::spawn_rss_poller(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             show_main,
             torrents_list,
@@ -327,6 +492,14 @@ pub async fn start() {
             torrent_action_forget,
             torrent_action_start,
             torrent_action_configure,
+            torrent_action_move_storage,
+            torrent_trackers,
+            torrent_action_set_trackers,
+            torrent_action_reannounce,
+            torrent_peers,
+            torrent_action_add_peers,
+            torrent_action_ban_peers,
+            torrent_action_unban_peers,
             torrent_create_from_base64_file,
             stats,
             get_torrent_files,
@@ -338,6 +511,13 @@ pub async fn start() {
             get_torrent_metadata,
             get_all_torrents_with_metadata,
             get_all_torrents_with_imdb,
+            get_torrent_seeding_policy,
+            set_torrent_seeding_policy,
+            torrent_queue_list,
+            torrent_queue_move_up,
+            torrent_queue_move_down,
+            torrent_queue_set_priority,
+            torrent_queue_force_start,
             get_version,
             config_default,
             config_current,
@@ -351,6 +531,14 @@ pub async fn start() {
             watch_history::get_watched_movies,
             watch_history::get_watched_episodes,
             watch_history::get_show_watched_episodes,
+            watchlist::get_watchlist,
+            watchlist::add_to_watchlist,
+            watchlist::remove_from_watchlist,
+            watchlist::sync_watchlist,
+            watchlist::check_watchlist,
+            rss::get_rss_status,
+            rss::refresh_rss_feeds,
+            rss::preview_rss_feed,
             tmdb::get_tmdb_config,
             tmdb::get_tmdb_movie,
             tmdb::get_tmdb_movie_images,
@@ -359,6 +547,7 @@ pub async fn start() {
             tmdb::get_tmdb_season,
             tmdb::get_tmdb_episode,
             tmdb::get_tmdb_episode_external_ids,
+            tmdb::get_tmdb_show_external_ids,
             tmdb::get_tmdb_season_images,
             tmdb::find_tmdb_movie_by_imdb,
             tmdb::find_tmdb_show_by_imdb,
@@ -376,7 +565,13 @@ pub async fn start() {
             settings_manager::update_nacho_server_url,
             settings_manager::get_nacho_auth_token,
             settings_manager::update_nacho_auth_token,
+            settings_manager::get_nacho_user,
             torrent_search::search_torrents_by_imdb,
+            torrent_search::search_torrents_for_show,
+            quality::rank_torrent_results,
+            play_best::play_best,
+            indexer::test_indexer,
+            torrent_search::preview_torrent_download,
             torrent_search::download_torrent_from_prowlarr,
             get_library_files_by_tmdb_id,
             get_library_files_by_imdb,
@@ -385,6 +580,18 @@ pub async fn start() {
             transmux::transmux_to_mp4,
             transmux::needs_transmux,
             transmux::get_transmux_output_path,
+            post_process::post_process_torrent,
+            storage::get_storage_report,
+            storage::run_storage_cleanup,
+            bandwidth::get_bandwidth_status,
+            bandwidth::set_bandwidth_override,
+            deep_link::take_pending_deep_link_events,
+            deep_link::open_deep_link,
+            torrent_create::create_torrent,
+            blocklist::get_blocklist_status,
+            blocklist::refresh_blocklist,
+            blocklist::apply_blocklist,
+            proxy::test_proxy,
             file_server::init_file_server,
             file_server::set_served_file,
             file_server::get_served_file_url,
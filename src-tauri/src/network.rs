@@ -0,0 +1,300 @@
+use std::{
+    net::IpAddr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use http::StatusCode;
+use librqbit::{AddTorrentOptions, ApiError, api::TorrentIdOrHash};
+use network_interface::{NetworkInterface, NetworkInterfaceConfig};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::config::RqbitDesktopConfigConnections;
+use crate::torrent_server::{self, State};
+
+/// Name of the Tauri event carrying [`KillSwitchEvent`] payloads to the frontend
+pub const KILL_SWITCH_EVENT: &str = "kill-switch-event";
+
+/// How often the bind interface is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Kill switch state, kept on [`State`]
+#[derive(Default)]
+pub struct KillSwitch {
+    /// Whether torrents are currently held paused because the bind interface is down
+    tripped: AtomicBool,
+    /// Info hashes paused by the kill switch, resumed when the interface comes back
+    paused: Mutex<Vec<String>>,
+    /// Address the running session was bound to
+    bound_ip: Mutex<Option<IpAddr>>,
+    /// Address a session restart last failed on; not retried until the address changes
+    failed_ip: Mutex<Option<IpAddr>>,
+}
+
+impl KillSwitch {
+    /// Whether the kill switch is holding torrents paused
+    pub fn tripped(&self) -> bool {
+        self.tripped.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_bound_ip(&self, ip: Option<IpAddr>) {
+        *self.bound_ip.lock() = ip;
+    }
+
+    /// Error for starting a torrent while the kill switch holds torrents paused
+    pub(crate) fn check_released(&self) -> Result<(), ApiError> {
+        if self.tripped() {
+            return Err(ApiError::new_from_text(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The network interface is down, torrents stay paused until it is back",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Make a torrent about to be added start paused while tripped.
+    /// Returns whether it is held, to be passed to [`Self::held`] once added.
+    pub(crate) fn hold(&self, opts: &mut Option<AddTorrentOptions>) -> bool {
+        if !self.tripped() {
+            return false;
+        }
+        let opts = opts.get_or_insert_with(Default::default);
+        if opts.paused || opts.list_only {
+            return false;
+        }
+        opts.paused = true;
+        true
+    }
+
+    /// Resume a torrent added by [`Self::hold`] on release. Returns false if the kill
+    /// switch was released in the meantime, then the caller has to start it.
+    pub(crate) fn held(&self, info_hash: String) -> bool {
+        let mut paused = self.paused.lock();
+        if !self.tripped() {
+            return false;
+        }
+        paused.push(info_hash);
+        true
+    }
+}
+
+/// Interface and address torrent traffic is bound to
+#[derive(Debug, Clone)]
+pub struct BindTarget {
+    pub interface: String,
+    pub ip: IpAddr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KillSwitchEvent {
+    /// True while torrents are paused because the interface is down
+    pub active: bool,
+    pub interface: String,
+    pub paused_torrents: usize,
+}
+
+/// Find an up interface by name, or the interface owning an IP.
+/// Names prefer the interface's IPv4 address.
+fn find_interface(spec: &str) -> anyhow::Result<Option<BindTarget>> {
+    let interfaces = NetworkInterface::show()?;
+    if let Ok(ip) = spec.parse::<IpAddr>() {
+        return Ok(interfaces
+            .into_iter()
+            .find(|i| i.addr.iter().any(|a| a.ip() == ip))
+            .map(|i| BindTarget {
+                interface: i.name,
+                ip,
+            }));
+    }
+
+    let Some(interface) = interfaces.into_iter().find(|i| i.name == spec) else {
+        return Ok(None);
+    };
+    let ip = interface
+        .addr
+        .iter()
+        .map(|a| a.ip())
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| interface.addr.first().map(|a| a.ip()));
+    Ok(ip.map(|ip| BindTarget {
+        interface: interface.name,
+        ip,
+    }))
+}
+
+/// Resolve the configured bind interface for a new session. With the kill switch on,
+/// a missing interface is an error so the session never starts unbound.
+pub(crate) fn resolve_bind(
+    connections: &RqbitDesktopConfigConnections,
+) -> anyhow::Result<Option<BindTarget>> {
+    let spec = connections.bind_interface.trim();
+    let target = if spec.is_empty() {
+        None
+    } else {
+        match find_interface(spec)? {
+            Some(target) => {
+                info!(
+                    "Binding torrent traffic to {} ({})",
+                    target.interface, target.ip
+                );
+                Some(target)
+            }
+            None if connections.kill_switch => {
+                anyhow::bail!(
+                    "network interface {:?} is down, not starting torrents",
+                    spec
+                )
+            }
+            None => {
+                warn!(
+                    "Network interface {:?} not found, not binding torrent traffic",
+                    spec
+                );
+                None
+            }
+        }
+    };
+    Ok(target)
+}
+
+/// Spawn the task pausing all torrents while the bind interface is down
+pub fn spawn_kill_switch(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            check_interface(&app).await;
+        }
+    });
+}
+
+async fn check_interface(app: &AppHandle) {
+    let state = app.state::<State>();
+    let kill_switch = &state.kill_switch;
+    let Some(connections) = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.connections.clone())
+    else {
+        return;
+    };
+    let spec = connections.bind_interface.trim();
+    if !connections.kill_switch || spec.is_empty() {
+        if kill_switch.tripped() {
+            release(app, spec).await;
+        }
+        return;
+    }
+
+    let target = match find_interface(spec) {
+        Ok(target) => target,
+        Err(e) => {
+            warn!("Failed to list network interfaces: {:#}", e);
+            None
+        }
+    };
+
+    match target {
+        None => {
+            // The interface coming back is worth another restart attempt
+            *kill_switch.failed_ip.lock() = None;
+            if !kill_switch.tripped() {
+                trip(app, spec).await;
+            }
+        }
+        Some(target) => {
+            // Rebind if the session isn't running yet or the VPN came back with a new address
+            let session_running = state.api().is_ok();
+            if !session_running || *kill_switch.bound_ip.lock() != Some(target.ip) {
+                if *kill_switch.failed_ip.lock() == Some(target.ip) {
+                    return;
+                }
+                info!(
+                    "Network interface {} is up at {}, restarting session",
+                    target.interface, target.ip
+                );
+                if let Err(e) = state.reload_session().await {
+                    warn!(error=?e, "Failed to restart session on network interface");
+                    *kill_switch.failed_ip.lock() = Some(target.ip);
+                    return;
+                }
+                *kill_switch.failed_ip.lock() = None;
+            }
+            if kill_switch.tripped() {
+                release(app, spec).await;
+            }
+        }
+    }
+}
+
+async fn trip(app: &AppHandle, interface: &str) {
+    let state = app.state::<State>();
+    state.kill_switch.tripped.store(true, Ordering::SeqCst);
+    warn!(
+        "Network interface {} is down, pausing all torrents",
+        interface
+    );
+
+    let mut paused = Vec::new();
+    if let Ok(api) = state.api() {
+        for torrent in api.api_torrent_list().torrents {
+            let Some(id) = torrent.id else { continue };
+            let running = api
+                .api_stats_v1(TorrentIdOrHash::Id(id))
+                .is_ok_and(|s| s.live.is_some());
+            if !running {
+                continue;
+            }
+            match api.api_torrent_action_pause(TorrentIdOrHash::Id(id)).await {
+                Ok(_) => paused.push(torrent.info_hash),
+                Err(e) => warn!(error=?e, "Kill switch failed to pause torrent"),
+            }
+        }
+    }
+
+    let count = paused.len();
+    state.kill_switch.paused.lock().extend(paused);
+    emit(app, true, interface, count);
+}
+
+async fn release(app: &AppHandle, interface: &str) {
+    let state = app.state::<State>();
+    // Released under the lock, so torrents added meanwhile are either listed or started
+    let paused = {
+        let mut paused = state.kill_switch.paused.lock();
+        state.kill_switch.tripped.store(false, Ordering::SeqCst);
+        std::mem::take(&mut *paused)
+    };
+    info!(
+        "Network interface {} is back, resuming {} torrents",
+        interface,
+        paused.len()
+    );
+
+    if let Ok(api) = state.api() {
+        for info_hash in &paused {
+            let Ok(id) = torrent_server::find_torrent_id(&state, info_hash) else {
+                continue;
+            };
+            if let Err(e) = api.api_torrent_action_start(TorrentIdOrHash::Id(id)).await {
+                warn!(error=?e, "Failed to resume torrent after kill switch");
+            }
+        }
+    }
+    emit(app, false, interface, paused.len());
+}
+
+fn emit(app: &AppHandle, active: bool, interface: &str, paused_torrents: usize) {
+    let event = KillSwitchEvent {
+        active,
+        interface: interface.to_owned(),
+        paused_torrents,
+    };
+    if let Err(e) = app.emit(KILL_SWITCH_EVENT, &event) {
+        warn!(error=?e, "Failed to emit kill switch event");
+    }
+}
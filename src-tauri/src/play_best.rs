@@ -0,0 +1,430 @@
+use std::time::Duration;
+
+use librqbit::api::{TorrentDetailsResponse, TorrentDetailsResponseFile, TorrentIdOrHash};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncReadExt;
+use tracing::{info, warn};
+
+use crate::error::CommandError;
+use crate::quality::rank_results;
+use crate::torrent_server::State;
+
+/// Name of the Tauri event carrying [`PlayBestProgress`] payloads to the frontend
+pub const PLAY_BEST_EVENT: &str = "play-best-progress";
+
+/// Bytes from the start of the file that must be downloaded before playback starts
+const BUFFER_BYTES: u64 = 16 * 1024 * 1024;
+/// Give up when the buffer doesn't fill in this time, e.g. when there are no peers
+const BUFFER_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+/// How many of the best results are tried before giving up on adding one
+const MAX_ATTEMPTS: usize = 3;
+
+const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "m4v", "avi", "mov", "webm", "ts"];
+
+/// Stage of a "play best" request
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum PlayBestStage {
+    Searching,
+    Ranked {
+        candidates: usize,
+    },
+    Adding {
+        title: String,
+    },
+    Buffering {
+        buffered_bytes: u64,
+        target_bytes: u64,
+    },
+    Ready {
+        stream_url: String,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayBestProgress {
+    pub tmdb_id: u64,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    #[serde(flatten)]
+    pub stage: PlayBestStage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayBestResponse {
+    pub torrent_id: usize,
+    pub info_hash: String,
+    /// Release title of the chosen search result
+    pub title: String,
+    pub score: i32,
+    pub file_id: usize,
+    pub file_name: String,
+    pub stream_url: String,
+    /// The file isn't MP4, so the in-app player can't play it until it's transmuxed
+    pub needs_transmux: bool,
+}
+
+/// Search for a movie or episode, add the best result under the default quality
+/// profile and return a stream URL once the start of the video is buffered.
+/// Progress is reported through [`PLAY_BEST_EVENT`].
+#[tauri::command]
+pub async fn play_best(
+    app: AppHandle,
+    state: tauri::State<'_, State>,
+    tmdb_id: u64,
+    media_type: String,
+    season: Option<u32>,
+    episode: Option<u32>,
+) -> Result<PlayBestResponse, CommandError> {
+    let report = |stage: PlayBestStage| {
+        let progress = PlayBestProgress {
+            tmdb_id,
+            season,
+            episode,
+            stage,
+        };
+        if let Err(e) = app.emit(PLAY_BEST_EVENT, &progress) {
+            warn!("Failed to emit play best progress: {}", e);
+        }
+    };
+
+    let result = run(&app, &state, tmdb_id, &media_type, season, episode, &report).await;
+    match &result {
+        Ok(response) => report(PlayBestStage::Ready {
+            stream_url: response.stream_url.clone(),
+        }),
+        Err(e) => report(PlayBestStage::Failed {
+            error: e.to_string(),
+        }),
+    }
+    result
+}
+
+async fn run(
+    app: &AppHandle,
+    state: &tauri::State<'_, State>,
+    tmdb_id: u64,
+    media_type: &str,
+    season: Option<u32>,
+    episode: Option<u32>,
+    report: &impl Fn(PlayBestStage),
+) -> Result<PlayBestResponse, CommandError> {
+    // A new torrent would stay paused and never buffer
+    state.kill_switch.check_released()?;
+    report(PlayBestStage::Searching);
+    let (results, runtime, episode_info) = match media_type {
+        "movie" => {
+            let movie = crate::tmdb::get_tmdb_movie(app.clone(), tmdb_id).await?;
+            let year = movie.release_date.as_deref().and_then(|d| d.get(..4));
+            let results = crate::torrent_search::search_movie(
+                app.clone(),
+                movie.imdb_id.clone(),
+                &movie.title,
+                year,
+            )
+            .await?;
+            (results, movie.runtime, None)
+        }
+        "tv" => {
+            let (Some(season), Some(episode)) = (season, episode) else {
+                return Err(CommandError::InvalidInput(
+                    "Playing a show needs a season and an episode".to_string(),
+                ));
+            };
+            let runtime =
+                match crate::tmdb::get_tmdb_episode(app.clone(), tmdb_id, season, episode).await {
+                    Ok(details) => details.runtime,
+                    Err(e) => {
+                        warn!("Failed to get episode runtime: {}", e);
+                        None
+                    }
+                };
+            let results = crate::torrent_search::search_torrents_for_show(
+                app.clone(),
+                tmdb_id,
+                Some(season),
+                Some(episode),
+                None,
+            )
+            .await?;
+            (results, runtime, Some((season as i32, episode as i32)))
+        }
+        other => {
+            return Err(CommandError::InvalidInput(format!(
+                "Unknown media type: {}",
+                other
+            )));
+        }
+    };
+
+    let profile = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.search.profile(None))
+        .ok_or_else(|| CommandError::NotConfigured("Configuration not available".to_string()))?;
+    let mut ranked = rank_results(results, &profile, runtime);
+    ranked.retain(|r| r.rejection.is_none() && r.result.seeders > 0);
+    report(PlayBestStage::Ranked {
+        candidates: ranked.len(),
+    });
+    if ranked.is_empty() {
+        return Err(CommandError::NotFound(
+            "No results match your quality profile".to_string(),
+        ));
+    }
+
+    // Torrents that were already added are left alone if anything below fails
+    let existing: Vec<usize> = state
+        .api()
+        .map(|api| {
+            api.api_torrent_list()
+                .torrents
+                .into_iter()
+                .filter_map(|t| t.id)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Dead links are common, so fall back to the next best results
+    let mut added = None;
+    let mut last_error = None;
+    for candidate in ranked.into_iter().take(MAX_ATTEMPTS) {
+        info!(
+            "Trying {} (score {})",
+            candidate.result.title, candidate.score
+        );
+        report(PlayBestStage::Adding {
+            title: candidate.result.title.clone(),
+        });
+        match crate::torrent_search::download_torrent_from_prowlarr(
+            state.clone(),
+            app.clone(),
+            candidate.result.download_url.clone(),
+            Some(tmdb_id),
+            Some(media_type.to_string()),
+            episode_info,
+            None,
+        )
+        .await
+        {
+            Ok(response) => {
+                added = Some((candidate, response));
+                break;
+            }
+            Err(e) => {
+                warn!("Failed to add {}: {}", candidate.result.title, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    let (candidate, response) = added.ok_or_else(|| {
+        last_error
+            .unwrap_or_else(|| CommandError::Internal("No result was tried".to_string()))
+            .context("Failed to add any result")
+    })?;
+    let torrent_id = response
+        .id
+        .ok_or_else(|| CommandError::Internal("Torrent was added without an ID".to_string()))?;
+
+    match prepare(state, torrent_id, season.zip(episode), report).await {
+        Ok((info_hash, file_id, file_name, stream_url)) => Ok(PlayBestResponse {
+            torrent_id,
+            info_hash,
+            title: candidate.result.title,
+            score: candidate.score,
+            file_id,
+            needs_transmux: crate::transmux::needs_transmux(file_name.clone()),
+            file_name,
+            stream_url,
+        }),
+        Err(e) => {
+            // Don't leave a download behind that nothing is going to play
+            if !existing.contains(&torrent_id) {
+                info!("Removing {} after: {}", candidate.result.title, e);
+                if let Err(e) = crate::torrent_server::torrent_action_delete(
+                    state,
+                    TorrentIdOrHash::Id(torrent_id),
+                )
+                .await
+                {
+                    warn!("Failed to remove torrent: {:?}", e);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Select the video file of an added torrent, start it and wait for the start of
+/// the file. Returns (info hash, file index, file name, stream URL).
+async fn prepare(
+    state: &State,
+    torrent_id: usize,
+    episode: Option<(u32, u32)>,
+    report: &impl Fn(PlayBestStage),
+) -> Result<(String, usize, String, String), CommandError> {
+    let id = TorrentIdOrHash::Id(torrent_id);
+    let details = crate::torrent_server::torrent_details(state, id)
+        .await
+        .map_err(|e| CommandError::from(e).context("Failed to get torrent details"))?;
+    let (file_id, file_name, file_length) = pick_file(&details, episode).ok_or_else(|| {
+        CommandError::NotFound("The torrent doesn't contain a matching video file".to_string())
+    })?;
+    info!("Selected file {}: {}", file_id, file_name);
+
+    crate::torrent_server::torrent_action_configure(state, id, vec![file_id])
+        .await
+        .map_err(|e| CommandError::from(e).context("Failed to select file"))?;
+    // It may have been added paused or been queued
+    if let Err(e) = crate::torrent_server::torrent_action_start(state, id).await {
+        warn!("Failed to start torrent: {:?}", e);
+    }
+
+    let target = BUFFER_BYTES.min(file_length);
+    tokio::time::timeout(BUFFER_TIMEOUT, buffer(state, id, file_id, target, report))
+        .await
+        .map_err(|_| {
+            CommandError::Network("Timed out waiting for the video to buffer".to_string())
+        })??;
+
+    let stream_url = stream_url(state, torrent_id, file_id)?;
+    Ok((details.info_hash, file_id, file_name, stream_url))
+}
+
+/// Read the start of the file through a stream, which makes the session download
+/// those pieces first
+async fn buffer(
+    state: &State,
+    id: TorrentIdOrHash,
+    file_id: usize,
+    target: u64,
+    report: &impl Fn(PlayBestStage),
+) -> Result<(), CommandError> {
+    let mut stream = state
+        .api()
+        .and_then(|api| api.api_stream(id, file_id))
+        .map_err(|e| CommandError::from(e).context("Failed to open stream"))?;
+    let mut buf = vec![0u8; 256 * 1024];
+    let mut buffered = 0u64;
+    let mut reported = 0u64;
+    report(PlayBestStage::Buffering {
+        buffered_bytes: 0,
+        target_bytes: target,
+    });
+    while buffered < target {
+        let n = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| CommandError::Internal(format!("Failed to read stream: {}", e)))?;
+        if n == 0 {
+            break;
+        }
+        buffered += n as u64;
+        if buffered - reported >= 1024 * 1024 || buffered >= target {
+            reported = buffered;
+            report(PlayBestStage::Buffering {
+                buffered_bytes: buffered.min(target),
+                target_bytes: target,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// URL of the session's HTTP API stream for a file
+fn stream_url(state: &State, torrent_id: usize, file_id: usize) -> Result<String, CommandError> {
+    let g = state.shared.read();
+    let http_api = &g
+        .as_ref()
+        .ok_or_else(|| CommandError::NotConfigured("Configuration not available".to_string()))?
+        .config
+        .http_api;
+    if http_api.disable {
+        return Err(CommandError::NotConfigured(
+            "Streaming needs the HTTP API to be enabled".to_string(),
+        ));
+    }
+    let mut addr = http_api.listen_addr;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+    }
+    Ok(format!(
+        "http://{}/torrents/{}/stream/{}",
+        addr, torrent_id, file_id
+    ))
+}
+
+/// The video file to play: for an episode, the largest one named after it,
+/// otherwise the largest video in the torrent. Returns (index, name, length).
+fn pick_file(
+    details: &TorrentDetailsResponse,
+    episode: Option<(u32, u32)>,
+) -> Option<(usize, String, u64)> {
+    let videos: Vec<(usize, &TorrentDetailsResponseFile)> = details
+        .files
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, f)| {
+            f.name
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+                && !f.name.to_lowercase().contains("sample")
+        })
+        .collect();
+
+    let matching: Vec<_> = match episode {
+        // Season packs hold every episode, so the name decides. A single-episode
+        // release may not repeat the episode in its file name.
+        Some((season, episode)) if videos.len() > 1 => videos
+            .into_iter()
+            .filter(|(_, f)| names_episode(&f.name, season, episode))
+            .collect(),
+        _ => videos,
+    };
+    matching
+        .into_iter()
+        .max_by_key(|(_, f)| f.length)
+        .map(|(i, f)| (i, f.name.clone(), f.length))
+}
+
+/// Whether a file name refers to an episode as "S01E02" or "1x02"
+fn names_episode(name: &str, season: u32, episode: u32) -> bool {
+    let name = name.to_lowercase();
+    [
+        format!("s{:02}e{:02}", season, episode),
+        format!("s{}e{:02}", season, episode),
+        format!("{}x{:02}", season, episode),
+    ]
+    .iter()
+    .any(|tag| {
+        name.match_indices(tag.as_str()).any(|(i, _)| {
+            let before = name[..i].chars().next_back();
+            let after = name[i + tag.len()..].chars().next();
+            !before.is_some_and(|c| c.is_ascii_alphanumeric())
+                && !after.is_some_and(|c| c.is_ascii_digit())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_episode() {
+        assert!(names_episode("Show.Name.S01E02.1080p.mkv", 1, 2));
+        assert!(names_episode("show name - 1x02 - title.mp4", 1, 2));
+        // A multi-episode file starts with the requested episode
+        assert!(names_episode("Show.S01E02E03.mkv", 1, 2));
+        assert!(!names_episode("Show.S01E12.mkv", 1, 2));
+        assert!(!names_episode("Show.S01E020.mkv", 1, 2));
+        assert!(!names_episode("Show.S11E02.mkv", 1, 2));
+        assert!(!names_episode("Show.11x02.mkv", 1, 2));
+    }
+}
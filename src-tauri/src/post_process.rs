@@ -0,0 +1,370 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use librqbit::api::{TorrentDetailsResponse, TorrentIdOrHash};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, info, warn};
+
+use crate::config::RqbitDesktopConfigPostProcess;
+use crate::error::CommandError;
+use crate::torrent_db::TorrentEntry;
+use crate::torrent_events::TorrentEvent;
+use crate::torrent_server::{self, State};
+use crate::transmux;
+
+/// Name of the Tauri event carrying [`PostProcessEvent`] payloads to the frontend
+pub const POST_PROCESS_EVENT: &str = "post-process-event";
+
+const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "m4v", "avi", "mov", "webm", "ts"];
+
+/// How often to check whether the session is up before catching up on unprocessed torrents
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Progress of the post-download pipeline for a torrent
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum PostProcessEvent {
+    Started {
+        info_hash: String,
+    },
+    Moved {
+        info_hash: String,
+        folder: PathBuf,
+    },
+    Transmuxed {
+        info_hash: String,
+        output: PathBuf,
+    },
+    SubtitlesExtracted {
+        info_hash: String,
+        files: Vec<PathBuf>,
+    },
+    Finished {
+        info_hash: String,
+    },
+    Failed {
+        info_hash: String,
+        error: String,
+    },
+}
+
+/// Spawn the task that runs the pipeline for every torrent that completes, and
+/// catch up on finished torrents that weren't processed in a previous run
+pub fn spawn_post_processor(app: AppHandle) {
+    let mut events = app.state::<State>().events.subscribe();
+    tokio::spawn(process_pending(app.clone()));
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(TorrentEvent::Completed { info_hash, .. }) => {
+                    let app = app.clone();
+                    tokio::spawn(async move {
+                        run_and_report(&app, info_hash, false).await;
+                    });
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Post-processor missed {} torrent events", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Run the pipeline for a torrent on demand, regardless of whether it ran before
+#[tauri::command]
+pub async fn post_process_torrent(app: AppHandle, id: TorrentIdOrHash) -> Result<(), CommandError> {
+    let info_hash = {
+        let state = app.state::<State>();
+        let api = state.api()?;
+        api.api_torrent_details(id)
+            .map_err(|e| CommandError::from(e).context("Failed to get torrent details"))?
+            .info_hash
+    };
+    run_pipeline(&app, &info_hash, true)
+        .await
+        .map_err(|e| CommandError::from(e).context("Post-processing failed"))
+}
+
+/// Run the pipeline for finished torrents that failed, were interrupted or finished
+/// before startup. The session watcher doesn't report those as completed.
+async fn process_pending(app: AppHandle) {
+    let mut interval = tokio::time::interval(STARTUP_POLL_INTERVAL);
+    let api = loop {
+        interval.tick().await;
+        if let Ok(api) = app.state::<State>().api() {
+            break api;
+        }
+    };
+    let state = app.state::<State>();
+    for torrent in api.api_torrent_list().torrents {
+        let Some(id) = torrent.id else { continue };
+        let Ok(stats) = api.api_stats_v1(TorrentIdOrHash::Id(id)) else {
+            continue;
+        };
+        let processed = state
+            .torrent_db
+            .get_by_hash(&torrent.info_hash)
+            .is_some_and(|e| e.post_processed);
+        if stats.finished && !processed {
+            run_and_report(&app, torrent.info_hash, false).await;
+        }
+    }
+}
+
+async fn run_and_report(app: &AppHandle, info_hash: String, force: bool) {
+    if let Err(e) = run_pipeline(app, &info_hash, force).await {
+        error!("Post-processing failed for {}: {:#}", info_hash, e);
+        emit(
+            app,
+            PostProcessEvent::Failed {
+                info_hash,
+                error: format!("{:#}", e),
+            },
+        );
+    }
+}
+
+fn emit(app: &AppHandle, event: PostProcessEvent) {
+    if let Err(e) = app.emit(POST_PROCESS_EVENT, &event) {
+        warn!(error=?e, "Failed to emit post-process event");
+    }
+}
+
+async fn run_pipeline(app: &AppHandle, info_hash: &str, force: bool) -> anyhow::Result<()> {
+    let state = app.state::<State>();
+    let Some(config) = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.post_process.clone())
+    else {
+        return Ok(());
+    };
+    if !config.enabled && !force {
+        return Ok(());
+    }
+
+    let mut id = torrent_server::find_torrent_id(&state, info_hash)?;
+    let api = state.api()?;
+    let mut details = api.api_torrent_details(TorrentIdOrHash::Id(id))?;
+
+    state
+        .torrent_db
+        .upsert_torrent(id as i32, info_hash.to_owned(), None, None, None)?;
+    let entry = state
+        .torrent_db
+        .get_by_hash(info_hash)
+        .context("torrent entry disappeared")?;
+    if entry.post_processed && !force {
+        return Ok(());
+    }
+    // Claim the torrent so re-adding it after a move doesn't start a second run.
+    // It's only marked as processed once the pipeline succeeded, so a failed or
+    // interrupted run is picked up again at the next startup.
+    let Some(_claim) = Claim::new(&state.post_processing, info_hash) else {
+        info!("Torrent {} is already being post-processed", info_hash);
+        return Ok(());
+    };
+
+    info!("Post-processing torrent {}", info_hash);
+    emit(
+        app,
+        PostProcessEvent::Started {
+            info_hash: info_hash.to_owned(),
+        },
+    );
+
+    if config.move_to_library {
+        match library_folder_for(app, &config, &entry, details.name.as_deref()).await {
+            Some(folder) if Path::new(&details.output_folder) != folder => {
+                let response = torrent_server::relocate_torrent(
+                    &state,
+                    TorrentIdOrHash::Id(id),
+                    folder.clone(),
+                    torrent_server::emit_move_progress(app),
+                )
+                .await?;
+                id = response.id.context("re-added torrent has no ID")?;
+                details = api.api_torrent_details(TorrentIdOrHash::Id(id))?;
+                emit(
+                    app,
+                    PostProcessEvent::Moved {
+                        info_hash: info_hash.to_owned(),
+                        folder,
+                    },
+                );
+            }
+            Some(_) => {}
+            None => info!(
+                "Torrent {} has no TMDB metadata, leaving it in place",
+                info_hash
+            ),
+        }
+    }
+
+    for video in video_files(&details) {
+        if config.transmux && transmux::needs_transmux(video.to_string_lossy().to_string()) {
+            let output = transmux::generate_output_path(&video)?;
+            if !output.exists() {
+                let (input, out) = (video.to_string_lossy().to_string(), output.clone());
+                tokio::task::spawn_blocking(move || transmux::transmux_file(&input, &out))
+                    .await
+                    .context("transmux task panicked")??;
+            }
+            state
+                .torrent_db
+                .add_derived_file(info_hash, output.clone())?;
+            emit(
+                app,
+                PostProcessEvent::Transmuxed {
+                    info_hash: info_hash.to_owned(),
+                    output,
+                },
+            );
+        }
+
+        if config.extract_subtitles {
+            let input = video.clone();
+            let files = tokio::task::spawn_blocking(move || transmux::extract_subtitles(&input))
+                .await
+                .context("subtitle task panicked")?
+                .unwrap_or_else(|e| {
+                    warn!("Failed to extract subtitles from {:?}: {:#}", video, e);
+                    Vec::new()
+                });
+            for file in &files {
+                state.torrent_db.add_derived_file(info_hash, file.clone())?;
+            }
+            if !files.is_empty() {
+                emit(
+                    app,
+                    PostProcessEvent::SubtitlesExtracted {
+                        info_hash: info_hash.to_owned(),
+                        files,
+                    },
+                );
+            }
+        }
+    }
+
+    state.torrent_db.set_post_processed(info_hash, true)?;
+    info!("Finished post-processing torrent {}", info_hash);
+    emit(
+        app,
+        PostProcessEvent::Finished {
+            info_hash: info_hash.to_owned(),
+        },
+    );
+    Ok(())
+}
+
+/// Marks a torrent as being post-processed until dropped
+struct Claim<'a> {
+    running: &'a Mutex<HashSet<String>>,
+    info_hash: String,
+}
+
+impl<'a> Claim<'a> {
+    fn new(running: &'a Mutex<HashSet<String>>, info_hash: &str) -> Option<Self> {
+        running.lock().insert(info_hash.to_owned()).then(|| Self {
+            running,
+            info_hash: info_hash.to_owned(),
+        })
+    }
+}
+
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        self.running.lock().remove(&self.info_hash);
+    }
+}
+
+/// Absolute paths of the selected video files of a torrent
+fn video_files(details: &TorrentDetailsResponse) -> Vec<PathBuf> {
+    let root = PathBuf::from(&details.output_folder);
+    details
+        .files
+        .iter()
+        .flatten()
+        .filter(|f| f.included)
+        .map(|f| f.components.iter().fold(root.clone(), |p, c| p.join(c)))
+        .filter(|p| {
+            p.extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .is_some_and(|e| VIDEO_EXTENSIONS.contains(&e.as_str()))
+        })
+        .collect()
+}
+
+/// Library folder for a torrent: `Movies/Title (Year)/` or `Shows/Name/Season 01/`.
+/// Returns None for torrents without TMDB metadata.
+async fn library_folder_for(
+    app: &AppHandle,
+    config: &RqbitDesktopConfigPostProcess,
+    entry: &TorrentEntry,
+    torrent_name: Option<&str>,
+) -> Option<PathBuf> {
+    let tmdb_id = entry.tmdb_id?;
+    let fallback = torrent_name.unwrap_or("Unknown");
+    match entry.media_type.as_deref()? {
+        "movie" => {
+            let name = match crate::tmdb::get_tmdb_movie(app.clone(), tmdb_id).await {
+                Ok(movie) => match movie.release_date.as_deref().and_then(|d| d.get(..4)) {
+                    Some(year) => format!("{} ({})", movie.title, year),
+                    None => movie.title,
+                },
+                Err(e) => {
+                    warn!(
+                        "Failed to fetch movie {} for library naming: {}",
+                        tmdb_id, e
+                    );
+                    fallback.to_owned()
+                }
+            };
+            Some(
+                config
+                    .library_folder
+                    .join("Movies")
+                    .join(sanitize_file_name(&name)),
+            )
+        }
+        "tv" => {
+            let name = match crate::tmdb::get_tmdb_show(app.clone(), tmdb_id).await {
+                Ok(show) => show.name,
+                Err(e) => {
+                    warn!("Failed to fetch show {} for library naming: {}", tmdb_id, e);
+                    fallback.to_owned()
+                }
+            };
+            let show_folder = config
+                .library_folder
+                .join("Shows")
+                .join(sanitize_file_name(&name));
+            Some(match entry.episode_info {
+                Some((season, _)) => show_folder.join(format!("Season {:02}", season)),
+                None => show_folder,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replace characters that are not allowed in file names on common filesystems
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    sanitized.trim().trim_end_matches('.').to_string()
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::config::RqbitDesktopConfigProxy;
+use crate::error::CommandError;
+use crate::torrent_server::State;
+
+/// Responds with the caller's address, so a test shows whether traffic leaves through the proxy
+const TEST_URL: &str = "https://1.1.1.1/cdn-cgi/trace";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyTestResult {
+    pub latency_ms: u64,
+    /// Address the test server saw the request coming from
+    pub external_ip: Option<String>,
+}
+
+/// Apply the proxy config to HTTP clients created from now on
+pub(crate) fn set_config(state: &State, config: &RqbitDesktopConfigProxy) {
+    let proxy = config
+        .http_proxy_url()
+        .and_then(|url| match reqwest::Proxy::all(&url) {
+            Ok(proxy) => Some(proxy),
+            Err(e) => {
+                warn!("Invalid proxy config, web requests won't be proxied: {}", e);
+                None
+            }
+        });
+    if proxy.is_some() {
+        info!("Routing web requests through {:?} proxy", config.kind);
+    }
+    *state.http_proxy.write() = proxy;
+}
+
+fn builder_with(proxy: Option<reqwest::Proxy>) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder();
+    match proxy {
+        Some(proxy) => builder.proxy(proxy),
+        None => builder,
+    }
+}
+
+/// Client builder routed through the configured proxy. Use this for every outgoing request.
+pub fn client_builder(state: &State) -> reqwest::ClientBuilder {
+    builder_with(state.http_proxy.read().clone())
+}
+
+/// Client routed through the configured proxy
+pub fn http_client(state: &State) -> Result<reqwest::Client, CommandError> {
+    client_builder(state)
+        .build()
+        .map_err(|e| CommandError::request("Failed to build HTTP client", e))
+}
+
+/// Request the test URL through a proxy. Failures are prefixed with `context`.
+async fn test_url(context: &str, url: &str) -> Result<ProxyTestResult, CommandError> {
+    let proxy = reqwest::Proxy::all(url)
+        .map_err(|e| CommandError::InvalidInput(format!("{}: Invalid proxy: {}", context, e)))?;
+    let client = builder_with(Some(proxy))
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| {
+            CommandError::request(&format!("{}: Failed to build HTTP client", context), e)
+        })?;
+
+    let context = format!("{}: Request through proxy failed", context);
+    let start = Instant::now();
+    let body = client
+        .get(TEST_URL)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| CommandError::request(&context, e))?
+        .text()
+        .await
+        .map_err(|e| CommandError::request(&context, e))?;
+
+    Ok(ProxyTestResult {
+        latency_ms: start.elapsed().as_millis() as u64,
+        external_ip: body
+            .lines()
+            .find_map(|l| l.strip_prefix("ip="))
+            .map(str::to_owned),
+    })
+}
+
+/// Check that a proxy config works before saving it. Web requests and peer connections
+/// use different proxy URLs, so each configured path is tested.
+#[tauri::command]
+pub async fn test_proxy(proxy: RqbitDesktopConfigProxy) -> Result<ProxyTestResult, CommandError> {
+    proxy
+        .validate()
+        .map_err(|e| CommandError::InvalidInput(format!("{:#}", e)))?;
+    let web = proxy.http_proxy_url();
+    let peers = proxy.peer_proxy_url();
+    if web.is_none() && peers.is_none() {
+        return Err(CommandError::InvalidInput(
+            "No proxy configured".to_string(),
+        ));
+    }
+
+    let mut result = None;
+    if let Some(url) = &web {
+        result = Some(test_url("Web requests", url).await?);
+    }
+    if let Some(url) = &peers
+        && peers != web
+    {
+        let peer_result = test_url("Peer connections", url).await?;
+        result.get_or_insert(peer_result);
+    }
+    result.ok_or_else(|| CommandError::InvalidInput("No proxy configured".to_string()))
+}
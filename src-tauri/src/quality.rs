@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::QualityProfile;
+use crate::error::CommandError;
+use crate::torrent_search::TorrentResult;
+use crate::torrent_server::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "480p")]
+    P480,
+    #[serde(rename = "720p")]
+    P720,
+    #[serde(rename = "1080p")]
+    P1080,
+    #[serde(rename = "2160p")]
+    P2160,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Cam,
+    Telesync,
+    Dvd,
+    Hdtv,
+    WebRip,
+    WebDl,
+    BluRay,
+    Remux,
+}
+
+impl Source {
+    fn score(self) -> i32 {
+        match self {
+            Source::Cam | Source::Telesync => -50,
+            Source::Dvd => 5,
+            Source::Hdtv => 10,
+            Source::WebRip => 20,
+            Source::WebDl => 25,
+            Source::BluRay => 30,
+            Source::Remux => 35,
+        }
+    }
+}
+
+/// Attributes parsed from a release title
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParsedRelease {
+    pub resolution: Option<Resolution>,
+    pub source: Option<Source>,
+    pub codec: Option<String>,
+    /// "Dolby Vision", "HDR10+", "HDR10" or "HDR"
+    pub hdr: Option<String>,
+    pub audio: Option<String>,
+    pub languages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredTorrentResult {
+    #[serde(flatten)]
+    pub result: TorrentResult,
+    pub parsed: ParsedRelease,
+    pub score: i32,
+    /// Why the profile rejects this result, if it does
+    pub rejection: Option<String>,
+}
+
+/// Title lowercased with separators collapsed to single spaces and padded, so whole
+/// words and phrases can be found with `contains(" word ")`
+fn normalize(s: &str) -> String {
+    let words: Vec<String> = s
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+fn has_any(normalized: &str, phrases: &[&str]) -> bool {
+    phrases
+        .iter()
+        .any(|p| normalized.contains(&format!(" {} ", p)))
+}
+
+/// Whether a user-supplied term appears in the normalized title
+fn matches_term(normalized: &str, term: &str) -> bool {
+    let term = normalize(term);
+    !term.trim().is_empty() && normalized.contains(&term)
+}
+
+pub fn parse_release(title: &str) -> ParsedRelease {
+    let t = normalize(title);
+    let raw = title.to_lowercase();
+
+    let resolution = if has_any(&t, &["2160p", "4k", "uhd"]) {
+        Some(Resolution::P2160)
+    } else if has_any(&t, &["1080p", "1080i"]) {
+        Some(Resolution::P1080)
+    } else if has_any(&t, &["720p"]) {
+        Some(Resolution::P720)
+    } else if has_any(&t, &["480p", "576p", "sd"]) {
+        Some(Resolution::P480)
+    } else {
+        None
+    };
+
+    let source = if has_any(&t, &["remux"]) {
+        Some(Source::Remux)
+    } else if has_any(&t, &["bluray", "blu ray", "bdrip", "brrip", "bdremux"]) {
+        Some(Source::BluRay)
+    } else if has_any(&t, &["webrip", "web rip"]) {
+        Some(Source::WebRip)
+    } else if has_any(&t, &["web dl", "webdl", "web"]) {
+        Some(Source::WebDl)
+    } else if has_any(&t, &["hdtv", "pdtv"]) {
+        Some(Source::Hdtv)
+    } else if has_any(&t, &["dvdrip", "dvd", "dvd5", "dvd9"]) {
+        Some(Source::Dvd)
+    } else if has_any(&t, &["ts", "hdts", "telesync", "tc", "telecine"]) {
+        Some(Source::Telesync)
+    } else if has_any(&t, &["cam", "hdcam", "camrip"]) {
+        Some(Source::Cam)
+    } else {
+        None
+    };
+
+    let codec = if has_any(&t, &["x265", "h265", "h 265", "hevc"]) {
+        Some("x265")
+    } else if has_any(&t, &["x264", "h264", "h 264", "avc"]) {
+        Some("x264")
+    } else if has_any(&t, &["av1"]) {
+        Some("AV1")
+    } else if has_any(&t, &["xvid", "divx"]) {
+        Some("XviD")
+    } else {
+        None
+    };
+
+    let hdr = if has_any(&t, &["dv", "dovi", "dolby vision"]) {
+        Some("Dolby Vision")
+    } else if raw.contains("hdr10+") || has_any(&t, &["hdr10plus"]) {
+        Some("HDR10+")
+    } else if has_any(&t, &["hdr10"]) {
+        Some("HDR10")
+    } else if has_any(&t, &["hdr"]) {
+        Some("HDR")
+    } else {
+        None
+    };
+
+    let audio = if has_any(&t, &["atmos"]) {
+        Some("Atmos")
+    } else if has_any(&t, &["truehd"]) {
+        Some("TrueHD")
+    } else if has_any(&t, &["dts hd", "dts x", "dtshd", "dts"]) {
+        Some("DTS")
+    } else if raw.contains("dd+") || has_any(&t, &["ddp", "eac3", "ddp5", "ddp2"]) {
+        Some("DD+")
+    } else if has_any(&t, &["dd5", "ac3", "dd"]) {
+        Some("DD")
+    } else if has_any(&t, &["flac"]) {
+        Some("FLAC")
+    } else if has_any(&t, &["aac", "aac2", "aac5"]) {
+        Some("AAC")
+    } else if has_any(&t, &["opus"]) {
+        Some("Opus")
+    } else {
+        None
+    };
+
+    const LANGUAGES: &[(&str, &[&str])] = &[
+        ("Multi", &["multi", "multi subs"]),
+        ("English", &["english", "eng"]),
+        ("French", &["french", "truefrench", "vff", "vostfr"]),
+        ("German", &["german", "ger"]),
+        ("Spanish", &["spanish", "castellano", "latino", "esp"]),
+        ("Italian", &["italian", "ita"]),
+        ("Portuguese", &["portuguese", "dublado"]),
+        ("Russian", &["russian", "rus"]),
+        ("Hindi", &["hindi"]),
+        ("Japanese", &["japanese"]),
+        ("Korean", &["korean"]),
+    ];
+    let languages = LANGUAGES
+        .iter()
+        .filter(|(_, tags)| has_any(&t, tags))
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    ParsedRelease {
+        resolution,
+        source,
+        codec: codec.map(str::to_owned),
+        hdr: hdr.map(str::to_owned),
+        audio: audio.map(str::to_owned),
+        languages,
+    }
+}
+
+/// Score a result against a profile. `runtime_minutes` enables the size bounds.
+pub fn score_result(
+    result: TorrentResult,
+    profile: &QualityProfile,
+    runtime_minutes: Option<u32>,
+) -> ScoredTorrentResult {
+    let parsed = parse_release(&result.title);
+    let t = normalize(&result.title);
+    let mut score = 0;
+    let mut rejection = None;
+
+    if let Some(resolution) = parsed.resolution
+        && !profile.resolutions.is_empty()
+    {
+        match profile.resolutions.iter().position(|r| *r == resolution) {
+            Some(idx) => score += (profile.resolutions.len() - idx) as i32 * 20,
+            None => rejection = Some("Resolution not allowed by profile".to_owned()),
+        }
+    }
+    if let Some(source) = parsed.source {
+        score += source.score();
+        if profile.rejected_sources.contains(&source) {
+            rejection.get_or_insert(format!("Source {:?} not allowed by profile", source));
+        }
+    }
+    if let Some(codec) = &parsed.codec
+        && profile
+            .preferred_codecs
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(codec))
+    {
+        score += 10;
+    }
+    if profile.prefer_hdr && parsed.hdr.is_some() {
+        score += 15;
+    }
+    // Most releases don't tag their language, assume those are English
+    let untagged = ["English".to_owned()];
+    let languages = if parsed.languages.is_empty() {
+        &untagged[..]
+    } else {
+        &parsed.languages[..]
+    };
+    if profile
+        .preferred_languages
+        .iter()
+        .any(|pref| languages.iter().any(|l| l.eq_ignore_ascii_case(pref)))
+    {
+        score += 10;
+    }
+
+    score += profile
+        .preferred_terms
+        .iter()
+        .filter(|term| matches_term(&t, term))
+        .count() as i32
+        * 10;
+    if let Some(term) = profile
+        .required_terms
+        .iter()
+        .find(|term| !matches_term(&t, term))
+    {
+        rejection.get_or_insert(format!("Missing required term {:?}", term));
+    }
+    if let Some(term) = profile
+        .rejected_terms
+        .iter()
+        .find(|term| matches_term(&t, term))
+    {
+        rejection.get_or_insert(format!("Contains rejected term {:?}", term));
+    }
+
+    if let Some(runtime) = runtime_minutes.filter(|r| *r > 0)
+        && result.size > 0
+    {
+        let mb_per_minute = result.size as f64 / 1_000_000.0 / runtime as f64;
+        if profile
+            .min_mb_per_minute
+            .is_some_and(|min| mb_per_minute < min)
+        {
+            rejection.get_or_insert("Too small for the runtime".to_owned());
+        } else if profile
+            .max_mb_per_minute
+            .is_some_and(|max| mb_per_minute > max)
+        {
+            rejection.get_or_insert("Too large for the runtime".to_owned());
+        }
+    }
+
+    score += if result.seeders == 0 {
+        -30
+    } else {
+        ((result.seeders as f64 + 1.0).log2() * 4.0).min(40.0) as i32
+    };
+
+    ScoredTorrentResult {
+        result,
+        parsed,
+        score,
+        rejection,
+    }
+}
+
+/// Score results and sort them best first, accepted results before rejected ones
+pub fn rank_results(
+    results: Vec<TorrentResult>,
+    profile: &QualityProfile,
+    runtime_minutes: Option<u32>,
+) -> Vec<ScoredTorrentResult> {
+    let mut scored: Vec<ScoredTorrentResult> = results
+        .into_iter()
+        .map(|r| score_result(r, profile, runtime_minutes))
+        .collect();
+    scored.sort_by(|a, b| {
+        a.rejection
+            .is_some()
+            .cmp(&b.rejection.is_some())
+            .then(b.score.cmp(&a.score))
+            .then(b.result.seeders.cmp(&a.result.seeders))
+    });
+    scored
+}
+
+/// Score search results with a quality profile (the default one if unset).
+/// Rejected results are left out unless `include_rejected` is set.
+#[tauri::command]
+pub fn rank_torrent_results(
+    state: tauri::State<'_, State>,
+    results: Vec<TorrentResult>,
+    profile: Option<String>,
+    runtime_minutes: Option<u32>,
+    include_rejected: Option<bool>,
+) -> Result<Vec<ScoredTorrentResult>, CommandError> {
+    let profile = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.search.profile(profile.as_deref()))
+        .ok_or_else(|| CommandError::NotConfigured("Configuration not available".to_string()))?;
+
+    let mut ranked = rank_results(results, &profile, runtime_minutes);
+    if !include_rejected.unwrap_or(false) {
+        ranked.retain(|r| r.rejection.is_none());
+    }
+    Ok(ranked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, size: u64, seeders: u32) -> TorrentResult {
+        TorrentResult {
+            title: title.to_owned(),
+            size,
+            seeders,
+            peers: 0,
+            download_url: String::new(),
+            magnet_url: None,
+            indexer: "test".to_owned(),
+            publish_date: None,
+            info_hash: None,
+            indexers: vec!["test".to_owned()],
+        }
+    }
+
+    #[test]
+    fn test_parse_release() {
+        let parsed =
+            parse_release("Some.Movie.2023.2160p.UHD.BluRay.REMUX.HDR10+.HEVC.TrueHD.Atmos-GRP");
+        assert_eq!(parsed.resolution, Some(Resolution::P2160));
+        assert_eq!(parsed.source, Some(Source::Remux));
+        assert_eq!(parsed.codec.as_deref(), Some("x265"));
+        assert_eq!(parsed.hdr.as_deref(), Some("HDR10+"));
+        assert_eq!(parsed.audio.as_deref(), Some("Atmos"));
+
+        let parsed = parse_release("Show S01E02 1080p WEB-DL DDP5.1 H.264 MULTI");
+        assert_eq!(parsed.resolution, Some(Resolution::P1080));
+        assert_eq!(parsed.source, Some(Source::WebDl));
+        assert_eq!(parsed.codec.as_deref(), Some("x264"));
+        assert_eq!(parsed.audio.as_deref(), Some("DD+"));
+        assert_eq!(parsed.languages, vec!["Multi".to_owned()]);
+    }
+
+    #[test]
+    fn test_profile_rejections() {
+        let profile = QualityProfile::default();
+        let cam = score_result(
+            result("Movie 2024 HDCAM x264", 1_000_000_000, 50),
+            &profile,
+            None,
+        );
+        assert!(cam.rejection.is_some());
+
+        // Pre-release tags other than "cam" and "telesync" are rejected too
+        for title in ["Movie 2024 HDTS x264", "Movie 2024 TC", "Movie 2024 CAMRip"] {
+            let scored = score_result(result(title, 1_000_000_000, 50), &profile, None);
+            assert!(scored.rejection.is_some(), "{} was accepted", title);
+        }
+
+        // 100 MB for a 100 minute movie is below 5 MB per minute
+        let tiny = score_result(
+            result("Movie 1080p WEB-DL", 100_000_000, 50),
+            &profile,
+            Some(100),
+        );
+        assert!(tiny.rejection.is_some());
+
+        let sd = score_result(
+            result("Movie 480p DVDRip", 1_000_000_000, 50),
+            &profile,
+            None,
+        );
+        assert!(sd.rejection.is_some());
+    }
+
+    #[test]
+    fn test_ranking_prefers_quality_over_seeders() {
+        let profile = QualityProfile::default();
+        let ranked = rank_results(
+            vec![
+                result("Movie 720p HDTV x264", 2_000_000_000, 500),
+                result("Movie 1080p BluRay x265", 5_000_000_000, 40),
+            ],
+            &profile,
+            Some(120),
+        );
+        assert_eq!(ranked[0].result.title, "Movie 1080p BluRay x265");
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use http::StatusCode;
+use librqbit::{
+    ApiError, WithStatusError,
+    api::{EmptyJsonResponse, TorrentIdOrHash},
+};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::torrent_db::{QueuePriority, TorrentEntry};
+use crate::torrent_events::TorrentEvent;
+use crate::torrent_server::State;
+
+/// How often the queue is re-evaluated when nothing happens
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueItem {
+    pub torrent_id: usize,
+    pub info_hash: String,
+    pub name: Option<String>,
+    pub finished: bool,
+    pub running: bool,
+    /// Waiting for a free slot
+    pub queued: bool,
+    pub position: Option<u64>,
+    pub priority: QueuePriority,
+    pub force_start: bool,
+}
+
+/// Spawn the background task enforcing the max active downloads/seeds
+pub fn spawn_queue_manager(app: AppHandle) {
+    let mut events = app.state::<State>().events.subscribe();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                event = events.recv() => match event {
+                    // A slot may have been taken or freed
+                    Ok(
+                        TorrentEvent::Added { .. }
+                        | TorrentEvent::Completed { .. }
+                        | TorrentEvent::Errored { .. }
+                        | TorrentEvent::Removed { .. },
+                    ) => {}
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            }
+            process_queue(&app.state::<State>()).await;
+        }
+    });
+}
+
+/// Torrents taking part in the queue, in queue order
+fn queue_items(state: &State) -> Result<Vec<QueueItem>, ApiError> {
+    let api = state.api()?;
+    let mut items: Vec<(QueueItem, TorrentEntry)> = Vec::new();
+    for torrent in api.api_torrent_list().torrents {
+        let Some(id) = torrent.id else { continue };
+        let Ok(stats) = api.api_stats_v1(TorrentIdOrHash::Id(id)) else {
+            continue;
+        };
+        let entry = match state
+            .torrent_db
+            .get_or_create(id as i32, &torrent.info_hash)
+        {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!(error=?e, "Failed to update torrent database");
+                continue;
+            }
+        };
+        items.push((
+            QueueItem {
+                torrent_id: id,
+                info_hash: torrent.info_hash,
+                name: torrent.name,
+                finished: stats.finished,
+                running: stats.live.is_some(),
+                queued: entry.queue_paused,
+                position: entry.queue_position,
+                priority: entry.queue_priority,
+                force_start: entry.force_start,
+            },
+            entry,
+        ));
+    }
+    items.sort_by(|a, b| a.1.queue_key().cmp(&b.1.queue_key()));
+    Ok(items.into_iter().map(|(item, _)| item).collect())
+}
+
+/// Start or pause torrents so at most the configured number are active
+pub(crate) async fn process_queue(state: &State) {
+    let _guard = state.queue_lock.lock().await;
+    // Nothing may start while the kill switch holds torrents paused
+    if state.kill_switch.tripped() {
+        return;
+    }
+    let Some(config) = state.shared.read().as_ref().map(|s| s.config.queue.clone()) else {
+        return;
+    };
+    let items = match queue_items(state) {
+        Ok(items) => items,
+        Err(e) => {
+            warn!(error=?e, "Failed to list torrents for the queue");
+            return;
+        }
+    };
+
+    // Torrents paused by the user (or by seeding/storage limits) stay out of the queue
+    let (downloads, seeds): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .filter(|i| i.running || i.queued)
+        .partition(|i| !i.finished);
+
+    if !config.enabled {
+        for item in downloads.iter().chain(&seeds).filter(|i| i.queued) {
+            set_running(state, item, true).await;
+        }
+        return;
+    }
+
+    apply_limit(state, &downloads, config.max_active_downloads).await;
+    apply_limit(state, &seeds, config.max_active_seeds).await;
+}
+
+async fn apply_limit(state: &State, items: &[QueueItem], max_active: usize) {
+    let mut active = 0;
+    for item in items {
+        let run = item.force_start || max_active == 0 || active < max_active;
+        if run && !item.force_start {
+            active += 1;
+        }
+        if run != item.running {
+            set_running(state, item, run).await;
+        }
+    }
+}
+
+async fn set_running(state: &State, item: &QueueItem, run: bool) {
+    let Ok(api) = state.api() else { return };
+    let id = TorrentIdOrHash::Id(item.torrent_id);
+    let result = if run {
+        info!(
+            "Starting queued torrent {} ({:?})",
+            item.info_hash, item.name
+        );
+        api.api_torrent_action_start(id).await
+    } else {
+        info!("Queueing torrent {} ({:?})", item.info_hash, item.name);
+        api.api_torrent_action_pause(id).await
+    };
+    match result {
+        Ok(_) => {
+            if let Err(e) = state.torrent_db.set_queue_paused(&item.info_hash, !run) {
+                warn!(error=?e, "Failed to update queue state in database");
+            }
+        }
+        Err(e) => warn!(error=?e, "Failed to apply queue state"),
+    }
+}
+
+fn info_hash(state: &State, id: TorrentIdOrHash) -> Result<String, ApiError> {
+    let details = state.api()?.api_torrent_details(id)?;
+    let torrent_id = details.id.with_status_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Torrent ID not available",
+    )?;
+    state
+        .torrent_db
+        .get_or_create(torrent_id as i32, &details.info_hash)
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update database",
+        )?;
+    Ok(details.info_hash)
+}
+
+/// List all torrents in queue order
+pub fn torrent_queue_list(state: &State) -> Result<Vec<QueueItem>, ApiError> {
+    queue_items(state)
+}
+
+/// Move a torrent one place up (earlier) or down in the queue
+pub async fn torrent_queue_move(
+    state: &State,
+    id: TorrentIdOrHash,
+    up: bool,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let info_hash = info_hash(state, id)?;
+    // Only reorder among what the queue shows, or the move may look like it did nothing
+    let listed = queue_items(state)?
+        .into_iter()
+        .map(|i| i.info_hash)
+        .collect();
+    let moved = state
+        .torrent_db
+        .move_in_queue(&info_hash, up, &listed)
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update database",
+        )?;
+    if !moved {
+        return Err(ApiError::new_from_text(
+            StatusCode::BAD_REQUEST,
+            "The torrent is already first or last among torrents of its priority",
+        ));
+    }
+    process_queue(state).await;
+    Ok(EmptyJsonResponse {})
+}
+
+pub async fn torrent_queue_set_priority(
+    state: &State,
+    id: TorrentIdOrHash,
+    priority: QueuePriority,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let info_hash = info_hash(state, id)?;
+    state
+        .torrent_db
+        .set_queue_priority(&info_hash, priority)
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update database",
+        )?;
+    process_queue(state).await;
+    Ok(EmptyJsonResponse {})
+}
+
+/// Run a torrent regardless of the queue limits, or return it to the queue
+pub async fn torrent_queue_force_start(
+    state: &State,
+    id: TorrentIdOrHash,
+    force: bool,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let info_hash = info_hash(state, id)?;
+    state
+        .torrent_db
+        .set_force_start(&info_hash, force)
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update database",
+        )?;
+    let api = state.api()?;
+    if force && api.api_stats_v1(id)?.live.is_none() {
+        state.kill_switch.check_released()?;
+        api.api_torrent_action_start(id).await?;
+        if let Err(e) = state.torrent_db.set_queue_paused(&info_hash, false) {
+            warn!(error=?e, "Failed to update queue state in database");
+        }
+    }
+    process_queue(state).await;
+    Ok(EmptyJsonResponse {})
+}
@@ -0,0 +1,662 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use quick_xml::events::{BytesStart, Event};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::config::{QualityProfile, RssFeedConfig};
+use crate::error::CommandError;
+use crate::quality::score_result;
+use crate::torrent_search::TorrentResult;
+use crate::torrent_server::State;
+
+/// Name of the Tauri event carrying [`RssTorrentAdded`] payloads to the frontend
+pub const RSS_TORRENT_ADDED_EVENT: &str = "rss-torrent-added";
+
+/// How often feeds are checked for being due a poll
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Seen item IDs remembered per feed, oldest are forgotten first
+const MAX_SEEN_PER_FEED: usize = 2000;
+
+/// A feed item that links to a torrent
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RssItem {
+    /// `guid` or `id`, falling back to the link
+    pub id: String,
+    pub title: String,
+    /// `.torrent` or magnet link
+    pub url: String,
+    pub size: Option<u64>,
+    pub published: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RssItemPreview {
+    #[serde(flatten)]
+    pub item: RssItem,
+    /// Why the feed's filters skip this item, if they do
+    pub rejection: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RssTorrentAdded {
+    pub feed: String,
+    pub title: String,
+    pub info_hash: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RssFeedStatus {
+    pub name: String,
+    pub url: String,
+    /// Unix timestamp of the last poll
+    pub last_polled: Option<i64>,
+    pub items: usize,
+    pub added: usize,
+    pub error: Option<String>,
+}
+
+/// Item IDs already handled, per feed URL
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SeenItems {
+    feeds: HashMap<String, Vec<String>>,
+}
+
+/// Feed poller state, kept on [`State`]
+pub struct RssPoller {
+    seen: Mutex<SeenItems>,
+    /// Status of the last poll per feed URL
+    last_status: Mutex<HashMap<String, RssFeedStatus>>,
+    last_poll: Mutex<Option<Instant>>,
+    /// Only one poll runs at a time, whether scheduled or requested
+    poll_lock: tokio::sync::Mutex<()>,
+}
+
+impl Default for RssPoller {
+    fn default() -> Self {
+        Self {
+            seen: Mutex::new(load_seen().unwrap_or_else(|e| {
+                warn!(error=?e, "Failed to load seen RSS items");
+                SeenItems::default()
+            })),
+            last_status: Default::default(),
+            last_poll: Default::default(),
+            poll_lock: Default::default(),
+        }
+    }
+}
+
+fn seen_path() -> anyhow::Result<PathBuf> {
+    Ok(directories::ProjectDirs::from("com", "rqbit", "desktop")
+        .context("directories::ProjectDirs::from")?
+        .data_dir()
+        .join("rss_seen.json"))
+}
+
+fn load_seen() -> anyhow::Result<SeenItems> {
+    let path = seen_path()?;
+    if !path.exists() {
+        return Ok(SeenItems::default());
+    }
+    let file = File::open(&path).context("Failed to open seen RSS items file")?;
+    serde_json::from_reader(BufReader::new(file)).context("Failed to deserialize seen RSS items")
+}
+
+fn save_seen(seen: &SeenItems) -> anyhow::Result<()> {
+    let path = seen_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create data directory")?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .context("Failed to create temp seen RSS items file")?;
+    serde_json::to_writer(BufWriter::new(file), seen)
+        .context("Failed to serialize seen RSS items")?;
+    std::fs::rename(&tmp_path, &path).context("Failed to rename temp seen RSS items file")?;
+    Ok(())
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// Whether a plain link is a magnet or `.torrent` link
+fn is_torrent_link(url: &str) -> bool {
+    url.starts_with("magnet:")
+        || tauri::Url::parse(url).is_ok_and(|u| u.path().to_lowercase().ends_with(".torrent"))
+}
+
+/// Parse an RSS 2.0 or Atom feed. Items are only taken from enclosures, magnets,
+/// BitTorrent-typed links and `.torrent` links; other items are skipped.
+pub fn parse_feed(xml: &str) -> anyhow::Result<Vec<RssItem>> {
+    #[derive(Default)]
+    struct Entry {
+        fields: HashMap<String, String>,
+        enclosure: Option<String>,
+        /// Atom `<link href>`s as (rel, type, href)
+        links: Vec<(Option<String>, Option<String>, String)>,
+        size: Option<u64>,
+    }
+
+    let mut reader = quick_xml::Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut items = Vec::new();
+    let mut entry: Option<Entry> = None;
+    let mut current = String::new();
+
+    loop {
+        let event = reader.read_event().context("Invalid feed XML")?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                match (name.as_str(), entry.as_mut()) {
+                    ("item" | "entry", _) => entry = Some(Entry::default()),
+                    ("enclosure", Some(entry)) => {
+                        entry.enclosure = attr(e, b"url");
+                        entry.size = attr(e, b"length").and_then(|l| l.parse().ok());
+                    }
+                    ("link", Some(entry)) => {
+                        if let Some(href) = attr(e, b"href") {
+                            entry.links.push((attr(e, b"rel"), attr(e, b"type"), href));
+                        }
+                    }
+                    _ => {}
+                }
+                if matches!(event, Event::Start(_)) {
+                    current = name;
+                }
+            }
+            Event::Text(text) => {
+                if let Some(entry) = entry.as_mut() {
+                    let text = text.unescape().context("Invalid feed XML")?;
+                    entry
+                        .fields
+                        .entry(current.clone())
+                        .or_default()
+                        .push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(entry) = entry.as_mut() {
+                    entry
+                        .fields
+                        .entry(current.clone())
+                        .or_default()
+                        .push_str(&String::from_utf8_lossy(data));
+                }
+            }
+            Event::End(e) => {
+                current.clear();
+                if matches!(e.local_name().as_ref(), b"item" | b"entry")
+                    && let Some(entry) = entry.take()
+                {
+                    let is_torrent =
+                        |t: &Option<String>| t.as_deref().is_some_and(|t| t.contains("bittorrent"));
+                    // Other links usually point at the release's web page
+                    let atom_link = entry
+                        .links
+                        .iter()
+                        .find(|(rel, kind, href)| {
+                            is_torrent(kind)
+                                || rel.as_deref() == Some("enclosure")
+                                || is_torrent_link(href)
+                        })
+                        .map(|(_, _, href)| href.clone());
+                    let text_link = entry
+                        .fields
+                        .get("link")
+                        .map(|l| l.trim().to_owned())
+                        .filter(|l| is_torrent_link(l));
+                    // Prefer magnets, they don't need another request
+                    let url = entry
+                        .fields
+                        .get("magnetURI")
+                        .map(|m| m.trim().to_owned())
+                        .or(entry.enclosure)
+                        .or(atom_link)
+                        .or(text_link)
+                        .filter(|u| !u.is_empty());
+                    let Some(url) = url else { continue };
+                    let size = entry
+                        .fields
+                        .get("contentLength")
+                        .and_then(|s| s.trim().parse().ok())
+                        .or(entry.size);
+                    items.push(RssItem {
+                        id: entry
+                            .fields
+                            .get("guid")
+                            .or_else(|| entry.fields.get("id"))
+                            .map(|g| g.trim().to_owned())
+                            .unwrap_or_else(|| url.clone()),
+                        title: entry
+                            .fields
+                            .get("title")
+                            .map(|t| t.trim().to_owned())
+                            .unwrap_or_default(),
+                        url,
+                        size,
+                        published: entry
+                            .fields
+                            .get("pubDate")
+                            .or_else(|| entry.fields.get("published"))
+                            .or_else(|| entry.fields.get("updated"))
+                            .cloned(),
+                    });
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(items)
+}
+
+/// A feed's filter rules, compiled
+struct FeedFilter {
+    include: Option<Regex>,
+    exclude: Option<Regex>,
+    profile: Option<QualityProfile>,
+}
+
+impl FeedFilter {
+    fn new(feed: &RssFeedConfig, profile: Option<QualityProfile>) -> anyhow::Result<Self> {
+        let compile = |pattern: &Option<String>, name: &str| {
+            pattern
+                .as_deref()
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| RegexBuilder::new(p).case_insensitive(true).build())
+                .transpose()
+                .map_err(|e| CommandError::InvalidInput(format!("Invalid {} filter: {}", name, e)))
+        };
+        Ok(Self {
+            include: compile(&feed.include, "include")?,
+            exclude: compile(&feed.exclude, "exclude")?,
+            profile,
+        })
+    }
+
+    /// Why the item is skipped, or None if it should be added
+    fn rejection(&self, item: &RssItem) -> Option<String> {
+        if let Some(include) = &self.include
+            && !include.is_match(&item.title)
+        {
+            return Some("Doesn't match the include filter".to_owned());
+        }
+        if let Some(exclude) = &self.exclude
+            && exclude.is_match(&item.title)
+        {
+            return Some("Matches the exclude filter".to_owned());
+        }
+        let profile = self.profile.as_ref()?;
+        let result = TorrentResult {
+            title: item.title.clone(),
+            size: item.size.unwrap_or(0),
+            seeders: 0,
+            peers: 0,
+            download_url: item.url.clone(),
+            magnet_url: None,
+            indexer: String::new(),
+            publish_date: item.published.clone(),
+            info_hash: None,
+            indexers: Vec::new(),
+        };
+        score_result(result, profile, None).rejection
+    }
+}
+
+/// Season and episode from a release title, e.g. "S01E02" or "1x02"
+fn parse_episode(title: &str) -> Option<(u32, u32)> {
+    static EPISODE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)\bs(\d{1,2})[ ._-]?e(\d{1,3})\b|\b(\d{1,2})x(\d{2,3})\b").unwrap()
+    });
+    let caps = EPISODE.captures(title)?;
+    let season = caps.get(1).or_else(|| caps.get(3))?.as_str().parse().ok()?;
+    let episode = caps.get(2).or_else(|| caps.get(4))?.as_str().parse().ok()?;
+    Some((season, episode))
+}
+
+async fn fetch_feed(state: &State, url: &str) -> anyhow::Result<Vec<RssItem>> {
+    let client = crate::proxy::client_builder(state)
+        .timeout(Duration::from_secs(30))
+        .build()
+        .context("Failed to build HTTP client")?;
+    let body = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .context("Failed to fetch feed")?
+        .text()
+        .await
+        .context("Failed to read feed")?;
+    parse_feed(&body)
+}
+
+fn feed_filter(state: &State, feed: &RssFeedConfig) -> anyhow::Result<FeedFilter> {
+    let profile = match &feed.profile {
+        Some(name) => Some(
+            state
+                .shared
+                .read()
+                .as_ref()
+                .map(|s| s.config.search.profile(Some(name)))
+                .ok_or_else(|| {
+                    CommandError::NotConfigured("Configuration not available".to_string())
+                })?,
+        ),
+        None => None,
+    };
+    FeedFilter::new(feed, profile)
+}
+
+/// Poll one feed and add the new items passing its filters. The first poll of a
+/// feed only records the items already in it, so subscribing doesn't add its backlog.
+async fn poll_feed(app: &AppHandle, feed: &RssFeedConfig) -> anyhow::Result<RssFeedStatus> {
+    let state = app.state::<State>();
+    let filter = feed_filter(&state, feed)?;
+    let items = fetch_feed(&state, &feed.url).await?;
+    let mut status = RssFeedStatus {
+        name: feed.name.clone(),
+        url: feed.url.clone(),
+        last_polled: Some(chrono::Utc::now().timestamp()),
+        items: items.len(),
+        ..Default::default()
+    };
+
+    let (first_poll, new_items): (bool, Vec<RssItem>) = {
+        let seen = state.rss.seen.lock();
+        match seen.feeds.get(&feed.url) {
+            Some(ids) => (
+                false,
+                items
+                    .iter()
+                    .filter(|item| !ids.contains(&item.id))
+                    .cloned()
+                    .collect(),
+            ),
+            None => (true, Vec::new()),
+        }
+    };
+    if first_poll {
+        info!(
+            "First poll of feed {:?}, skipping {} existing items",
+            feed.name,
+            items.len()
+        );
+    }
+
+    for item in &new_items {
+        if let Some(reason) = filter.rejection(item) {
+            info!(
+                "Skipping {:?} from feed {:?}: {}",
+                item.title, feed.name, reason
+            );
+            continue;
+        }
+        // Items are marked seen even if adding fails, a broken link would fail every poll
+        match crate::torrent_server::torrent_create_from_url(&state, item.url.clone(), None).await {
+            Ok(response) => {
+                info!("Added {:?} from feed {:?}", item.title, feed.name);
+                let info_hash = response.details.info_hash.clone();
+                if let (Some(tmdb_id), Some(id)) = (feed.tmdb_id, response.id) {
+                    let episode_info = match feed.media_type.as_deref() {
+                        Some("tv") => parse_episode(&item.title).map(|(s, e)| (s as i32, e as i32)),
+                        _ => None,
+                    };
+                    if let Err(e) = state.torrent_db.upsert_torrent(
+                        id as i32,
+                        info_hash.clone(),
+                        Some(tmdb_id),
+                        feed.media_type.clone(),
+                        episode_info,
+                    ) {
+                        warn!(error=?e, "Failed to set torrent metadata");
+                    }
+                }
+                status.added += 1;
+                let added = RssTorrentAdded {
+                    feed: feed.name.clone(),
+                    title: item.title.clone(),
+                    info_hash,
+                };
+                if let Err(e) = app.emit(RSS_TORRENT_ADDED_EVENT, &added) {
+                    warn!("Failed to emit RSS torrent added: {}", e);
+                }
+            }
+            Err(e) => warn!(
+                "Failed to add {:?} from feed {:?}: {:?}",
+                item.title, feed.name, e
+            ),
+        }
+    }
+
+    {
+        let mut seen = state.rss.seen.lock();
+        let ids = seen.feeds.entry(feed.url.clone()).or_default();
+        for item in &items {
+            if !ids.contains(&item.id) {
+                ids.push(item.id.clone());
+            }
+        }
+        if ids.len() > MAX_SEEN_PER_FEED {
+            let excess = ids.len() - MAX_SEEN_PER_FEED;
+            ids.drain(..excess);
+        }
+        if let Err(e) = save_seen(&seen) {
+            warn!(error=?e, "Failed to save seen RSS items");
+        }
+    }
+    Ok(status)
+}
+
+/// Poll every enabled feed
+async fn poll_all(app: &AppHandle) -> Vec<RssFeedStatus> {
+    let state = app.state::<State>();
+    let _guard = state.rss.poll_lock.lock().await;
+    let feeds = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.rss.feeds.clone())
+        .unwrap_or_default();
+
+    let mut statuses = Vec::new();
+    for feed in feeds.into_iter().filter(|f| f.enabled) {
+        let status = match poll_feed(app, &feed).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Failed to poll feed {:?}: {:#}", feed.name, e);
+                RssFeedStatus {
+                    name: feed.name.clone(),
+                    url: feed.url.clone(),
+                    last_polled: Some(chrono::Utc::now().timestamp()),
+                    error: Some(format!("{:#}", e)),
+                    ..Default::default()
+                }
+            }
+        };
+        statuses.push(status);
+    }
+    *state.rss.last_poll.lock() = Some(Instant::now());
+
+    let mut last = state.rss.last_status.lock();
+    last.clear();
+    last.extend(statuses.iter().map(|s| (s.url.clone(), s.clone())));
+    statuses
+}
+
+/// Spawn the task that polls feeds on the configured interval
+pub fn spawn_rss_poller(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(config) = app
+                .state::<State>()
+                .shared
+                .read()
+                .as_ref()
+                .map(|s| s.config.rss.clone())
+            else {
+                continue;
+            };
+            let due = app
+                .state::<State>()
+                .rss
+                .last_poll
+                .lock()
+                .is_none_or(|at| at.elapsed() >= config.poll_interval);
+            if config.enabled && due {
+                poll_all(&app).await;
+            }
+        }
+    });
+}
+
+/// Result of the last poll of each feed
+#[tauri::command]
+pub fn get_rss_status(state: tauri::State<'_, State>) -> Vec<RssFeedStatus> {
+    state.rss.last_status.lock().values().cloned().collect()
+}
+
+/// Poll all enabled feeds now
+#[tauri::command]
+pub async fn refresh_rss_feeds(app: AppHandle) -> Result<Vec<RssFeedStatus>, CommandError> {
+    Ok(poll_all(&app).await)
+}
+
+/// Fetch a feed and show which items its filters would add, without adding anything
+#[tauri::command]
+pub async fn preview_rss_feed(
+    state: tauri::State<'_, State>,
+    feed: RssFeedConfig,
+) -> Result<Vec<RssItemPreview>, CommandError> {
+    let filter = feed_filter(&state, &feed)?;
+    let items = fetch_feed(&state, &feed.url).await?;
+    Ok(items
+        .into_iter()
+        .map(|item| RssItemPreview {
+            rejection: filter.rejection(&item),
+            item,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss() {
+        let xml = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:torrent="http://xmlns.ezrss.it/0.1/">
+  <channel>
+    <title>Feed</title>
+    <item>
+      <title>Show.S01E02.1080p.WEB-DL</title>
+      <guid isPermaLink="false">abc</guid>
+      <link>https://example.com/details/1</link>
+      <enclosure url="https://example.com/1.torrent" length="123" type="application/x-bittorrent"/>
+      <pubDate>Mon, 01 Jan 2024 00:00:00 +0000</pubDate>
+    </item>
+    <item>
+      <title><![CDATA[Other Release]]></title>
+      <torrent:magnetURI><![CDATA[magnet:?xt=urn:btih:abc]]></torrent:magnetURI>
+      <torrent:contentLength>456</torrent:contentLength>
+    </item>
+    <item>
+      <title>Web page only</title>
+      <link>https://example.com/details/3</link>
+    </item>
+    <item>
+      <title>Plain torrent link</title>
+      <link>https://example.com/dl/4.torrent?passkey=x</link>
+    </item>
+    <item><title>No link</title></item>
+  </channel>
+</rss>"#;
+        let items = parse_feed(xml).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].id, "abc");
+        assert_eq!(items[0].url, "https://example.com/1.torrent");
+        assert_eq!(items[0].size, Some(123));
+        assert_eq!(items[1].title, "Other Release");
+        assert_eq!(items[1].url, "magnet:?xt=urn:btih:abc");
+        assert_eq!(items[1].id, "magnet:?xt=urn:btih:abc");
+        assert_eq!(items[1].size, Some(456));
+        assert_eq!(items[2].url, "https://example.com/dl/4.torrent?passkey=x");
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let xml = r#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <entry>
+    <id>urn:1</id>
+    <title>Movie 2024 2160p</title>
+    <link rel="alternate" href="https://example.com/page"/>
+    <link rel="enclosure" type="application/x-bittorrent" href="https://example.com/m.torrent"/>
+    <updated>2024-01-01T00:00:00Z</updated>
+  </entry>
+  <entry>
+    <id>urn:2</id>
+    <title>Page only</title>
+    <link href="https://example.com/page/2"/>
+  </entry>
+</feed>"#;
+        let items = parse_feed(xml).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "urn:1");
+        assert_eq!(items[0].url, "https://example.com/m.torrent");
+        assert_eq!(items[0].published.as_deref(), Some("2024-01-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let feed = RssFeedConfig {
+            include: Some("^show[ .]name".to_owned()),
+            exclude: Some(r"\b720p\b".to_owned()),
+            ..Default::default()
+        };
+        let filter = FeedFilter::new(&feed, Some(QualityProfile::default())).unwrap();
+        let item = |title: &str| RssItem {
+            id: title.to_owned(),
+            title: title.to_owned(),
+            url: "magnet:?xt=urn:btih:abc".to_owned(),
+            size: None,
+            published: None,
+        };
+        assert_eq!(filter.rejection(&item("Show.Name.S01E02.1080p.WEB")), None);
+        assert!(filter.rejection(&item("Other.S01E02.1080p")).is_some());
+        assert!(filter.rejection(&item("Show.Name.S01E02.720p")).is_some());
+        // Rejected by the quality profile
+        assert!(filter.rejection(&item("Show Name 2024 HDCAM")).is_some());
+    }
+
+    #[test]
+    fn test_parse_episode() {
+        assert_eq!(parse_episode("Show.Name.S01E02.1080p"), Some((1, 2)));
+        assert_eq!(parse_episode("Show Name 3x10 HDTV"), Some((3, 10)));
+        assert_eq!(parse_episode("Movie.2024.1080p"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::{path::Path, time::Duration};
+
+use http::StatusCode;
+use librqbit::{
+    ApiError, WithStatusError,
+    api::{EmptyJsonResponse, TorrentIdOrHash, TorrentStats},
+};
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+
+use crate::config::{SeedingAction, SeedingPolicy};
+use crate::torrent_events::TorrentEvent;
+use crate::torrent_server::{self, State};
+
+/// How often seeding limits are evaluated
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Spawn the background task that records completion times and applies seeding policies
+pub fn spawn_seeding_enforcer(app: AppHandle) {
+    let mut events = app.state::<State>().events.subscribe();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    enforce_policies(&app.state::<State>()).await;
+                }
+                event = events.recv() => match event {
+                    Ok(TorrentEvent::Completed { info_hash, .. }) => {
+                        let state = app.state::<State>();
+                        let now = chrono::Utc::now().timestamp();
+                        if let Err(e) = state.torrent_db.set_completed_at(&info_hash, now) {
+                            warn!(error=?e, "Failed to record torrent completion time");
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    });
+}
+
+/// Check every seeding torrent against its policy and apply the configured action
+async fn enforce_policies(state: &State) {
+    let Some(global) = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.seeding.clone())
+    else {
+        return;
+    };
+    let Ok(api) = state.api() else {
+        return;
+    };
+    let now = chrono::Utc::now().timestamp();
+
+    for torrent in api.api_torrent_list().torrents {
+        let Some(id) = torrent.id else { continue };
+        let Ok(stats) = api.api_stats_v1(TorrentIdOrHash::Id(id)) else {
+            continue;
+        };
+        // Only completed torrents that are still running are seeding
+        if !stats.finished || stats.live.is_none() {
+            continue;
+        }
+
+        let entry = match state
+            .torrent_db
+            .get_or_create(id as i32, &torrent.info_hash)
+        {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!(error=?e, "Failed to update torrent database");
+                continue;
+            }
+        };
+        if entry.seeding_stopped {
+            continue;
+        }
+        let completed_at = match entry.completed_at {
+            Some(t) => t,
+            None => {
+                // Completed while the app wasn't watching, start the clock now
+                if let Err(e) = state.torrent_db.set_completed_at(&torrent.info_hash, now) {
+                    warn!(error=?e, "Failed to record torrent completion time");
+                }
+                now
+            }
+        };
+
+        let policy = entry.seeding_policy.unwrap_or_else(|| global.clone());
+        if policy.is_unlimited() {
+            continue;
+        }
+        let Some(reason) = limit_reached(
+            &policy,
+            &stats,
+            now - completed_at,
+            Path::new(&torrent.output_folder),
+        ) else {
+            continue;
+        };
+
+        info!(
+            "Seeding limit reached for {} ({}), applying {:?}",
+            torrent.info_hash, reason, policy.action
+        );
+        let result = match policy.action {
+            SeedingAction::Pause => {
+                torrent_server::torrent_action_pause(state, TorrentIdOrHash::Id(id)).await
+            }
+            SeedingAction::Forget => {
+                torrent_server::torrent_action_forget(state, TorrentIdOrHash::Id(id)).await
+            }
+            SeedingAction::DeleteWithFiles => {
+                torrent_server::torrent_action_delete(state, TorrentIdOrHash::Id(id)).await
+            }
+        };
+        match result {
+            Ok(_) => {
+                if let Err(e) = state
+                    .torrent_db
+                    .set_seeding_stopped(&torrent.info_hash, true)
+                {
+                    warn!(error=?e, "Failed to record seeding stop");
+                }
+            }
+            Err(e) => warn!(error=?e, "Failed to apply seeding action"),
+        }
+    }
+}
+
+/// Returns a description of the first limit the torrent has reached, if any
+fn limit_reached(
+    policy: &SeedingPolicy,
+    stats: &TorrentStats,
+    seeding_secs: i64,
+    output_folder: &Path,
+) -> Option<String> {
+    if let Some(limit) = policy.ratio_limit
+        && stats.progress_bytes > 0
+    {
+        let ratio = stats.uploaded_bytes as f64 / stats.progress_bytes as f64;
+        if ratio >= limit {
+            return Some(format!("ratio {:.2} >= {:.2}", ratio, limit));
+        }
+    }
+
+    if let Some(hours) = policy.time_limit_hours
+        && i64::try_from(hours.saturating_mul(3600)).is_ok_and(|limit| seeding_secs >= limit)
+    {
+        return Some(format!("seeded for {} hours", hours));
+    }
+
+    if let Some(min_free) = policy.min_free_disk_bytes {
+        match crate::disk::available_space(output_folder) {
+            Ok(free) if free < min_free => {
+                return Some(format!("{} bytes free < {}", free, min_free));
+            }
+            Ok(_) => {}
+            Err(e) => warn!(error=?e, "Failed to check free disk space"),
+        }
+    }
+
+    None
+}
+
+/// Get the per-torrent seeding policy, if one overrides the global policy
+pub fn get_torrent_seeding_policy(
+    state: &State,
+    id: TorrentIdOrHash,
+) -> Result<Option<SeedingPolicy>, ApiError> {
+    let details = state.api()?.api_torrent_details(id)?;
+    Ok(state
+        .torrent_db
+        .get_by_hash(&details.info_hash)
+        .and_then(|e| e.seeding_policy))
+}
+
+/// Set or clear (with None) the per-torrent seeding policy
+pub fn set_torrent_seeding_policy(
+    state: &State,
+    id: TorrentIdOrHash,
+    policy: Option<SeedingPolicy>,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let details = state.api()?.api_torrent_details(id)?;
+    let torrent_id = details.id.with_status_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Torrent ID not available",
+    )?;
+
+    state
+        .torrent_db
+        .upsert_torrent(
+            torrent_id as i32,
+            details.info_hash.clone(),
+            None,
+            None,
+            None,
+        )
+        .and_then(|_| {
+            state
+                .torrent_db
+                .set_seeding_policy(&details.info_hash, policy)
+        })
+        .map_err(|e| {
+            warn!(error=?e, "Failed to set seeding policy in database");
+            e
+        })
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to update database",
+        )?;
+
+    Ok(EmptyJsonResponse {})
+}
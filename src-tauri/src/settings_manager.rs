@@ -4,12 +4,32 @@ use std::fs;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
+use crate::error::CommandError;
+use crate::torrent_server::State;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub nacho_server_url: Option<String>,
     pub nacho_auth_token: Option<String>,
 }
 
+/// A Nacho Server account, as returned by `/api/user`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NachoUserInfo {
+    pub id: String,
+    pub username: String,
+    #[serde(default)]
+    pub is_admin: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct NachoUserResponse {
+    #[serde(default)]
+    success: bool,
+    user: Option<NachoUserInfo>,
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -31,36 +51,44 @@ fn get_settings_path(app: &AppHandle) -> Result<PathBuf> {
 }
 
 #[tauri::command]
-pub fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
-    let settings_path = get_settings_path(&app).map_err(|e| e.to_string())?;
+pub fn get_settings(app: AppHandle) -> Result<AppSettings, CommandError> {
+    let settings_path =
+        get_settings_path(&app).map_err(|e| CommandError::Internal(format!("{:#}", e)))?;
 
     if !settings_path.exists() {
         return Ok(AppSettings::default());
     }
 
     let contents = fs::read_to_string(&settings_path)
-        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+        .map_err(|e| CommandError::Internal(format!("Failed to read settings file: {}", e)))?;
 
-    let settings: AppSettings =
-        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    let settings: AppSettings = serde_json::from_str(&contents)
+        .map_err(|e| CommandError::Internal(format!("Failed to parse settings: {}", e)))?;
 
     Ok(settings)
 }
 
 #[tauri::command]
-pub fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
-    let settings_path = get_settings_path(&app).map_err(|e| e.to_string())?;
+pub fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), CommandError> {
+    let settings_path =
+        get_settings_path(&app).map_err(|e| CommandError::Internal(format!("{:#}", e)))?;
 
     let json = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+        .map_err(|e| CommandError::Internal(format!("Failed to serialize settings: {}", e)))?;
 
-    fs::write(&settings_path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+    fs::write(&settings_path, json)
+        .map_err(|e| CommandError::Internal(format!("Failed to write settings file: {}", e)))?;
+    // Results from another Nacho Server or account must not be reused
+    app.state::<State>().search_cache.invalidate();
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn update_nacho_server_url(app: AppHandle, url: Option<String>) -> Result<AppSettings, String> {
+pub fn update_nacho_server_url(
+    app: AppHandle,
+    url: Option<String>,
+) -> Result<AppSettings, CommandError> {
     let mut settings = get_settings(app.clone())?;
     settings.nacho_server_url = url;
     save_settings(app, settings.clone())?;
@@ -68,7 +96,7 @@ pub fn update_nacho_server_url(app: AppHandle, url: Option<String>) -> Result<Ap
 }
 
 #[tauri::command]
-pub fn get_nacho_server_url(app: AppHandle) -> Result<Option<String>, String> {
+pub fn get_nacho_server_url(app: AppHandle) -> Result<Option<String>, CommandError> {
     let settings = get_settings(app)?;
     Ok(settings.nacho_server_url)
 }
@@ -77,7 +105,7 @@ pub fn get_nacho_server_url(app: AppHandle) -> Result<Option<String>, String> {
 pub fn update_nacho_auth_token(
     app: AppHandle,
     auth_token: Option<String>,
-) -> Result<AppSettings, String> {
+) -> Result<AppSettings, CommandError> {
     let mut settings = get_settings(app.clone())?;
     settings.nacho_auth_token = auth_token;
     save_settings(app, settings.clone())?;
@@ -85,7 +113,64 @@ pub fn update_nacho_auth_token(
 }
 
 #[tauri::command]
-pub fn get_nacho_auth_token(app: AppHandle) -> Result<Option<String>, String> {
+pub fn get_nacho_auth_token(app: AppHandle) -> Result<Option<String>, CommandError> {
     let settings = get_settings(app)?;
     Ok(settings.nacho_auth_token)
 }
+
+/// Get the user a Nacho Server token belongs to, which also verifies the token
+pub async fn fetch_nacho_user(
+    app: &AppHandle,
+    server_url: &str,
+    auth_token: &str,
+) -> Result<NachoUserInfo, CommandError> {
+    let client = crate::proxy::http_client(&app.state::<State>())?;
+    let response = client
+        .get(format!("{}/api/user", server_url.trim_end_matches('/')))
+        .header("X-Nacho-Auth", auth_token)
+        .send()
+        .await
+        .map_err(|e| CommandError::request("Failed to reach Nacho Server", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(CommandError::from_status(
+            status,
+            format!("Nacho Server rejected the token ({})", status),
+        ));
+    }
+    let body: NachoUserResponse = response
+        .json()
+        .await
+        .map_err(|e| CommandError::request("Failed to read Nacho Server response", e))?;
+    match body.user {
+        Some(user) if body.success => Ok(user),
+        _ => Err(CommandError::InvalidResponse(
+            "Nacho Server didn't return a user".to_string(),
+        )),
+    }
+}
+
+/// Get the user for the saved Nacho Server token, or for `server_url` and
+/// `auth_token` when both are given, e.g. to verify them before saving
+#[tauri::command]
+pub async fn get_nacho_user(
+    app: AppHandle,
+    server_url: Option<String>,
+    auth_token: Option<String>,
+) -> Result<NachoUserInfo, CommandError> {
+    let (server_url, auth_token) = match (server_url, auth_token) {
+        (Some(url), Some(token)) => (url, token),
+        _ => {
+            let settings = get_settings(app.clone())?;
+            match (settings.nacho_server_url, settings.nacho_auth_token) {
+                (Some(url), Some(token)) if !url.is_empty() && !token.is_empty() => (url, token),
+                _ => {
+                    return Err(CommandError::NotConfigured(
+                        "Nacho Server not configured. Please set it in Settings.".to_string(),
+                    ));
+                }
+            }
+        }
+    };
+    fetch_nacho_user(&app, &server_url, &auth_token).await
+}
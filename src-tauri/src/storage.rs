@@ -0,0 +1,375 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use librqbit::api::{TorrentDetailsResponse, TorrentIdOrHash};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::config::RqbitDesktopConfigStorage;
+use crate::error::CommandError;
+use crate::torrent_db::TorrentEntry;
+use crate::torrent_server::{self, State};
+use crate::transmux;
+use crate::watch_history::{self, WatchHistoryData};
+
+/// Name of the Tauri event carrying [`StorageEvent`] payloads to the frontend
+pub const STORAGE_EVENT: &str = "storage-event";
+
+/// How often quota and watched-media cleanup run
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Space used by a single torrent
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageTorrent {
+    pub torrent_id: usize,
+    pub info_hash: String,
+    pub name: Option<String>,
+    pub episode_info: Option<(i32, i32)>,
+    pub finished: bool,
+    /// Downloaded torrent payload
+    pub payload_bytes: u64,
+    /// Transmux outputs and extracted subtitles
+    pub derived_bytes: u64,
+}
+
+/// Space used by a library item (all torrents for one TMDB title)
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageItem {
+    pub tmdb_id: Option<u64>,
+    pub media_type: Option<String>,
+    pub total_bytes: u64,
+    pub torrents: Vec<StorageTorrent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub used_bytes: u64,
+    pub quota_bytes: Option<u64>,
+    /// Free space on the disk holding the default download folder
+    pub available_bytes: Option<u64>,
+    pub items: Vec<StorageItem>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupSummary {
+    pub removed: Vec<StorageTorrent>,
+    pub freed_bytes: u64,
+    pub over_quota: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StorageEvent {
+    /// Watched media was removed
+    Cleaned {
+        removed: Vec<StorageTorrent>,
+        freed_bytes: u64,
+    },
+    /// Usage is above quota; unfinished downloads were paused
+    QuotaExceeded { used_bytes: u64, quota_bytes: u64 },
+}
+
+/// Get space used per library item, including transmux outputs
+#[tauri::command]
+pub fn get_storage_report(state: tauri::State<'_, State>) -> Result<StorageReport, CommandError> {
+    build_report(&state)
+        .map_err(|e| CommandError::from(e).context("Failed to build storage report"))
+}
+
+/// Run watched-media cleanup, if enabled, and quota enforcement now
+#[tauri::command]
+pub async fn run_storage_cleanup(app: AppHandle) -> Result<CleanupSummary, CommandError> {
+    run_cleanup(&app)
+        .await
+        .map_err(|e| CommandError::from(e).context("Storage cleanup failed"))
+}
+
+/// Spawn the background task enforcing the storage configuration
+pub fn spawn_storage_manager(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_cleanup(&app).await {
+                warn!("Storage cleanup failed: {:#}", e);
+            }
+        }
+    });
+}
+
+fn file_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Files generated from a torrent: transmux outputs next to its files, including
+/// ones made by the transmux command, and what post-processing recorded.
+/// These are removed together with the torrent.
+pub(crate) fn derived_files(
+    details: &TorrentDetailsResponse,
+    entry: Option<&TorrentEntry>,
+) -> Vec<PathBuf> {
+    let folder = Path::new(&details.output_folder);
+    let files: HashSet<PathBuf> = details
+        .files
+        .iter()
+        .flatten()
+        .map(|f| {
+            f.components
+                .iter()
+                .fold(folder.to_owned(), |p, c| p.join(c))
+        })
+        .collect();
+    let mut derived: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|f| transmux::generate_output_path(f).ok())
+        .chain(
+            entry
+                .into_iter()
+                .flat_map(|e| e.derived_files.iter().cloned()),
+        )
+        .filter(|p| !files.contains(p) && p.exists())
+        .collect();
+    derived.sort();
+    derived.dedup();
+    derived
+}
+
+fn build_report(state: &State) -> anyhow::Result<StorageReport> {
+    let (config, download_folder) = {
+        let g = state.shared.read();
+        let shared = g
+            .as_ref()
+            .ok_or_else(|| CommandError::NotConfigured("not configured".to_string()))?;
+        (
+            shared.config.storage.clone(),
+            shared.config.default_download_location.clone(),
+        )
+    };
+    let api = state.api()?;
+
+    let mut items: BTreeMap<(Option<u64>, Option<String>), StorageItem> = BTreeMap::new();
+    for torrent in api.api_torrent_list().torrents {
+        let Some(id) = torrent.id else { continue };
+        let Ok(stats) = api.api_stats_v1(TorrentIdOrHash::Id(id)) else {
+            continue;
+        };
+        let entry: Option<TorrentEntry> = state.torrent_db.get_by_hash(&torrent.info_hash);
+        let derived_bytes = match api.api_torrent_details(TorrentIdOrHash::Id(id)) {
+            Ok(details) => derived_files(&details, entry.as_ref())
+                .iter()
+                .map(|p| file_size(p))
+                .sum(),
+            Err(_) => 0,
+        };
+        let (tmdb_id, media_type, episode_info) = match &entry {
+            Some(e) => (e.tmdb_id, e.media_type.clone(), e.episode_info),
+            None => (None, None, None),
+        };
+
+        let item = items
+            .entry((tmdb_id, media_type.clone()))
+            .or_insert_with(|| StorageItem {
+                tmdb_id,
+                media_type,
+                total_bytes: 0,
+                torrents: Vec::new(),
+            });
+        item.total_bytes += stats.progress_bytes + derived_bytes;
+        item.torrents.push(StorageTorrent {
+            torrent_id: id,
+            info_hash: torrent.info_hash,
+            name: torrent.name,
+            episode_info,
+            finished: stats.finished,
+            payload_bytes: stats.progress_bytes,
+            derived_bytes,
+        });
+    }
+
+    let mut items: Vec<StorageItem> = items.into_values().collect();
+    items.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes));
+
+    Ok(StorageReport {
+        used_bytes: items.iter().map(|i| i.total_bytes).sum(),
+        quota_bytes: config.quota_bytes,
+        available_bytes: crate::disk::available_space(&download_folder).ok(),
+        items,
+    })
+}
+
+/// Timestamp a torrent's title was marked watched, if it was
+fn watched_at(
+    history: &WatchHistoryData,
+    item: &StorageItem,
+    torrent: &StorageTorrent,
+) -> Option<i64> {
+    let tmdb_id = item.tmdb_id?;
+    let timestamp = match (item.media_type.as_deref()?, torrent.episode_info) {
+        ("movie", _) => history
+            .movies
+            .iter()
+            .find(|m| m.tmdb_id == tmdb_id)
+            .map(|m| m.timestamp_watched.as_str())?,
+        ("tv", Some((season, episode))) => history
+            .episodes
+            .iter()
+            .find(|e| {
+                e.tmdb_id == tmdb_id && e.season as i32 == season && e.episode as i32 == episode
+            })
+            .map(|e| e.timestamp_watched.as_str())?,
+        _ => return None,
+    };
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+async fn run_cleanup(app: &AppHandle) -> anyhow::Result<CleanupSummary> {
+    let state = app.state::<State>();
+    let config: RqbitDesktopConfigStorage = match state.shared.read().as_ref() {
+        Some(shared) => shared.config.storage.clone(),
+        None => return Ok(CleanupSummary::default()),
+    };
+    let mut report = build_report(&state)?;
+    let mut summary = CleanupSummary::default();
+
+    if config.auto_cleanup_watched {
+        match watch_history::get_watch_history(app.clone(), None, None).await {
+            Ok(history) => {
+                let cutoff =
+                    chrono::Utc::now().timestamp() - config.watched_grace_days as i64 * 86400;
+                for item in &report.items {
+                    for torrent in &item.torrents {
+                        let Some(watched) = watched_at(&history.data, item, torrent) else {
+                            continue;
+                        };
+                        if watched > cutoff {
+                            continue;
+                        }
+                        info!(
+                            "Removing watched torrent {} ({:?})",
+                            torrent.info_hash, torrent.name
+                        );
+                        match torrent_server::torrent_action_delete(
+                            &state,
+                            TorrentIdOrHash::Id(torrent.torrent_id),
+                        )
+                        .await
+                        {
+                            Ok(_) => {
+                                summary.freed_bytes +=
+                                    torrent.payload_bytes + torrent.derived_bytes;
+                                summary.removed.push(torrent.clone());
+                            }
+                            Err(e) => warn!(error=?e, "Failed to remove watched torrent"),
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Skipping watched cleanup, history unavailable: {}", e),
+        }
+
+        if !summary.removed.is_empty() {
+            emit(
+                app,
+                StorageEvent::Cleaned {
+                    removed: summary.removed.clone(),
+                    freed_bytes: summary.freed_bytes,
+                },
+            );
+            report = build_report(&state)?;
+        }
+    }
+
+    match config.quota_bytes {
+        Some(quota) if report.used_bytes > quota => {
+            summary.over_quota = true;
+            warn!(
+                "Storage quota exceeded: {} of {} bytes used, pausing downloads",
+                report.used_bytes, quota
+            );
+            for torrent in report.items.iter().flat_map(|i| &i.torrents) {
+                if torrent.finished {
+                    continue;
+                }
+                match torrent_server::torrent_action_pause(
+                    &state,
+                    TorrentIdOrHash::Id(torrent.torrent_id),
+                )
+                .await
+                {
+                    Ok(_) => {
+                        // Persisted, librqbit keeps the torrent paused across restarts
+                        let marked = state
+                            .torrent_db
+                            .get_or_create(torrent.torrent_id as i32, &torrent.info_hash)
+                            .and_then(|_| {
+                                state.torrent_db.set_quota_paused(&torrent.info_hash, true)
+                            });
+                        if let Err(e) = marked {
+                            warn!(error=?e, "Failed to record torrent paused by the storage quota");
+                        }
+                    }
+                    Err(e) => warn!(
+                        error=?e,
+                        "Failed to pause torrent over quota, it may already be paused"
+                    ),
+                }
+            }
+            emit(
+                app,
+                StorageEvent::QuotaExceeded {
+                    used_bytes: report.used_bytes,
+                    quota_bytes: quota,
+                },
+            );
+        }
+        _ => resume_quota_paused(&state).await,
+    }
+
+    Ok(summary)
+}
+
+/// Resume downloads the quota paused, now that usage is back under it
+async fn resume_quota_paused(state: &State) {
+    // Nothing may start while the kill switch holds torrents paused
+    if state.kill_switch.tripped() {
+        return;
+    }
+    let paused: Vec<String> = state
+        .torrent_db
+        .get_all()
+        .into_iter()
+        .filter(|e| e.quota_paused)
+        .map(|e| e.info_hash)
+        .collect();
+    if paused.is_empty() {
+        return;
+    }
+    info!(
+        "Storage is back under quota, resuming {} downloads",
+        paused.len()
+    );
+    for info_hash in paused {
+        if let Ok(id) = torrent_server::find_torrent_id(state, &info_hash)
+            && let Err(e) =
+                torrent_server::torrent_action_start(state, TorrentIdOrHash::Id(id)).await
+        {
+            warn!(error=?e, "Failed to resume torrent paused by the storage quota");
+            continue;
+        }
+        if let Err(e) = state.torrent_db.set_quota_paused(&info_hash, false) {
+            warn!(error=?e, "Failed to update torrent database");
+        }
+    }
+}
+
+fn emit(app: &AppHandle, event: StorageEvent) {
+    if let Err(e) = app.emit(STORAGE_EVENT, &event) {
+        warn!(error=?e, "Failed to emit storage event");
+    }
+}
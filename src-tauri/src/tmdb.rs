@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
-use tracing::error;
+use tauri::{AppHandle, Manager};
+use tracing::{debug, error};
+
+use crate::error::CommandError;
+use crate::torrent_server::State;
 
 // TMDB API configuration - URLs will be proxied through Nacho Server
 const TMDB_API_PATH: &str = "/api/tmdb/3";
@@ -8,24 +11,28 @@ const TMDB_API_PATH: &str = "/api/tmdb/3";
 const TMDB_IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p";
 
 // Helper function to get Nacho Server base URL
-fn get_nacho_server_base_url(app: &AppHandle) -> Result<String, String> {
+fn get_nacho_server_base_url(app: &AppHandle) -> Result<String, CommandError> {
     let nacho_server_url = crate::settings_manager::get_nacho_server_url(app.clone())
-        .map_err(|e| format!("Failed to get Nacho Server URL: {}", e))?;
+        .map_err(|e| e.context("Failed to get Nacho Server URL"))?;
 
     match nacho_server_url {
         Some(url) if !url.is_empty() => Ok(url.trim_end_matches('/').to_string()),
-        _ => Err("Nacho Server URL not configured. Please set it in Settings.".to_string()),
+        _ => Err(CommandError::NotConfigured(
+            "Nacho Server URL not configured. Please set it in Settings.".to_string(),
+        )),
     }
 }
 
 // Helper function to get auth token
-fn get_nacho_auth_token(app: &AppHandle) -> Result<String, String> {
+fn get_nacho_auth_token(app: &AppHandle) -> Result<String, CommandError> {
     let auth_token = crate::settings_manager::get_nacho_auth_token(app.clone())
-        .map_err(|e| format!("Failed to get Nacho Auth Token: {}", e))?;
+        .map_err(|e| e.context("Failed to get Nacho Auth Token"))?;
 
     match auth_token {
         Some(token) if !token.is_empty() => Ok(token),
-        _ => Err("Nacho Auth Token not configured. Please set it in Settings.".to_string()),
+        _ => Err(CommandError::NotConfigured(
+            "Nacho Auth Token not configured. Please set it in Settings.".to_string(),
+        )),
     }
 }
 
@@ -33,6 +40,7 @@ fn get_nacho_auth_token(app: &AppHandle) -> Result<String, String> {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TmdbMovie {
     pub id: u64,
+    pub imdb_id: Option<String>,
     pub title: String,
     pub original_title: Option<String>,
     pub overview: Option<String>,
@@ -117,14 +125,14 @@ pub struct TmdbImageConfiguration {
 
 // Get TMDB API configuration
 #[tauri::command]
-pub async fn get_tmdb_config(app: AppHandle) -> Result<TmdbConfiguration, String> {
+pub async fn get_tmdb_config(app: AppHandle) -> Result<TmdbConfiguration, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching TMDB configuration...");
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/configuration", base_url, TMDB_API_PATH);
 
     println!("[TMDB] Request URL: {}", url);
@@ -135,7 +143,7 @@ pub async fn get_tmdb_config(app: AppHandle) -> Result<TmdbConfiguration, String
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch TMDB config: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch TMDB config", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -144,16 +152,15 @@ pub async fn get_tmdb_config(app: AppHandle) -> Result<TmdbConfiguration, String
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch TMDB config: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch TMDB config: {} - {}", status, error_text),
         ));
     }
 
-    let config: TmdbConfiguration = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse TMDB config: {}", e))?;
+    let config: TmdbConfiguration = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse TMDB config: {}", e))
+    })?;
 
     println!("[TMDB] Successfully fetched TMDB configuration");
     println!("[TMDB] ========================================");
@@ -163,14 +170,14 @@ pub async fn get_tmdb_config(app: AppHandle) -> Result<TmdbConfiguration, String
 
 // Get movie details by TMDB ID
 #[tauri::command]
-pub async fn get_tmdb_movie(app: AppHandle, tmdb_id: u64) -> Result<TmdbMovie, String> {
+pub async fn get_tmdb_movie(app: AppHandle, tmdb_id: u64) -> Result<TmdbMovie, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching movie details for TMDB ID: {}", tmdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/movie/{}", base_url, TMDB_API_PATH, tmdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -181,7 +188,7 @@ pub async fn get_tmdb_movie(app: AppHandle, tmdb_id: u64) -> Result<TmdbMovie, S
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch movie details: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch movie details", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -190,16 +197,15 @@ pub async fn get_tmdb_movie(app: AppHandle, tmdb_id: u64) -> Result<TmdbMovie, S
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch movie details: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch movie details: {} - {}", status, error_text),
         ));
     }
 
-    let movie: TmdbMovie = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse movie details: {}", e))?;
+    let movie: TmdbMovie = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse movie details: {}", e))
+    })?;
 
     println!("[TMDB] Successfully fetched movie: {}", movie.title);
     println!("[TMDB] ========================================");
@@ -212,14 +218,14 @@ pub async fn get_tmdb_movie(app: AppHandle, tmdb_id: u64) -> Result<TmdbMovie, S
 pub async fn get_tmdb_movie_images(
     app: AppHandle,
     tmdb_id: u64,
-) -> Result<TmdbMovieImages, String> {
+) -> Result<TmdbMovieImages, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching movie images for TMDB ID: {}", tmdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/movie/{}/images", base_url, TMDB_API_PATH, tmdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -230,7 +236,7 @@ pub async fn get_tmdb_movie_images(
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch movie images: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch movie images", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -239,16 +245,15 @@ pub async fn get_tmdb_movie_images(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch movie images: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch movie images: {} - {}", status, error_text),
         ));
     }
 
-    let images: TmdbMovieImages = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse movie images: {}", e))?;
+    let images: TmdbMovieImages = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse movie images: {}", e))
+    })?;
 
     println!(
         "[TMDB] Successfully fetched {} posters and {} backdrops",
@@ -262,14 +267,17 @@ pub async fn get_tmdb_movie_images(
 
 // Find movie by IMDB ID
 #[tauri::command]
-pub async fn find_tmdb_movie_by_imdb(app: AppHandle, imdb_id: String) -> Result<TmdbMovie, String> {
+pub async fn find_tmdb_movie_by_imdb(
+    app: AppHandle,
+    imdb_id: String,
+) -> Result<TmdbMovie, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Finding movie by IMDB ID: {}", imdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/find/{}", base_url, TMDB_API_PATH, imdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -281,7 +289,7 @@ pub async fn find_tmdb_movie_by_imdb(app: AppHandle, imdb_id: String) -> Result<
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to find movie by IMDB ID: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to find movie by IMDB ID", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -290,34 +298,41 @@ pub async fn find_tmdb_movie_by_imdb(app: AppHandle, imdb_id: String) -> Result<
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!("Failed to find movie: {} - {}", status, error_text));
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to find movie: {} - {}", status, error_text),
+        ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[TMDB] Response Body: {}", response_text);
 
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse find response: {}", e))?;
+    let result: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse find response: {}", e))
+    })?;
 
     // Extract movie results
     let movie_results = result["movie_results"]
         .as_array()
-        .ok_or("No movie results found")?;
+        .ok_or_else(|| CommandError::InvalidResponse("No movie results found".to_string()))?;
 
     if movie_results.is_empty() {
         println!("[TMDB] No movie found with IMDB ID: {}", imdb_id);
         println!("[TMDB] ========================================");
-        return Err(format!("No movie found with IMDB ID: {}", imdb_id));
+        return Err(CommandError::NotFound(format!(
+            "No movie found with IMDB ID: {}",
+            imdb_id
+        )));
     }
 
     // Get the first result and fetch full details
     let tmdb_id = movie_results[0]["id"]
         .as_u64()
-        .ok_or("Invalid TMDB ID in response")?;
+        .ok_or_else(|| CommandError::InvalidResponse("Invalid TMDB ID in response".to_string()))?;
 
     println!(
         "[TMDB] Found TMDB ID: {}, fetching full details...",
@@ -385,13 +400,16 @@ pub struct TmdbVideosResponse {
 
 // Get movie videos/trailers
 #[tauri::command]
-pub async fn get_tmdb_movie_videos(app: AppHandle, tmdb_id: u64) -> Result<Vec<TmdbVideo>, String> {
+pub async fn get_tmdb_movie_videos(
+    app: AppHandle,
+    tmdb_id: u64,
+) -> Result<Vec<TmdbVideo>, CommandError> {
     println!("[TMDB] Fetching videos for movie ID: {}", tmdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/movie/{}/videos", base_url, TMDB_API_PATH, tmdb_id);
 
     let response = client
@@ -400,16 +418,18 @@ pub async fn get_tmdb_movie_videos(app: AppHandle, tmdb_id: u64) -> Result<Vec<T
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch videos: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch videos", e))?;
 
     if !response.status().is_success() {
-        return Err(format!("TMDB API error: {}", response.status()));
+        return Err(CommandError::from_status(
+            response.status(),
+            format!("TMDB API error: {}", response.status()),
+        ));
     }
 
-    let videos_response: TmdbVideosResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse videos response: {}", e))?;
+    let videos_response: TmdbVideosResponse = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse videos response: {}", e))
+    })?;
 
     println!("[TMDB] Found {} videos", videos_response.results.len());
     Ok(videos_response.results)
@@ -468,6 +488,14 @@ pub struct TmdbEpisodeExternalIds {
     pub tvrage_id: Option<u64>,
 }
 
+// TMDB TV show external IDs response
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TmdbShowExternalIds {
+    pub id: u64,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u64>,
+}
+
 // TMDB Season images response
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TmdbSeasonImages {
@@ -477,14 +505,14 @@ pub struct TmdbSeasonImages {
 
 // Get TV show details by TMDB ID
 #[tauri::command]
-pub async fn get_tmdb_show(app: AppHandle, tmdb_id: u64) -> Result<TmdbShow, String> {
+pub async fn get_tmdb_show(app: AppHandle, tmdb_id: u64) -> Result<TmdbShow, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching TV show details for TMDB ID: {}", tmdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/tv/{}", base_url, TMDB_API_PATH, tmdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -495,7 +523,7 @@ pub async fn get_tmdb_show(app: AppHandle, tmdb_id: u64) -> Result<TmdbShow, Str
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch TV show details: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch TV show details", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -504,16 +532,18 @@ pub async fn get_tmdb_show(app: AppHandle, tmdb_id: u64) -> Result<TmdbShow, Str
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch TV show details: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch TV show details: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
-    let show: TmdbShow = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse TV show details: {}", e))?;
+    let show: TmdbShow = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse TV show details: {}", e))
+    })?;
 
     println!("[TMDB] Successfully fetched TV show: {}", show.name);
     println!("[TMDB] ========================================");
@@ -523,14 +553,17 @@ pub async fn get_tmdb_show(app: AppHandle, tmdb_id: u64) -> Result<TmdbShow, Str
 
 // Get TV show images by TMDB ID
 #[tauri::command]
-pub async fn get_tmdb_show_images(app: AppHandle, tmdb_id: u64) -> Result<TmdbShowImages, String> {
+pub async fn get_tmdb_show_images(
+    app: AppHandle,
+    tmdb_id: u64,
+) -> Result<TmdbShowImages, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching TV show images for TMDB ID: {}", tmdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/tv/{}/images", base_url, TMDB_API_PATH, tmdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -541,7 +574,7 @@ pub async fn get_tmdb_show_images(app: AppHandle, tmdb_id: u64) -> Result<TmdbSh
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch show images: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch show images", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -550,16 +583,18 @@ pub async fn get_tmdb_show_images(app: AppHandle, tmdb_id: u64) -> Result<TmdbSh
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch TV show images: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch TV show images: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
-    let images: TmdbShowImages = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse TV show images: {}", e))?;
+    let images: TmdbShowImages = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse TV show images: {}", e))
+    })?;
 
     println!(
         "[TMDB] Successfully fetched {} posters and {} backdrops",
@@ -573,14 +608,17 @@ pub async fn get_tmdb_show_images(app: AppHandle, tmdb_id: u64) -> Result<TmdbSh
 
 // Find TV show by IMDB ID
 #[tauri::command]
-pub async fn find_tmdb_show_by_imdb(app: AppHandle, imdb_id: String) -> Result<TmdbShow, String> {
+pub async fn find_tmdb_show_by_imdb(
+    app: AppHandle,
+    imdb_id: String,
+) -> Result<TmdbShow, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Finding TV show by IMDB ID: {}", imdb_id);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/find/{}", base_url, TMDB_API_PATH, imdb_id);
 
     println!("[TMDB] Request URL: {}", url);
@@ -592,7 +630,7 @@ pub async fn find_tmdb_show_by_imdb(app: AppHandle, imdb_id: String) -> Result<T
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to find show by IMDB ID: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to find show by IMDB ID", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -601,37 +639,38 @@ pub async fn find_tmdb_show_by_imdb(app: AppHandle, imdb_id: String) -> Result<T
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to find TV show: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to find TV show: {} - {}", status, error_text),
         ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[TMDB] Response Body: {}", response_text);
 
-    let result: serde_json::Value = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse find response: {}", e))?;
+    let result: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse find response: {}", e))
+    })?;
 
     // Extract TV show results
     let show_results = result["tv_results"]
         .as_array()
-        .ok_or("No TV show results found")?;
+        .ok_or_else(|| CommandError::InvalidResponse("No TV show results found".to_string()))?;
 
     let tmdb_id = if !show_results.is_empty() {
         // Found a TV show directly
-        show_results[0]["id"]
-            .as_u64()
-            .ok_or("Invalid TMDB ID in response")?
+        show_results[0]["id"].as_u64().ok_or_else(|| {
+            CommandError::InvalidResponse("Invalid TMDB ID in response".to_string())
+        })?
     } else {
         // Check if this is an episode IMDB ID instead
-        let episode_results = result["tv_episode_results"]
-            .as_array()
-            .ok_or("No TV show or episode results found")?;
+        let episode_results = result["tv_episode_results"].as_array().ok_or_else(|| {
+            CommandError::InvalidResponse("No TV show or episode results found".to_string())
+        })?;
 
         if episode_results.is_empty() {
             println!(
@@ -639,17 +678,17 @@ pub async fn find_tmdb_show_by_imdb(app: AppHandle, imdb_id: String) -> Result<T
                 imdb_id
             );
             println!("[TMDB] ========================================");
-            return Err(format!(
+            return Err(CommandError::NotFound(format!(
                 "No TV show or episode found with IMDB ID: {}",
                 imdb_id
-            ));
+            )));
         }
 
         // Extract the show_id from the episode result
         println!("[TMDB] IMDB ID is for an episode, extracting show_id...");
-        episode_results[0]["show_id"]
-            .as_u64()
-            .ok_or("Invalid show_id in episode response")?
+        episode_results[0]["show_id"].as_u64().ok_or_else(|| {
+            CommandError::InvalidResponse("Invalid show_id in episode response".to_string())
+        })?
     };
 
     println!(
@@ -668,7 +707,7 @@ pub async fn get_tmdb_season(
     app: AppHandle,
     tmdb_id: u64,
     season_number: u32,
-) -> Result<TmdbSeason, String> {
+) -> Result<TmdbSeason, CommandError> {
     println!("[TMDB] ========================================");
     println!(
         "[TMDB] Fetching season {} for TV show ID: {}",
@@ -678,7 +717,7 @@ pub async fn get_tmdb_season(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!(
         "{}{}/tv/{}/season/{}",
         base_url, TMDB_API_PATH, tmdb_id, season_number
@@ -692,7 +731,7 @@ pub async fn get_tmdb_season(
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch season details: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch season details", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -701,9 +740,12 @@ pub async fn get_tmdb_season(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch season details: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch season details: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
@@ -711,7 +753,7 @@ pub async fn get_tmdb_season(
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!(
         "[TMDB] Response Body (first 500 chars): {}",
@@ -719,11 +761,11 @@ pub async fn get_tmdb_season(
     );
 
     let season: TmdbSeason = serde_json::from_str(&response_text).map_err(|e| {
-        format!(
+        CommandError::InvalidResponse(format!(
             "Failed to parse season details: {} - Response: {}",
             e,
             &response_text.chars().take(200).collect::<String>()
-        )
+        ))
     })?;
 
     println!("[TMDB] Successfully fetched season: {}", season.name);
@@ -739,7 +781,7 @@ pub async fn get_tmdb_episode(
     tmdb_id: u64,
     season_number: u32,
     episode_number: u32,
-) -> Result<TmdbEpisode, String> {
+) -> Result<TmdbEpisode, CommandError> {
     println!("[TMDB] ========================================");
     println!(
         "[TMDB] Fetching episode {} of season {} for TV show ID: {}",
@@ -749,7 +791,7 @@ pub async fn get_tmdb_episode(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!(
         "{}{}/tv/{}/season/{}/episode/{}",
         base_url, TMDB_API_PATH, tmdb_id, season_number, episode_number
@@ -763,7 +805,7 @@ pub async fn get_tmdb_episode(
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch episode details: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch episode details", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -772,16 +814,18 @@ pub async fn get_tmdb_episode(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch episode details: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch episode details: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
-    let episode: TmdbEpisode = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse episode details: {}", e))?;
+    let episode: TmdbEpisode = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse episode details: {}", e))
+    })?;
 
     println!("[TMDB] Successfully fetched episode: {}", episode.name);
     println!("[TMDB] ========================================");
@@ -796,23 +840,22 @@ pub async fn get_tmdb_episode_external_ids(
     tmdb_id: u64,
     season_number: u32,
     episode_number: u32,
-) -> Result<TmdbEpisodeExternalIds, String> {
-    println!("[TMDB] ========================================");
-    println!(
-        "[TMDB] Fetching external IDs for episode {} of season {} for TV show ID: {}",
+) -> Result<TmdbEpisodeExternalIds, CommandError> {
+    debug!(
+        "Fetching external IDs for episode {} of season {} of TV show {}",
         episode_number, season_number, tmdb_id
     );
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!(
         "{}{}/tv/{}/season/{}/episode/{}/external_ids",
         base_url, TMDB_API_PATH, tmdb_id, season_number, episode_number
     );
 
-    println!("[TMDB] Request URL: {}", url);
+    debug!("Request URL: {}", url);
 
     let response = client
         .get(&url)
@@ -820,31 +863,80 @@ pub async fn get_tmdb_episode_external_ids(
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch episode external IDs: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch episode external IDs", e))?;
 
     let status = response.status();
-    println!("[TMDB] Response Status: {}", status);
+    debug!("Response status: {}", status);
 
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        println!("[TMDB] Response Body (Error): {}", error_text);
-        println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch episode external IDs: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch episode external IDs: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
-    let external_ids: TmdbEpisodeExternalIds = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse episode external IDs: {}", e))?;
+    let external_ids: TmdbEpisodeExternalIds = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse episode external IDs: {}", e))
+    })?;
 
-    println!(
-        "[TMDB] Successfully fetched external IDs for episode. IMDB ID: {:?}",
+    debug!(
+        "Fetched external IDs for episode, IMDB ID: {:?}",
         external_ids.imdb_id
     );
-    println!("[TMDB] ========================================");
+
+    Ok(external_ids)
+}
+
+// Get TV show external IDs (IMDB, TVDB) by TMDB ID
+#[tauri::command]
+pub async fn get_tmdb_show_external_ids(
+    app: AppHandle,
+    tmdb_id: u64,
+) -> Result<TmdbShowExternalIds, CommandError> {
+    debug!("Fetching external IDs for TV show {}", tmdb_id);
+
+    let base_url = get_nacho_server_base_url(&app)?;
+    let auth_token = get_nacho_auth_token(&app)?;
+
+    let client = crate::proxy::http_client(&app.state::<State>())?;
+    let url = format!("{}{}/tv/{}/external_ids", base_url, TMDB_API_PATH, tmdb_id);
+
+    debug!("Request URL: {}", url);
+
+    let response = client
+        .get(&url)
+        .header("X-Nacho-Auth", &auth_token)
+        .header("accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| CommandError::request("Failed to fetch TV show external IDs", e))?;
+
+    let status = response.status();
+    debug!("Response status: {}", status);
+
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch TV show external IDs: {} - {}",
+                status, error_text
+            ),
+        ));
+    }
+
+    let external_ids: TmdbShowExternalIds = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse TV show external IDs: {}", e))
+    })?;
+
+    debug!(
+        "Fetched external IDs for TV show, IMDB ID: {:?}, TVDB ID: {:?}",
+        external_ids.imdb_id, external_ids.tvdb_id
+    );
 
     Ok(external_ids)
 }
@@ -855,7 +947,7 @@ pub async fn get_tmdb_season_images(
     app: AppHandle,
     tmdb_id: u64,
     season_number: u32,
-) -> Result<TmdbSeasonImages, String> {
+) -> Result<TmdbSeasonImages, CommandError> {
     println!("[TMDB] ========================================");
     println!(
         "[TMDB] Fetching images for season {} of TV show ID: {}",
@@ -865,7 +957,7 @@ pub async fn get_tmdb_season_images(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!(
         "{}{}/tv/{}/season/{}/images",
         base_url, TMDB_API_PATH, tmdb_id, season_number
@@ -879,7 +971,7 @@ pub async fn get_tmdb_season_images(
         .header("accept", "application/json")
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch season images: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch season images", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
@@ -888,16 +980,15 @@ pub async fn get_tmdb_season_images(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch season images: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch season images: {} - {}", status, error_text),
         ));
     }
 
-    let images: TmdbSeasonImages = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse season images: {}", e))?;
+    let images: TmdbSeasonImages = response.json().await.map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse season images: {}", e))
+    })?;
 
     println!(
         "[TMDB] Successfully fetched {} posters for season {}",
@@ -963,14 +1054,14 @@ pub async fn search_tmdb_movies(
     app: AppHandle,
     query: String,
     page: Option<u32>,
-) -> Result<TmdbSearchMoviesResponse, String> {
+) -> Result<TmdbSearchMoviesResponse, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Searching movies for query: {}", query);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/search/movie", base_url, TMDB_API_PATH);
     let page_num = page.unwrap_or(1);
 
@@ -983,20 +1074,23 @@ pub async fn search_tmdb_movies(
         .query(&[("query", query.as_str()), ("page", &page_num.to_string())])
         .send()
         .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to send request", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
 
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("TMDB API error: {} - {}", status, error_text));
+        return Err(CommandError::from_status(
+            status,
+            format!("TMDB API error: {} - {}", status, error_text),
+        ));
     }
 
     let search_response: TmdbSearchMoviesResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| CommandError::InvalidResponse(format!("Failed to parse response: {}", e)))?;
 
     println!(
         "[TMDB] Found {} movies (page {} of {})",
@@ -1015,14 +1109,14 @@ pub async fn search_tmdb_shows(
     app: AppHandle,
     query: String,
     page: Option<u32>,
-) -> Result<TmdbSearchShowsResponse, String> {
+) -> Result<TmdbSearchShowsResponse, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Searching TV shows for query: {}", query);
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/search/tv", base_url, TMDB_API_PATH);
     let page_num = page.unwrap_or(1);
 
@@ -1035,20 +1129,23 @@ pub async fn search_tmdb_shows(
         .query(&[("query", query.as_str()), ("page", &page_num.to_string())])
         .send()
         .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to send request", e))?;
 
     let status = response.status();
     println!("[TMDB] Response Status: {}", status);
 
     if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("TMDB API error: {} - {}", status, error_text));
+        return Err(CommandError::from_status(
+            status,
+            format!("TMDB API error: {} - {}", status, error_text),
+        ));
     }
 
     let search_response: TmdbSearchShowsResponse = response
         .json()
         .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+        .map_err(|e| CommandError::InvalidResponse(format!("Failed to parse response: {}", e)))?;
 
     println!(
         "[TMDB] Found {} TV shows (page {} of {})",
@@ -1163,7 +1260,7 @@ pub struct TmdbPopularShowsResponse {
 pub async fn get_popular_movies(
     app: AppHandle,
     page: Option<u32>,
-) -> Result<Vec<TraktCompatibleTrendingItem>, String> {
+) -> Result<Vec<TraktCompatibleTrendingItem>, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching popular movies...");
 
@@ -1173,7 +1270,7 @@ pub async fn get_popular_movies(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/movie/popular", base_url, TMDB_API_PATH);
 
     println!("[TMDB] Request URL: {}", url);
@@ -1187,7 +1284,7 @@ pub async fn get_popular_movies(
         .await
         .map_err(|e| {
             error!("Failed to fetch popular movies: {}", e);
-            format!("Failed to fetch popular movies: {}", e)
+            CommandError::request("Failed to fetch popular movies", e)
         })?;
 
     let status = response.status();
@@ -1197,15 +1294,18 @@ pub async fn get_popular_movies(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch popular movies: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch popular movies: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
     let popular_response: TmdbPopularMoviesResponse = response.json().await.map_err(|e| {
         error!("Failed to parse popular movies response: {}", e);
-        format!("Failed to parse popular movies response: {}", e)
+        CommandError::InvalidResponse(format!("Failed to parse popular movies response: {}", e))
     })?;
 
     println!(
@@ -1263,7 +1363,7 @@ pub async fn get_popular_movies(
 pub async fn get_popular_shows(
     app: AppHandle,
     page: Option<u32>,
-) -> Result<Vec<TraktCompatibleTrendingShowItem>, String> {
+) -> Result<Vec<TraktCompatibleTrendingShowItem>, CommandError> {
     println!("[TMDB] ========================================");
     println!("[TMDB] Fetching popular TV shows...");
 
@@ -1273,7 +1373,7 @@ pub async fn get_popular_shows(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = reqwest::Client::new();
+    let client = crate::proxy::http_client(&app.state::<State>())?;
     let url = format!("{}{}/tv/popular", base_url, TMDB_API_PATH);
 
     println!("[TMDB] Request URL: {}", url);
@@ -1287,7 +1387,7 @@ pub async fn get_popular_shows(
         .await
         .map_err(|e| {
             error!("Failed to fetch popular TV shows: {}", e);
-            format!("Failed to fetch popular TV shows: {}", e)
+            CommandError::request("Failed to fetch popular TV shows", e)
         })?;
 
     let status = response.status();
@@ -1297,15 +1397,18 @@ pub async fn get_popular_shows(
         let error_text = response.text().await.unwrap_or_default();
         println!("[TMDB] Response Body (Error): {}", error_text);
         println!("[TMDB] ========================================");
-        return Err(format!(
-            "Failed to fetch popular TV shows: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to fetch popular TV shows: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
     let popular_response: TmdbPopularShowsResponse = response.json().await.map_err(|e| {
         error!("Failed to parse popular TV shows response: {}", e);
-        format!("Failed to parse popular TV shows response: {}", e)
+        CommandError::InvalidResponse(format!("Failed to parse popular TV shows response: {}", e))
     })?;
 
     println!(
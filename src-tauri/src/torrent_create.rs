@@ -0,0 +1,411 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use librqbit::{AddTorrent, AddTorrentOptions};
+use librqbit_sha1_wrapper::{ISha1, Sha1};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tracing::{info, warn};
+
+use crate::error::CommandError;
+use crate::torrent_server::State;
+
+/// Name of the Tauri event carrying [`CreateTorrentProgress`] payloads to the frontend
+pub const CREATE_TORRENT_EVENT: &str = "create-torrent-progress";
+
+const MIN_PIECE_LENGTH: u32 = 16 * 1024;
+const MAX_PIECE_LENGTH: u32 = 16 * 1024 * 1024;
+/// Auto piece size aims for about this many pieces
+const TARGET_PIECES: u64 = 1500;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTorrentRequest {
+    /// File or folder to share
+    pub path: PathBuf,
+    /// Piece size in bytes, a power of two between 16 KiB and 16 MiB. Picked automatically if unset.
+    pub piece_length: Option<u32>,
+    #[serde(default)]
+    pub trackers: Vec<String>,
+    #[serde(default)]
+    pub web_seeds: Vec<String>,
+    #[serde(default)]
+    pub private: bool,
+    pub comment: Option<String>,
+    /// Where to also write the .torrent file
+    pub torrent_output_path: Option<PathBuf>,
+    pub tmdb_id: Option<u64>,
+    pub media_type: Option<String>,
+    pub episode_info: Option<(i32, i32)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedTorrent {
+    pub torrent_id: Option<usize>,
+    pub info_hash: String,
+    pub name: String,
+    pub magnet: String,
+    pub piece_length: u32,
+    pub total_bytes: u64,
+    pub torrent_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateTorrentProgress {
+    pub path: PathBuf,
+    pub hashed_bytes: u64,
+    pub total_bytes: u64,
+}
+
+// Bencode dictionaries must be sorted by key, fields are declared in that order
+
+#[derive(Serialize)]
+struct MetaInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    announce: Option<String>,
+    #[serde(rename = "announce-list", skip_serializing_if = "Vec::is_empty")]
+    announce_list: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(rename = "created by")]
+    created_by: String,
+    #[serde(rename = "creation date")]
+    creation_date: i64,
+    info: Info,
+    #[serde(rename = "url-list", skip_serializing_if = "Vec::is_empty")]
+    url_list: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Info {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files: Option<Vec<InfoFile>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    length: Option<u64>,
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: u32,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct InfoFile {
+    length: u64,
+    path: Vec<String>,
+}
+
+/// Create a torrent from a local file or folder and seed it from where it is
+#[tauri::command]
+pub async fn create_torrent(
+    app: AppHandle,
+    request: CreateTorrentRequest,
+) -> Result<CreatedTorrent, CommandError> {
+    create_and_seed(&app, request)
+        .await
+        .map_err(|e| CommandError::from(e).context("Failed to create torrent"))
+}
+
+async fn create_and_seed(
+    app: &AppHandle,
+    request: CreateTorrentRequest,
+) -> anyhow::Result<CreatedTorrent> {
+    let path = request
+        .path
+        .canonicalize()
+        .map_err(|e| CommandError::InvalidInput(format!("{:?} not found: {}", request.path, e)))?;
+    if let Some(length) = request.piece_length
+        && (!length.is_power_of_two() || !(MIN_PIECE_LENGTH..=MAX_PIECE_LENGTH).contains(&length))
+    {
+        return Err(CommandError::InvalidInput(
+            "Piece size must be a power of two between 16 KiB and 16 MiB".to_string(),
+        )
+        .into());
+    }
+    for url in request.trackers.iter().chain(&request.web_seeds) {
+        Url::parse(url)
+            .map_err(|e| CommandError::InvalidInput(format!("Invalid URL {:?}: {}", url, e)))?;
+    }
+
+    let files = list_files(&path)?;
+    let total_bytes: u64 = files.iter().map(|(_, len)| len).sum();
+    if total_bytes == 0 {
+        return Err(
+            CommandError::InvalidInput(format!("Nothing to share, {:?} is empty", path)).into(),
+        );
+    }
+    let piece_length = request
+        .piece_length
+        .unwrap_or_else(|| auto_piece_length(total_bytes));
+
+    let pieces = {
+        let app = app.clone();
+        let path = path.clone();
+        let files = files.clone();
+        tokio::task::spawn_blocking(move || {
+            hash_pieces(&files, piece_length, |hashed_bytes| {
+                let progress = CreateTorrentProgress {
+                    path: path.clone(),
+                    hashed_bytes,
+                    total_bytes,
+                };
+                if let Err(e) = app.emit(CREATE_TORRENT_EVENT, &progress) {
+                    warn!(error=?e, "Failed to emit torrent creation progress");
+                }
+            })
+        })
+        .await
+        .context("Hashing task panicked")??
+    };
+
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .context("Path has no valid UTF-8 name")?
+        .to_owned();
+    let is_dir = path.is_dir();
+    let info = Info {
+        files: is_dir.then(|| {
+            files
+                .iter()
+                .map(|(file, length)| InfoFile {
+                    length: *length,
+                    path: file
+                        .strip_prefix(&path)
+                        .unwrap_or(file)
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                        .collect(),
+                })
+                .collect()
+        }),
+        length: (!is_dir).then_some(total_bytes),
+        name: name.clone(),
+        piece_length,
+        pieces,
+        private: request.private.then_some(1),
+    };
+
+    let info_bytes = serde_bencode::to_bytes(&info).context("Failed to encode torrent info")?;
+    let mut hasher = Sha1::new();
+    hasher.update(&info_bytes);
+    let info_hash: String = hasher
+        .finish()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    let meta = MetaInfo {
+        announce: request.trackers.first().cloned(),
+        announce_list: request.trackers.iter().map(|t| vec![t.clone()]).collect(),
+        comment: request.comment.clone(),
+        created_by: format!("Nacho Time {}", env!("CARGO_PKG_VERSION")),
+        creation_date: chrono::Utc::now().timestamp(),
+        info,
+        url_list: request.web_seeds.clone(),
+    };
+    let torrent_bytes = serde_bencode::to_bytes(&meta).context("Failed to encode torrent")?;
+
+    let magnet = magnet_link(&info_hash, &name, &request.trackers, &request.web_seeds)?;
+
+    if let Some(output) = &request.torrent_output_path {
+        std::fs::write(output, &torrent_bytes)
+            .with_context(|| format!("Failed to write {:?}", output))?;
+    }
+
+    // Files already sit in their final place, point the session at them so it seeds after checking
+    let output_folder = if is_dir {
+        path.clone()
+    } else {
+        path.parent()
+            .context("File has no parent folder")?
+            .to_owned()
+    };
+    let state = app.state::<State>();
+    let response = crate::torrent_server::add_torrent(
+        &state,
+        AddTorrent::TorrentFileBytes(torrent_bytes.into()),
+        Some(AddTorrentOptions {
+            output_folder: Some(output_folder.to_string_lossy().into_owned()),
+            overwrite: true,
+            ..Default::default()
+        }),
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to add torrent to session: {:?}", e))?;
+
+    if let Some(id) = response.id
+        && let Err(e) = state.torrent_db.upsert_torrent(
+            id as i32,
+            info_hash.clone(),
+            request.tmdb_id,
+            request.media_type.clone(),
+            request.episode_info,
+        )
+    {
+        warn!(error=?e, "Failed to update torrent database");
+    }
+
+    info!("Created torrent {} for {:?}", info_hash, path);
+    Ok(CreatedTorrent {
+        torrent_id: response.id,
+        info_hash,
+        name,
+        magnet,
+        piece_length,
+        total_bytes,
+        torrent_path: request.torrent_output_path,
+    })
+}
+
+/// All files under `path` (or `path` itself) with their sizes, in a stable order
+fn list_files(path: &Path) -> anyhow::Result<Vec<(PathBuf, u64)>> {
+    let metadata = std::fs::metadata(path)?;
+    if metadata.is_file() {
+        return Ok(vec![(path.to_owned(), metadata.len())]);
+    }
+
+    let mut files = Vec::new();
+    let mut entries: Vec<_> = std::fs::read_dir(path)
+        .with_context(|| format!("Failed to read {:?}", path))?
+        .collect::<Result<_, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            files.extend(list_files(&entry.path())?);
+        } else if file_type.is_file() {
+            files.push((entry.path(), entry.metadata()?.len()));
+        }
+    }
+    Ok(files)
+}
+
+fn auto_piece_length(total_bytes: u64) -> u32 {
+    let target = (total_bytes / TARGET_PIECES).max(1);
+    (target.next_power_of_two() as u32).clamp(MIN_PIECE_LENGTH, MAX_PIECE_LENGTH)
+}
+
+/// SHA-1 of every piece of the concatenated files
+fn hash_pieces(
+    files: &[(PathBuf, u64)],
+    piece_length: u32,
+    mut on_progress: impl FnMut(u64),
+) -> anyhow::Result<Vec<u8>> {
+    let mut pieces = Vec::new();
+    let mut buf = vec![0u8; piece_length as usize];
+    let mut filled = 0;
+    let mut hashed = 0u64;
+    let mut last_report = 0u64;
+
+    for (path, _) in files {
+        let mut file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+        loop {
+            let n = file
+                .read(&mut buf[filled..])
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+            hashed += n as u64;
+            if filled == buf.len() {
+                finish_piece(&buf, &mut pieces);
+                filled = 0;
+                // Report roughly every 64 MiB
+                if hashed - last_report >= 64 * 1024 * 1024 {
+                    on_progress(hashed);
+                    last_report = hashed;
+                }
+            }
+        }
+    }
+    if filled > 0 {
+        finish_piece(&buf[..filled], &mut pieces);
+    }
+    on_progress(hashed);
+    Ok(pieces)
+}
+
+fn finish_piece(piece: &[u8], pieces: &mut Vec<u8>) {
+    let mut hasher = Sha1::new();
+    hasher.update(piece);
+    pieces.extend_from_slice(&hasher.finish());
+}
+
+fn magnet_link(
+    info_hash: &str,
+    name: &str,
+    trackers: &[String],
+    web_seeds: &[String],
+) -> anyhow::Result<String> {
+    let mut magnet = Url::parse(&format!("magnet:?xt=urn:btih:{}", info_hash))?;
+    {
+        let mut query = magnet.query_pairs_mut();
+        query.append_pair("dn", name);
+        for tracker in trackers {
+            query.append_pair("tr", tracker);
+        }
+        for seed in web_seeds {
+            query.append_pair("ws", seed);
+        }
+    }
+    Ok(magnet.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> Vec<String> {
+        bytes
+            .chunks(20)
+            .map(|c| c.iter().map(|b| format!("{:02x}", b)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_auto_piece_length() {
+        assert_eq!(auto_piece_length(0), MIN_PIECE_LENGTH);
+        assert_eq!(auto_piece_length(10_000_000), MIN_PIECE_LENGTH);
+        assert_eq!(auto_piece_length(100_000_000), 128 * 1024);
+        assert_eq!(auto_piece_length(1024 * 1024 * 1024), 1024 * 1024);
+        assert_eq!(
+            auto_piece_length(100 * 1024 * 1024 * 1024),
+            MAX_PIECE_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_hash_pieces_across_files() {
+        let dir = std::env::temp_dir().join(format!("nacho-create-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("a");
+        let second = dir.join("b");
+        std::fs::write(&first, b"abc").unwrap();
+        std::fs::write(&second, b"defghij").unwrap();
+
+        let mut progress = Vec::new();
+        let pieces = hash_pieces(&[(first, 3), (second, 7)], 4, |n| progress.push(n)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // "abcd" spans both files, "ij" is the short last piece
+        assert_eq!(
+            hex(&pieces),
+            [
+                "81fe8bfe87576c3ecb22426f8e57847382917acf",
+                "2aed8aa9f826c21ef07d5ee15b48eea06e9c8a62",
+                "4cfa380a7a05ae26270f5ea888009520ab54b677",
+            ]
+        );
+        assert_eq!(progress.last(), Some(&10));
+    }
+}
@@ -1,16 +1,27 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::{File, OpenOptions},
     io::{BufReader, BufWriter},
     path::PathBuf,
     sync::Arc,
 };
 
+use crate::config::SeedingPolicy;
 use anyhow::Context;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
+/// Queue priority of a torrent; higher priorities start first
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum QueuePriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
 /// Represents a torrent entry in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TorrentEntry {
@@ -31,6 +42,49 @@ pub struct TorrentEntry {
     /// Optional IMDB code for external reference only (deprecated, use tmdb_id)
     #[serde(default)]
     pub imdb_code: Option<String>,
+    /// Folder the torrent's files live in, once it has been moved out of the default location
+    #[serde(default)]
+    pub output_folder: Option<PathBuf>,
+    /// Files generated from this torrent (transmux outputs, extracted subtitles)
+    #[serde(default)]
+    pub derived_files: Vec<PathBuf>,
+    /// Whether the post-download pipeline already ran for this torrent
+    #[serde(default)]
+    pub post_processed: bool,
+    /// Timestamp when the download completed
+    #[serde(default)]
+    pub completed_at: Option<i64>,
+    /// Per-torrent seeding limits, overriding the global policy
+    #[serde(default)]
+    pub seeding_policy: Option<SeedingPolicy>,
+    /// Whether the seeding policy action was already applied
+    #[serde(default)]
+    pub seeding_stopped: bool,
+    /// Position in the download queue, lower starts first
+    #[serde(default)]
+    pub queue_position: Option<u64>,
+    #[serde(default)]
+    pub queue_priority: QueuePriority,
+    /// Run regardless of the queue limits
+    #[serde(default)]
+    pub force_start: bool,
+    /// Paused by the queue (as opposed to by the user) and waiting for a free slot
+    #[serde(default)]
+    pub queue_paused: bool,
+    /// Paused because storage usage went over the quota, resumed once it's back under
+    #[serde(default)]
+    pub quota_paused: bool,
+}
+
+impl TorrentEntry {
+    /// Sort key for the queue: priority first, then position, then age
+    pub fn queue_key(&self) -> (std::cmp::Reverse<QueuePriority>, u64, i64) {
+        (
+            std::cmp::Reverse(self.queue_priority),
+            self.queue_position.unwrap_or(u64::MAX),
+            self.created_at,
+        )
+    }
 }
 
 /// The persistent database structure
@@ -128,7 +182,13 @@ impl TorrentDb {
             entry.updated_at = now;
             debug!("Updated torrent entry: {}", info_hash);
         } else {
-            // Create new entry
+            // Create new entry at the end of the queue
+            let queue_position = data
+                .entries
+                .values()
+                .filter_map(|e| e.queue_position)
+                .max()
+                .map_or(0, |p| p + 1);
             let entry = TorrentEntry {
                 torrent_id,
                 info_hash: info_hash.clone(),
@@ -138,6 +198,17 @@ impl TorrentDb {
                 updated_at: now,
                 episode_info,
                 imdb_code: None, // Deprecated field
+                output_folder: None,
+                derived_files: Vec::new(),
+                post_processed: false,
+                completed_at: None,
+                seeding_policy: None,
+                seeding_stopped: false,
+                queue_position: Some(queue_position),
+                queue_priority: QueuePriority::default(),
+                force_start: false,
+                queue_paused: false,
+                quota_paused: false,
             };
             data.entries.insert(info_hash.clone(), entry);
             debug!("Created new torrent entry: {}", info_hash);
@@ -155,6 +226,16 @@ impl TorrentDb {
         data.entries.get(info_hash).cloned()
     }
 
+    /// Get a torrent entry by info hash, creating an empty one if it doesn't exist
+    pub fn get_or_create(&self, torrent_id: i32, info_hash: &str) -> anyhow::Result<TorrentEntry> {
+        if let Some(entry) = self.get_by_hash(info_hash) {
+            return Ok(entry);
+        }
+        self.upsert_torrent(torrent_id, info_hash.to_owned(), None, None, None)?;
+        self.get_by_hash(info_hash)
+            .context("Entry missing right after insert")
+    }
+
     /// Get a torrent entry by torrent ID
     #[allow(dead_code)]
     pub fn get_by_id(&self, torrent_id: i32) -> Option<TorrentEntry> {
@@ -180,6 +261,144 @@ impl TorrentDb {
             .and_then(|entry| entry.imdb_code.clone())
     }
 
+    /// Apply a change to an existing entry and persist it.
+    /// Returns false if there is no entry for the info hash.
+    fn update_entry(
+        &self,
+        info_hash: &str,
+        f: impl FnOnce(&mut TorrentEntry),
+    ) -> anyhow::Result<bool> {
+        let mut data = self.data.write();
+        let Some(entry) = data.entries.get_mut(info_hash) else {
+            return Ok(false);
+        };
+        f(entry);
+        entry.updated_at = chrono::Utc::now().timestamp();
+        drop(data);
+        self.save_to_file()?;
+        Ok(true)
+    }
+
+    /// Record the folder a torrent's files were moved to
+    pub fn set_output_folder(&self, info_hash: &str, folder: PathBuf) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.output_folder = Some(folder))
+    }
+
+    /// Record a file generated from the torrent's contents
+    pub fn add_derived_file(&self, info_hash: &str, path: PathBuf) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| {
+            if !entry.derived_files.contains(&path) {
+                entry.derived_files.push(path);
+            }
+        })
+    }
+
+    /// Replace the list of generated files, e.g. after they were moved
+    pub fn set_derived_files(&self, info_hash: &str, paths: Vec<PathBuf>) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.derived_files = paths)
+    }
+
+    /// Mark the post-download pipeline as done for a torrent
+    pub fn set_post_processed(&self, info_hash: &str, done: bool) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.post_processed = done)
+    }
+
+    /// Record when a torrent finished downloading, keeping the first timestamp
+    pub fn set_completed_at(&self, info_hash: &str, timestamp: i64) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| {
+            entry.completed_at.get_or_insert(timestamp);
+        })
+    }
+
+    /// Set or clear the per-torrent seeding policy
+    pub fn set_seeding_policy(
+        &self,
+        info_hash: &str,
+        policy: Option<SeedingPolicy>,
+    ) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| {
+            entry.seeding_policy = policy;
+            entry.seeding_stopped = false;
+        })
+    }
+
+    /// Mark that the seeding policy action was applied
+    pub fn set_seeding_stopped(&self, info_hash: &str, stopped: bool) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.seeding_stopped = stopped)
+    }
+
+    pub fn set_queue_priority(
+        &self,
+        info_hash: &str,
+        priority: QueuePriority,
+    ) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.queue_priority = priority)
+    }
+
+    pub fn set_force_start(&self, info_hash: &str, force: bool) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.force_start = force)
+    }
+
+    pub fn set_queue_paused(&self, info_hash: &str, paused: bool) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.queue_paused = paused)
+    }
+
+    pub fn set_quota_paused(&self, info_hash: &str, paused: bool) -> anyhow::Result<bool> {
+        self.update_entry(info_hash, |entry| entry.quota_paused = paused)
+    }
+
+    /// Swap a torrent with its neighbour among the `listed` torrents of the queue.
+    /// Their positions are renumbered in current queue order first, so entries without
+    /// a position get one. Returns false if the torrent is unknown, already at the edge,
+    /// or next to a torrent of another priority, since priority decides the order before
+    /// position.
+    pub fn move_in_queue(
+        &self,
+        info_hash: &str,
+        up: bool,
+        listed: &HashSet<String>,
+    ) -> anyhow::Result<bool> {
+        let mut data = self.data.write();
+
+        let mut order: Vec<(String, _)> = data
+            .entries
+            .iter()
+            .filter(|(hash, _)| listed.contains(*hash))
+            .map(|(hash, entry)| (hash.clone(), entry.queue_key()))
+            .collect();
+        order.sort_by(|a, b| a.1.cmp(&b.1));
+        let mut order: Vec<String> = order.into_iter().map(|(hash, _)| hash).collect();
+
+        let Some(index) = order.iter().position(|h| h == info_hash) else {
+            return Ok(false);
+        };
+        let neighbour = if up {
+            index.checked_sub(1)
+        } else {
+            Some(index + 1).filter(|i| *i < order.len())
+        };
+        let Some(neighbour) = neighbour else {
+            return Ok(false);
+        };
+        let priority = |hash: &String| data.entries.get(hash).map(|e| e.queue_priority);
+        if priority(&order[index]) != priority(&order[neighbour]) {
+            return Ok(false);
+        }
+        order.swap(index, neighbour);
+
+        let now = chrono::Utc::now().timestamp();
+        for (position, hash) in order.iter().enumerate() {
+            if let Some(entry) = data.entries.get_mut(hash) {
+                entry.queue_position = Some(position as u64);
+                entry.updated_at = now;
+            }
+        }
+
+        drop(data);
+        self.save_to_file()?;
+        Ok(true)
+    }
+
     /// Remove a torrent entry by info hash
     pub fn remove_by_hash(&self, info_hash: &str) -> anyhow::Result<()> {
         let mut data = self.data.write();
@@ -285,6 +504,87 @@ impl TorrentDb {
     }
 }
 
+#[cfg(test)]
+mod queue_tests {
+    use super::*;
+
+    /// Database in a fresh file under the temp dir
+    fn temp_db(name: &str) -> TorrentDb {
+        let path = std::env::temp_dir().join(format!(
+            "nacho-torrent-db-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        TorrentDb::new(path).unwrap()
+    }
+
+    fn order(db: &TorrentDb, listed: &HashSet<String>) -> Vec<String> {
+        let mut entries: Vec<TorrentEntry> = db
+            .data
+            .read()
+            .entries
+            .values()
+            .filter(|e| listed.contains(&e.info_hash))
+            .cloned()
+            .collect();
+        entries.sort_by_key(|e| e.queue_key());
+        entries.into_iter().map(|e| e.info_hash).collect()
+    }
+
+    fn hashes(hashes: &[&str]) -> HashSet<String> {
+        hashes.iter().map(|h| h.to_string()).collect()
+    }
+
+    #[test]
+    fn test_queue_order() {
+        let db = temp_db("order");
+        for (id, hash) in ["a", "b", "c"].into_iter().enumerate() {
+            db.upsert_torrent(id as i32, hash.to_owned(), None, None, None)
+                .unwrap();
+        }
+        let listed = hashes(&["a", "b", "c"]);
+        assert_eq!(order(&db, &listed), ["a", "b", "c"]);
+
+        db.set_queue_priority("c", QueuePriority::High).unwrap();
+        assert_eq!(order(&db, &listed), ["c", "a", "b"]);
+
+        assert!(db.move_in_queue("b", true, &listed).unwrap());
+        assert_eq!(order(&db, &listed), ["c", "b", "a"]);
+        assert!(!db.move_in_queue("a", false, &listed).unwrap());
+        let _ = std::fs::remove_file(&db.db_path);
+    }
+
+    #[test]
+    fn test_move_stays_in_priority_group() {
+        let db = temp_db("priority");
+        for (id, hash) in ["a", "b"].into_iter().enumerate() {
+            db.upsert_torrent(id as i32, hash.to_owned(), None, None, None)
+                .unwrap();
+        }
+        db.set_queue_priority("a", QueuePriority::High).unwrap();
+        let listed = hashes(&["a", "b"]);
+
+        assert!(!db.move_in_queue("b", true, &listed).unwrap());
+        assert_eq!(order(&db, &listed), ["a", "b"]);
+        let _ = std::fs::remove_file(&db.db_path);
+    }
+
+    #[test]
+    fn test_move_skips_unlisted_entries() {
+        let db = temp_db("unlisted");
+        for (id, hash) in ["a", "gone", "b"].into_iter().enumerate() {
+            db.upsert_torrent(id as i32, hash.to_owned(), None, None, None)
+                .unwrap();
+        }
+        // "gone" is no longer in the session, so "b" moves straight past it
+        let listed = hashes(&["a", "b"]);
+        assert!(db.move_in_queue("b", true, &listed).unwrap());
+        assert_eq!(order(&db, &listed), ["b", "a"]);
+        let _ = std::fs::remove_file(&db.db_path);
+    }
+}
+
 // Tests are disabled as tempfile is not a dependency
 // To enable tests, add tempfile to dev-dependencies in Cargo.toml
 /*
@@ -0,0 +1,359 @@
+use std::{collections::HashMap, time::Duration};
+
+use librqbit::api::{TorrentIdOrHash, TorrentStats};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+use crate::torrent_server::State;
+
+/// Name of the Tauri event carrying [`TorrentEvent`] payloads to the frontend
+pub const TORRENT_EVENT: &str = "torrent-event";
+
+/// How often the session watcher samples the torrent list
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Capacity of the in-process event channel
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Lifecycle event for a single torrent in the session
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TorrentEvent {
+    /// A torrent appeared in the session
+    Added {
+        id: usize,
+        info_hash: String,
+        name: Option<String>,
+    },
+    /// Name and size of the torrent became known
+    MetadataResolved {
+        id: usize,
+        info_hash: String,
+        name: Option<String>,
+        total_bytes: u64,
+    },
+    /// Periodic progress update while the torrent is live
+    Progress {
+        id: usize,
+        info_hash: String,
+        progress_bytes: u64,
+        uploaded_bytes: u64,
+        total_bytes: u64,
+        download_speed_mbps: f64,
+        upload_speed_mbps: f64,
+    },
+    /// All selected files finished downloading
+    Completed {
+        id: usize,
+        info_hash: String,
+        name: Option<String>,
+    },
+    /// The torrent entered the error state
+    Errored {
+        id: usize,
+        info_hash: String,
+        error: String,
+    },
+    /// The torrent is no longer part of the session
+    Removed { id: usize, info_hash: String },
+}
+
+impl TorrentEvent {
+    pub fn info_hash(&self) -> &str {
+        match self {
+            TorrentEvent::Added { info_hash, .. }
+            | TorrentEvent::MetadataResolved { info_hash, .. }
+            | TorrentEvent::Progress { info_hash, .. }
+            | TorrentEvent::Completed { info_hash, .. }
+            | TorrentEvent::Errored { info_hash, .. }
+            | TorrentEvent::Removed { info_hash, .. } => info_hash,
+        }
+    }
+}
+
+/// Fan-out of torrent events to backend subscribers (post-processing, notifications, ...)
+pub struct TorrentEventBus {
+    tx: broadcast::Sender<TorrentEvent>,
+}
+
+impl Default for TorrentEventBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+}
+
+impl TorrentEventBus {
+    pub fn subscribe(&self) -> broadcast::Receiver<TorrentEvent> {
+        self.tx.subscribe()
+    }
+
+    fn publish(&self, event: TorrentEvent) {
+        // Sending only fails when nobody is subscribed, which is fine
+        let _ = self.tx.send(event);
+    }
+}
+
+/// Last observed state of a torrent, used to detect transitions
+struct TorrentSnapshot {
+    id: usize,
+    metadata_resolved: bool,
+    finished: bool,
+    errored: bool,
+    progress_bytes: u64,
+    uploaded_bytes: u64,
+}
+
+/// Spawn the background task that watches the session and emits lifecycle events
+pub fn spawn_session_watcher(app: AppHandle) {
+    tokio::spawn(async move {
+        info!("Starting torrent session watcher");
+        let mut snapshots: Option<HashMap<String, TorrentSnapshot>> = None;
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let state = app.state::<State>();
+            let api = match state.api() {
+                Ok(api) => api,
+                Err(_) => continue,
+            };
+
+            let mut samples = Vec::new();
+            for torrent in api.api_torrent_list().torrents {
+                let Some(id) = torrent.id else { continue };
+                let stats = match api.api_stats_v1(TorrentIdOrHash::Id(id)) {
+                    Ok(stats) => stats,
+                    Err(e) => {
+                        debug!(id, error=?e, "Failed to fetch torrent stats");
+                        continue;
+                    }
+                };
+                samples.push(TorrentSample::new(
+                    id,
+                    torrent.info_hash,
+                    torrent.name,
+                    &stats,
+                ));
+            }
+
+            let (current, events) = diff_session(snapshots.take(), samples);
+            snapshots = Some(current);
+
+            for event in events {
+                if let Err(e) = app.emit(TORRENT_EVENT, &event) {
+                    warn!(error=?e, "Failed to emit torrent event");
+                }
+                state.events.publish(event);
+            }
+        }
+    });
+}
+
+/// The stats of one torrent that events are derived from
+struct TorrentSample {
+    id: usize,
+    info_hash: String,
+    name: Option<String>,
+    total_bytes: u64,
+    progress_bytes: u64,
+    uploaded_bytes: u64,
+    finished: bool,
+    error: Option<String>,
+    /// Download and upload speed in Mbps, while the torrent is live
+    speeds: Option<(f64, f64)>,
+}
+
+impl TorrentSample {
+    fn new(id: usize, info_hash: String, name: Option<String>, stats: &TorrentStats) -> Self {
+        Self {
+            id,
+            info_hash,
+            name,
+            total_bytes: stats.total_bytes,
+            progress_bytes: stats.progress_bytes,
+            uploaded_bytes: stats.uploaded_bytes,
+            finished: stats.finished,
+            error: stats.error.clone(),
+            speeds: stats
+                .live
+                .as_ref()
+                .map(|live| (live.download_speed.mbps, live.upload_speed.mbps)),
+        }
+    }
+}
+
+/// Diff one pass over the session against the previous snapshots.
+/// The first pass (no previous snapshots) only records what is already in the session.
+fn diff_session(
+    previous: Option<HashMap<String, TorrentSnapshot>>,
+    samples: Vec<TorrentSample>,
+) -> (HashMap<String, TorrentSnapshot>, Vec<TorrentEvent>) {
+    let initial = previous.is_none();
+    let mut previous = previous.unwrap_or_default();
+    let mut current = HashMap::new();
+    let mut events = Vec::new();
+
+    for sample in samples {
+        let prev = previous.remove(&sample.info_hash);
+        let snapshot = diff_torrent(&sample, prev.as_ref(), &mut events);
+        current.insert(sample.info_hash, snapshot);
+    }
+
+    for (info_hash, snapshot) in previous {
+        events.push(TorrentEvent::Removed {
+            id: snapshot.id,
+            info_hash,
+        });
+    }
+
+    if initial {
+        events.clear();
+    }
+    (current, events)
+}
+
+/// Compare fresh stats against the previous snapshot and queue the resulting events
+fn diff_torrent(
+    stats: &TorrentSample,
+    prev: Option<&TorrentSnapshot>,
+    events: &mut Vec<TorrentEvent>,
+) -> TorrentSnapshot {
+    let id = stats.id;
+    let info_hash = &stats.info_hash;
+    let name = &stats.name;
+    let metadata_resolved = name.is_some() && stats.total_bytes > 0;
+    let errored = stats.error.is_some();
+
+    if prev.is_none() {
+        events.push(TorrentEvent::Added {
+            id,
+            info_hash: info_hash.to_owned(),
+            name: name.clone(),
+        });
+    }
+
+    if metadata_resolved && !prev.is_some_and(|p| p.metadata_resolved) {
+        events.push(TorrentEvent::MetadataResolved {
+            id,
+            info_hash: info_hash.to_owned(),
+            name: name.clone(),
+            total_bytes: stats.total_bytes,
+        });
+    }
+
+    let changed = prev.is_none_or(|p| {
+        p.progress_bytes != stats.progress_bytes || p.uploaded_bytes != stats.uploaded_bytes
+    });
+    if let Some((download_speed_mbps, upload_speed_mbps)) = stats.speeds
+        && changed
+    {
+        events.push(TorrentEvent::Progress {
+            id,
+            info_hash: info_hash.to_owned(),
+            progress_bytes: stats.progress_bytes,
+            uploaded_bytes: stats.uploaded_bytes,
+            total_bytes: stats.total_bytes,
+            download_speed_mbps,
+            upload_speed_mbps,
+        });
+    }
+
+    if stats.finished && !prev.is_some_and(|p| p.finished) {
+        events.push(TorrentEvent::Completed {
+            id,
+            info_hash: info_hash.to_owned(),
+            name: name.clone(),
+        });
+    }
+
+    if let Some(error) = stats.error.as_ref()
+        && !prev.is_some_and(|p| p.errored)
+    {
+        events.push(TorrentEvent::Errored {
+            id,
+            info_hash: info_hash.to_owned(),
+            error: error.clone(),
+        });
+    }
+
+    TorrentSnapshot {
+        id,
+        metadata_resolved,
+        finished: stats.finished,
+        errored,
+        progress_bytes: stats.progress_bytes,
+        uploaded_bytes: stats.uploaded_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(progress_bytes: u64, finished: bool, error: Option<&str>) -> TorrentSample {
+        TorrentSample {
+            id: 1,
+            info_hash: "hash1".to_owned(),
+            name: Some("Movie".to_owned()),
+            total_bytes: 100,
+            progress_bytes,
+            uploaded_bytes: 0,
+            finished,
+            error: error.map(str::to_owned),
+            speeds: Some((1.0, 0.5)),
+        }
+    }
+
+    fn kinds(events: &[TorrentEvent]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|e| match e {
+                TorrentEvent::Added { .. } => "added",
+                TorrentEvent::MetadataResolved { .. } => "metadata_resolved",
+                TorrentEvent::Progress { .. } => "progress",
+                TorrentEvent::Completed { .. } => "completed",
+                TorrentEvent::Errored { .. } => "errored",
+                TorrentEvent::Removed { .. } => "removed",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_first_pass_is_suppressed() {
+        let (snapshots, events) = diff_session(None, vec![sample(100, true, None)]);
+        assert!(events.is_empty());
+        assert!(snapshots.contains_key("hash1"));
+
+        // Nothing changed, so nothing is emitted on the next pass either
+        let (_, events) = diff_session(Some(snapshots), vec![sample(100, true, None)]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        let (snapshots, _) = diff_session(None, vec![]);
+
+        let (snapshots, events) = diff_session(Some(snapshots), vec![sample(0, false, None)]);
+        assert_eq!(kinds(&events), ["added", "metadata_resolved", "progress"]);
+
+        let (snapshots, events) = diff_session(Some(snapshots), vec![sample(50, false, None)]);
+        assert_eq!(kinds(&events), ["progress"]);
+
+        let (snapshots, events) = diff_session(Some(snapshots), vec![sample(100, true, None)]);
+        assert_eq!(kinds(&events), ["progress", "completed"]);
+
+        // Errors are reported once, not on every pass
+        let (snapshots, events) =
+            diff_session(Some(snapshots), vec![sample(100, true, Some("disk full"))]);
+        assert_eq!(kinds(&events), ["errored"]);
+        let (snapshots, events) =
+            diff_session(Some(snapshots), vec![sample(100, true, Some("disk full"))]);
+        assert!(events.is_empty());
+
+        let (snapshots, events) = diff_session(Some(snapshots), vec![]);
+        assert_eq!(kinds(&events), ["removed"]);
+        assert!(snapshots.is_empty());
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    time::Instant,
+};
+
+use http::StatusCode;
+use librqbit::{
+    ApiError, WithStatusError,
+    api::{ApiAddTorrentResponse, EmptyJsonResponse, TorrentIdOrHash},
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_bencode::value::Value;
+use tracing::info;
+
+use crate::blocklist::BlocklistStatus;
+use crate::torrent_server::{self, State};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackerInfo {
+    pub url: String,
+    /// Trackers in lower tiers are tried first
+    pub tier: usize,
+}
+
+/// A connected peer. librqbit's peer stats carry no peer id or extension handshake, so
+/// there is no client name or flags.
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerInfo {
+    pub addr: String,
+    /// Connection state, e.g. "live", "connecting", "queued" or "dead"
+    pub state: String,
+    /// Transport, e.g. "tcp" or "utp"
+    pub connection_kind: Option<String>,
+    pub downloaded_bytes: u64,
+    pub uploaded_bytes: u64,
+    /// Rates since the previous listing of the same torrent
+    pub download_bps: Option<u64>,
+    pub upload_bps: Option<u64>,
+}
+
+/// Previous byte counters per (info hash, peer) to derive rates between listings
+#[derive(Default)]
+pub struct PeerCounters(Mutex<BTreeMap<(String, String), (Instant, u64, u64)>>);
+
+/// The parts of librqbit's peer stats snapshot that are listed
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PeerStatsSnapshot {
+    peers: BTreeMap<String, PeerStats>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PeerStats {
+    state: Option<String>,
+    conn_kind: Option<String>,
+    counters: PeerStatsCounters,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PeerStatsCounters {
+    fetched_bytes: u64,
+    uploaded_bytes: u64,
+}
+
+fn torrent_metainfo(
+    state: &State,
+    id: TorrentIdOrHash,
+) -> Result<HashMap<Vec<u8>, Value>, ApiError> {
+    let bytes = state.api()?.api_export_torrent(id)?;
+    match serde_bencode::from_bytes::<Value>(&bytes) {
+        Ok(Value::Dict(dict)) => Ok(dict),
+        _ => Err(ApiError::new_from_text(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Invalid torrent metainfo",
+        )),
+    }
+}
+
+fn value_str(value: &Value) -> Option<String> {
+    match value {
+        Value::Bytes(b) => String::from_utf8(b.clone()).ok(),
+        _ => None,
+    }
+}
+
+/// List the torrent's trackers by tier
+pub fn torrent_trackers(state: &State, id: TorrentIdOrHash) -> Result<Vec<TrackerInfo>, ApiError> {
+    let meta = torrent_metainfo(state, id)?;
+
+    let mut trackers = Vec::new();
+    if let Some(Value::List(tiers)) = meta.get(b"announce-list".as_slice()) {
+        for (tier, urls) in tiers.iter().enumerate() {
+            if let Value::List(urls) = urls {
+                trackers.extend(
+                    urls.iter()
+                        .filter_map(value_str)
+                        .map(|url| TrackerInfo { url, tier }),
+                );
+            }
+        }
+    }
+    if trackers.is_empty()
+        && let Some(url) = meta.get(b"announce".as_slice()).and_then(value_str)
+    {
+        trackers.push(TrackerInfo { url, tier: 0 });
+    }
+    Ok(trackers)
+}
+
+/// Replace the torrent's trackers. Each inner list is a tier.
+pub async fn torrent_action_set_trackers(
+    state: &State,
+    id: TorrentIdOrHash,
+    tiers: Vec<Vec<String>>,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    let tiers: Vec<Vec<String>> = tiers
+        .into_iter()
+        .map(|tier| {
+            tier.into_iter()
+                .map(|url| url.trim().to_owned())
+                .filter(|url| !url.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|tier| !tier.is_empty())
+        .collect();
+    for url in tiers.iter().flatten() {
+        tauri::Url::parse(url)
+            .ok()
+            .filter(|u| matches!(u.scheme(), "http" | "https" | "udp" | "ws" | "wss"))
+            .with_status_error(StatusCode::BAD_REQUEST, "Invalid tracker URL")?;
+    }
+
+    let api = state.api()?;
+    let original = api.api_export_torrent(id)?;
+    let info = find_info(&original).with_status_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Invalid torrent metainfo",
+    )?;
+    let mut meta = torrent_metainfo(state, id)?;
+    meta.remove(b"announce".as_slice());
+    meta.remove(b"announce-list".as_slice());
+    if let Some(first) = tiers.first().and_then(|t| t.first()) {
+        meta.insert(
+            b"announce".to_vec(),
+            Value::Bytes(first.as_bytes().to_vec()),
+        );
+        meta.insert(
+            b"announce-list".to_vec(),
+            Value::List(
+                tiers
+                    .iter()
+                    .map(|tier| {
+                        Value::List(
+                            tier.iter()
+                                .map(|url| Value::Bytes(url.as_bytes().to_vec()))
+                                .collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+        );
+    }
+
+    let bytes = encode_with_raw_info(meta, &original[info]).with_status_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Failed to encode torrent metainfo",
+    )?;
+    info!("Replacing trackers of torrent {:?}", id);
+    torrent_server::replace_torrent(state, id, bytes.into()).await
+}
+
+/// Encode a top-level metainfo dict, copying the info dict verbatim so the info hash is kept
+fn encode_with_raw_info(
+    mut meta: HashMap<Vec<u8>, Value>,
+    raw_info: &[u8],
+) -> Result<Vec<u8>, serde_bencode::Error> {
+    meta.remove(b"info".as_slice());
+    let mut entries: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+    for (key, value) in meta {
+        entries.insert(key, serde_bencode::to_bytes(&value)?);
+    }
+    entries.insert(b"info".to_vec(), raw_info.to_vec());
+
+    let mut out = b"d".to_vec();
+    for (key, value) in entries {
+        out.extend_from_slice(format!("{}:", key.len()).as_bytes());
+        out.extend_from_slice(&key);
+        out.extend_from_slice(&value);
+    }
+    out.push(b'e');
+    Ok(out)
+}
+
+/// Byte range of the top-level "info" value
+pub(crate) fn find_info(buf: &[u8]) -> Option<std::ops::Range<usize>> {
+    if buf.first() != Some(&b'd') {
+        return None;
+    }
+    let mut pos = 1;
+    while buf.get(pos)? != &b'e' {
+        let key_end = value_end(buf, pos)?;
+        let key = &buf[buf[pos..key_end].iter().position(|b| *b == b':')? + pos + 1..key_end];
+        let end = value_end(buf, key_end)?;
+        if key == b"info" {
+            return Some(key_end..end);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Deepest list or dict nesting accepted, so untrusted downloads can't overflow the stack
+const MAX_BENCODE_DEPTH: usize = 64;
+
+/// Index just past the bencoded value starting at `pos`
+fn value_end(buf: &[u8], pos: usize) -> Option<usize> {
+    nested_value_end(buf, pos, 0)
+}
+
+fn nested_value_end(buf: &[u8], pos: usize, depth: usize) -> Option<usize> {
+    let end = match buf.get(pos)? {
+        b'i' => pos + buf[pos..].iter().position(|b| *b == b'e')? + 1,
+        b'l' | b'd' if depth < MAX_BENCODE_DEPTH => {
+            let mut pos = pos + 1;
+            while buf.get(pos)? != &b'e' {
+                pos = nested_value_end(buf, pos, depth + 1)?;
+            }
+            pos + 1
+        }
+        b'0'..=b'9' => {
+            let colon = pos + buf[pos..].iter().position(|b| *b == b':')?;
+            let len: usize = std::str::from_utf8(&buf[pos..colon]).ok()?.parse().ok()?;
+            // The length comes from the download, it may be anything
+            colon.checked_add(1)?.checked_add(len)?
+        }
+        _ => return None,
+    };
+    (end > pos && end <= buf.len()).then_some(end)
+}
+
+/// Announce to trackers again now. librqbit announces when a torrent starts, so this
+/// restarts it.
+pub async fn torrent_action_reannounce(
+    state: &State,
+    id: TorrentIdOrHash,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let api = state.api()?;
+    if api.api_stats_v1(id)?.live.is_none() {
+        return Err(ApiError::new_from_text(
+            StatusCode::BAD_REQUEST,
+            "Torrent is not running",
+        ));
+    }
+    state.kill_switch.check_released()?;
+    api.api_torrent_action_pause(id).await?;
+    api.api_torrent_action_start(id).await
+}
+
+/// List the torrent's peers with transfer totals and rates
+pub fn torrent_peers(state: &State, id: TorrentIdOrHash) -> Result<Vec<PeerInfo>, ApiError> {
+    let api = state.api()?;
+    let info_hash = api.api_torrent_details(id)?.info_hash;
+    let snapshot = api.api_peer_stats(id, Default::default())?;
+    let snapshot: PeerStatsSnapshot = serde_json::to_value(&snapshot)
+        .and_then(serde_json::from_value)
+        .with_status_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to read peer stats",
+        )?;
+
+    let now = Instant::now();
+    let mut counters = state.peer_counters.0.lock();
+    let mut peers = Vec::new();
+    for (addr, stats) in snapshot.peers {
+        let key = (info_hash.clone(), addr.clone());
+        let peer = peer_info(addr, stats, counters.get(&key).copied(), now);
+        counters.insert(key, (now, peer.downloaded_bytes, peer.uploaded_bytes));
+        peers.push(peer);
+    }
+    // Forget peers that are gone
+    counters.retain(|(hash, addr), _| hash != &info_hash || peers.iter().any(|p| &p.addr == addr));
+    peers.sort_by(|a, b| b.download_bps.cmp(&a.download_bps));
+    Ok(peers)
+}
+
+/// Peer listing entry, with rates from the counters of the previous listing
+fn peer_info(
+    addr: String,
+    stats: PeerStats,
+    previous: Option<(Instant, u64, u64)>,
+    now: Instant,
+) -> PeerInfo {
+    let downloaded = stats.counters.fetched_bytes;
+    let uploaded = stats.counters.uploaded_bytes;
+    let rate = |prev: u64, cur: u64, secs: f64| (cur.saturating_sub(prev) as f64 / secs) as u64;
+    let (download_bps, upload_bps) = match previous {
+        Some((at, prev_down, prev_up)) if now > at => {
+            let secs = (now - at).as_secs_f64();
+            (
+                Some(rate(prev_down, downloaded, secs)),
+                Some(rate(prev_up, uploaded, secs)),
+            )
+        }
+        _ => (None, None),
+    };
+    PeerInfo {
+        addr,
+        state: stats.state.unwrap_or_else(|| "unknown".to_owned()),
+        connection_kind: stats.conn_kind,
+        downloaded_bytes: downloaded,
+        uploaded_bytes: uploaded,
+        download_bps,
+        upload_bps,
+    }
+}
+
+/// Connect to the given peers in addition to those found by trackers and DHT
+pub fn torrent_action_add_peers(
+    state: &State,
+    id: TorrentIdOrHash,
+    peers: Vec<SocketAddr>,
+) -> Result<EmptyJsonResponse, ApiError> {
+    let handle = state.api()?.mgr_handle(id)?;
+    let live = handle
+        .live()
+        .with_status_error(StatusCode::BAD_REQUEST, "Torrent is not running")?;
+    for addr in peers {
+        live.add_peer_if_not_seen(addr)
+            .with_status_error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to add peer")?;
+    }
+    Ok(EmptyJsonResponse {})
+}
+
+/// Ban IPs from all torrents. librqbit only reads the blocklist when a session starts, and
+/// restarting cuts off streams, so the bans are saved and reported as pending until the
+/// user applies them with [`crate::blocklist::apply_blocklist`].
+pub async fn torrent_action_ban_peers(
+    state: &State,
+    ips: Vec<IpAddr>,
+) -> Result<BlocklistStatus, ApiError> {
+    // The session blocklist can't hold IPv6 ranges, see `write_session_blocklist`
+    if ips.iter().any(|ip| ip.is_ipv6()) {
+        return Err(ApiError::new_from_text(
+            StatusCode::BAD_REQUEST,
+            "Banning IPv6 peers is not supported",
+        ));
+    }
+    update_banned_ips(state, |banned| {
+        for ip in ips {
+            if !banned.contains(&ip) {
+                info!("Banning peer {}", ip);
+                banned.push(ip);
+            }
+        }
+    })
+    .await
+}
+
+/// Lift bans added with [`torrent_action_ban_peers`], pending like the bans themselves
+pub async fn torrent_action_unban_peers(
+    state: &State,
+    ips: Vec<IpAddr>,
+) -> Result<BlocklistStatus, ApiError> {
+    update_banned_ips(state, |banned| banned.retain(|ip| !ips.contains(ip))).await
+}
+
+async fn update_banned_ips(
+    state: &State,
+    update: impl FnOnce(&mut Vec<IpAddr>),
+) -> Result<BlocklistStatus, ApiError> {
+    let mut config = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| s.config.clone())
+        .with_status_error(StatusCode::BAD_REQUEST, "Torrent session not configured")?;
+    let before = config.connections.banned_ips.clone();
+    update(&mut config.connections.banned_ips);
+    if config.connections.banned_ips != before {
+        state.configure(config).await?;
+    }
+    Ok(crate::blocklist::blocklist_status(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_peer_stats_from_snapshot() {
+        let snapshot: PeerStatsSnapshot = serde_json::from_value(serde_json::json!({
+            "peers": {
+                "1.2.3.4:6881": {
+                    "state": "live",
+                    "conn_kind": "tcp",
+                    "counters": {"fetched_bytes": 4096, "uploaded_bytes": 1024, "errors": 0}
+                },
+                "[::1]:6881": {"state": "connecting"}
+            }
+        }))
+        .unwrap();
+        assert_eq!(snapshot.peers.len(), 2);
+
+        let now = Instant::now();
+        let (addr, stats) = snapshot.peers.into_iter().next().unwrap();
+        let peer = peer_info(addr, stats, None, now);
+        assert_eq!(peer.addr, "1.2.3.4:6881");
+        assert_eq!(peer.state, "live");
+        assert_eq!(peer.connection_kind.as_deref(), Some("tcp"));
+        assert_eq!(peer.downloaded_bytes, 4096);
+        assert_eq!(peer.download_bps, None);
+    }
+
+    #[test]
+    fn test_peer_rates() {
+        let stats = PeerStats {
+            state: None,
+            conn_kind: None,
+            counters: PeerStatsCounters {
+                fetched_bytes: 3000,
+                uploaded_bytes: 500,
+            },
+        };
+        let then = Instant::now();
+        let now = then + Duration::from_secs(2);
+        let peer = peer_info("peer".to_owned(), stats, Some((then, 1000, 1000)), now);
+        assert_eq!(peer.state, "unknown");
+        assert_eq!(peer.download_bps, Some(1000));
+        // Counters going backwards (reconnect) don't underflow
+        assert_eq!(peer.upload_bps, Some(0));
+    }
+}
@@ -1,8 +1,51 @@
-use crate::settings_manager;
+use crate::error::CommandError;
+use crate::indexer::{SearchKind, SearchRequest, TV_CATEGORY};
+use crate::torrent_server::State;
+use librqbit_sha1_wrapper::{ISha1, Sha1};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use serde_bencode::value::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::OnceCell;
+use tokio::task::JoinSet;
 use tracing::{error, info, warn};
 
+/// At most this many `.torrent` files are fetched per search to find info hashes
+const MAX_HASH_FETCHES: usize = 20;
+const HASH_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long search results are reused for the same request
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+const CACHE_MAX_ENTRIES: usize = 200;
+
+type SearchOutcome = Result<Vec<TorrentResult>, CommandError>;
+
+/// A request, tagged with the indexer configuration it was searched with
+type CacheKey = (u64, SearchRequest);
+
+/// Recent and running searches, kept on [`State`]
+#[derive(Default)]
+pub struct SearchCache {
+    /// Bumped when the indexers change, so earlier searches are neither reused nor cached
+    generation: AtomicU64,
+    /// Recent results per request
+    results: Mutex<HashMap<CacheKey, (Instant, Vec<TorrentResult>)>>,
+    /// Searches currently running, joined by identical requests instead of searching again
+    in_flight: Mutex<HashMap<CacheKey, Arc<OnceCell<SearchOutcome>>>>,
+}
+
+impl SearchCache {
+    /// Forget all results, e.g. because an indexer or the Nacho Server changed
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.results.lock().clear();
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TorrentResult {
     pub title: String,
@@ -13,26 +56,12 @@ pub struct TorrentResult {
     pub magnet_url: Option<String>,
     pub indexer: String,
     pub publish_date: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ProwlarrResponse {
-    #[serde(rename = "title")]
-    title: String,
-    #[serde(rename = "size")]
-    size: Option<u64>,
-    #[serde(rename = "seeders")]
-    seeders: Option<u32>,
-    #[serde(rename = "peers")]
-    peers: Option<u32>,
-    #[serde(rename = "downloadUrl")]
-    download_url: Option<String>,
-    #[serde(rename = "magnetUrl")]
-    magnet_url: Option<String>,
-    #[serde(rename = "indexer")]
-    indexer: Option<String>,
-    #[serde(rename = "publishDate")]
-    publish_date: Option<String>,
+    /// Lowercase hex info hash, if it could be resolved
+    #[serde(default)]
+    pub info_hash: Option<String>,
+    /// Every indexer listing this release, when duplicates were merged
+    #[serde(default)]
+    pub indexers: Vec<String>,
 }
 
 #[tauri::command]
@@ -40,146 +69,238 @@ pub async fn search_torrents_by_imdb(
     app: AppHandle,
     imdb_id: String,
     title: Option<String>,
-) -> Result<Vec<TorrentResult>, String> {
+    force_refresh: Option<bool>,
+) -> Result<Vec<TorrentResult>, CommandError> {
     info!("Starting torrent search");
 
     // Determine search query - prefer title over IMDB ID
-    let search_query = if let Some(movie_title) = title.filter(|t| !t.is_empty()) {
+    let request = if let Some(movie_title) = title.filter(|t| !t.is_empty()) {
         info!("Using movie title for search: {}", movie_title);
-        movie_title
+        SearchRequest {
+            kind: SearchKind::Movie,
+            query: Some(movie_title),
+            ..Default::default()
+        }
     } else {
         info!("Using IMDB ID for search: {}", imdb_id);
-        let clean_imdb_id = imdb_id.trim_start_matches("tt");
-        format!("imdbid:{}", clean_imdb_id)
+        SearchRequest {
+            kind: SearchKind::Movie,
+            imdb_id: Some(format!("tt{}", imdb_id.trim_start_matches("tt"))),
+            ..Default::default()
+        }
     };
 
-    // Get Nacho Server URL from settings
-    info!("Fetching Nacho Server URL from settings");
-    let nacho_server_url = settings_manager::get_nacho_server_url(app.clone()).map_err(|e| {
-        error!("Failed to get Nacho Server URL from settings: {}", e);
-        format!("Failed to get Nacho Server URL: {}", e)
-    })?;
+    cached_search(app, request, force_refresh.unwrap_or(false)).await
+}
 
-    let nacho_server_url = match nacho_server_url {
-        Some(url) if !url.is_empty() => {
-            info!("Nacho Server URL found: {}", url);
-            url
-        }
-        _ => {
-            warn!("Nacho Server URL not configured in settings");
-            return Err("Nacho Server URL not configured. Please set it in Settings.".to_string());
+/// Search for a movie by IMDb ID, falling back to a "Title Year" text query
+pub(crate) async fn search_movie(
+    app: AppHandle,
+    imdb_id: Option<String>,
+    title: &str,
+    year: Option<&str>,
+) -> Result<Vec<TorrentResult>, CommandError> {
+    if let Some(imdb_id) = imdb_id.filter(|id| !id.is_empty()) {
+        info!("Searching by IMDB ID: {}", imdb_id);
+        let request = SearchRequest {
+            kind: SearchKind::Movie,
+            imdb_id: Some(format!("tt{}", imdb_id.trim_start_matches("tt"))),
+            ..Default::default()
+        };
+        match cached_search(app.clone(), request, false).await {
+            Ok(results) if !results.is_empty() => return Ok(results),
+            Ok(_) => info!("ID search found nothing, falling back to a text search"),
+            Err(e) => warn!("ID search failed, falling back to a text search: {}", e),
         }
+    }
+
+    let query = match year {
+        Some(year) => format!("{} {}", title, year),
+        None => title.to_owned(),
+    };
+    info!("Searching by name: {}", query);
+    let request = SearchRequest {
+        kind: SearchKind::Movie,
+        query: Some(query),
+        ..Default::default()
     };
+    cached_search(app, request, false).await
+}
 
-    // Get Nacho Auth Token from settings
-    info!("Fetching Nacho Auth Token from settings");
-    let auth_token = settings_manager::get_nacho_auth_token(app).map_err(|e| {
-        error!("Failed to get Nacho Auth Token from settings: {}", e);
-        format!("Failed to get Nacho Auth Token: {}", e)
-    })?;
+/// Search for a TV show, a season or a single episode. Tries an ID-based `tvsearch`
+/// first and falls back to a "Name S01E02" / "Name S01" text query.
+#[tauri::command]
+pub async fn search_torrents_for_show(
+    app: AppHandle,
+    tmdb_id: u64,
+    season: Option<u32>,
+    episode: Option<u32>,
+    force_refresh: Option<bool>,
+) -> Result<Vec<TorrentResult>, CommandError> {
+    let force_refresh = force_refresh.unwrap_or(false);
+    info!(
+        "Starting show search for TMDB ID {} (season {:?}, episode {:?})",
+        tmdb_id, season, episode
+    );
+    if episode.is_some() && season.is_none() {
+        return Err(CommandError::InvalidInput(
+            "An episode search needs a season".to_string(),
+        ));
+    }
 
-    let auth_token = match auth_token {
-        Some(token) if !token.is_empty() => {
-            info!("Nacho Auth Token found");
-            token
+    let show = crate::tmdb::get_tmdb_show(app.clone(), tmdb_id).await?;
+    let external_ids = match crate::tmdb::get_tmdb_show_external_ids(app.clone(), tmdb_id).await {
+        Ok(ids) => Some(ids),
+        Err(e) => {
+            warn!("Failed to get external IDs for show: {}", e);
+            None
         }
-        _ => {
-            warn!("Nacho Auth Token not configured in settings");
-            return Err("Nacho Auth Token not configured. Please set it in Settings.".to_string());
+    };
+    let tvdb_id = external_ids.as_ref().and_then(|ids| ids.tvdb_id);
+    let imdb_id = external_ids
+        .and_then(|ids| ids.imdb_id)
+        .or(show.imdb_id)
+        .filter(|id| !id.is_empty());
+
+    if tvdb_id.is_some() || imdb_id.is_some() {
+        info!("Searching by ID: tvdb {:?}, imdb {:?}", tvdb_id, imdb_id);
+        let request = SearchRequest {
+            kind: SearchKind::Tv,
+            imdb_id,
+            tvdb_id,
+            season,
+            episode,
+            ..Default::default()
+        };
+        match cached_search(app.clone(), request, force_refresh).await {
+            Ok(results) if !results.is_empty() => return Ok(results),
+            Ok(_) => info!("ID search found nothing, falling back to a text search"),
+            Err(e) => warn!("ID search failed, falling back to a text search: {}", e),
         }
+    }
+
+    let query = text_query(&show.name, season, episode);
+    info!("Searching by name: {}", query);
+    let request = SearchRequest {
+        query: Some(query),
+        categories: vec![TV_CATEGORY],
+        ..Default::default()
     };
+    cached_search(app, request, force_refresh).await
+}
 
-    // Construct search URL base - route through Nacho Server proxy
-    let base_url = format!(
-        "{}/api/prowlarr/api/v1/search",
-        nacho_server_url.trim_end_matches('/')
-    );
-    info!("Constructed search URL with query: {}", search_query);
+/// Scene-style text query, e.g. "Name S01E02" or "Name S01"
+fn text_query(name: &str, season: Option<u32>, episode: Option<u32>) -> String {
+    match (season, episode) {
+        (Some(season), Some(episode)) => format!("{} S{:02}E{:02}", name, season, episode),
+        (Some(season), None) => format!("{} S{:02}", name, season),
+        _ => name.to_string(),
+    }
+}
 
-    // Make HTTP request
-    info!("Sending HTTP request to Nacho Server with auth token");
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()
-        .map_err(|e| {
-            error!("Failed to create HTTP client: {}", e);
-            format!("Failed to create HTTP client: {}", e)
-        })?;
+/// [`run_search`] with results reused for [`CACHE_TTL`]. Concurrent identical requests
+/// share one search. `force_refresh` skips the cache but still joins a running search.
+async fn cached_search(
+    app: AppHandle,
+    request: SearchRequest,
+    force_refresh: bool,
+) -> SearchOutcome {
+    let state = app.state::<State>();
+    let cache = &state.search_cache;
+    let key = (cache.generation.load(Ordering::SeqCst), request);
+    if !force_refresh
+        && let Some((at, results)) = cache.results.lock().get(&key)
+        && at.elapsed() < CACHE_TTL
+    {
+        info!("Using cached results for {:?}", key.1);
+        return Ok(results.clone());
+    }
 
-    let response = client
-        .get(&base_url)
-        .query(&[("query", &search_query), ("type", &"movie".to_string())])
-        .header("X-Nacho-Auth", auth_token)
-        .send()
+    let cell = cache
+        .in_flight
+        .lock()
+        .entry(key.clone())
+        .or_insert_with(|| Arc::new(OnceCell::new()))
+        .clone();
+    let outcome = cell
+        .get_or_init(|| run_search(app.clone(), key.1.clone()))
         .await
-        .map_err(|e| {
-            error!("Failed to send request to Nacho Server: {}", e);
-            format!("Failed to connect to Nacho Server: {}", e)
-        })?;
-
-    let status = response.status();
-    info!("Nacho Server response status: {}", status);
+        .clone();
+    {
+        let mut in_flight = cache.in_flight.lock();
+        if in_flight.get(&key).is_some_and(|c| Arc::ptr_eq(c, &cell)) {
+            in_flight.remove(&key);
+        }
+    }
 
-    if !status.is_success() {
-        error!("Nacho Server returned error status: {}", status);
-        return Err(format!("Nacho Server returned error: {}", status));
+    if let Ok(results) = &outcome
+        && key.0 == cache.generation.load(Ordering::SeqCst)
+    {
+        let mut cache = cache.results.lock();
+        cache.retain(|_, (at, _)| at.elapsed() < CACHE_TTL);
+        if cache.len() >= CACHE_MAX_ENTRIES
+            && let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, (at, _))| *at)
+                .map(|(k, _)| k.clone())
+        {
+            cache.remove(&oldest);
+        }
+        cache.insert(key, (Instant::now(), results.clone()));
     }
+    outcome
+}
 
-    // Parse response
-    info!("Parsing Nacho Server response");
-    let prowlarr_results: Vec<ProwlarrResponse> = response.json().await.map_err(|e| {
-        error!("Failed to parse Nacho Server response: {}", e);
-        format!("Failed to parse Nacho Server response: {}", e)
-    })?;
+/// Query every configured indexer, merge duplicates and sort by seeders.
+/// Fails only if every indexer failed.
+async fn run_search(app: AppHandle, request: SearchRequest) -> SearchOutcome {
+    let indexers = crate::indexer::configured_indexers(&app)?;
 
-    info!(
-        "Received {} results from Nacho Server",
-        prowlarr_results.len()
-    );
+    let mut tasks = JoinSet::new();
+    for indexer in indexers {
+        let request = request.clone();
+        tasks.spawn(async move {
+            let results = indexer.search(&request).await;
+            (indexer.name().to_owned(), results)
+        });
+    }
 
-    // Convert to our result format
-    let results: Vec<TorrentResult> = prowlarr_results
-        .into_iter()
-        .enumerate()
-        .filter_map(|(idx, result)| {
-            info!("Processing result {}: {}", idx + 1, result.title);
-
-            // Need either download_url or magnet_url
-            let download_url = result
-                .download_url
-                .clone()
-                .or_else(|| result.magnet_url.clone());
-
-            if download_url.is_none() {
-                warn!(
-                    "Skipping result {} - no download URL or magnet link",
-                    idx + 1
-                );
-                return None;
+    let mut results = Vec::new();
+    let mut errors: Vec<CommandError> = Vec::new();
+    let mut succeeded = false;
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((name, Ok(found))) => {
+                info!("Received {} results from {}", found.len(), name);
+                succeeded = true;
+                results.extend(found);
             }
-
-            let torrent = TorrentResult {
-                title: result.title,
-                size: result.size.unwrap_or(0),
-                seeders: result.seeders.unwrap_or(0),
-                peers: result.peers.unwrap_or(0),
-                download_url: download_url.unwrap(),
-                magnet_url: result.magnet_url,
-                indexer: result.indexer.unwrap_or_else(|| "Unknown".to_string()),
-                publish_date: result.publish_date,
-            };
-
-            info!(
-                "  - Title: {}, Size: {} bytes, Seeders: {}, Peers: {}, Indexer: {}",
-                torrent.title, torrent.size, torrent.seeders, torrent.peers, torrent.indexer
-            );
-
-            Some(torrent)
-        })
-        .collect();
+            Ok((name, Err(e))) => {
+                error!("Search on {} failed: {}", name, e);
+                errors.push(e.with_message(format!("{}: {}", name, e)));
+            }
+            Err(e) => errors.push(CommandError::Internal(format!("Search task failed: {}", e))),
+        }
+    }
+    if !succeeded {
+        return Err(search_error(errors));
+    }
 
     info!("Successfully processed {} torrent results", results.len());
 
+    let state = app.state::<State>();
+    let resolve = state
+        .shared
+        .read()
+        .as_ref()
+        .is_some_and(|s| s.config.search.resolve_info_hashes);
+    if resolve {
+        let nacho = crate::indexer::nacho_credentials(&app)?;
+        resolve_info_hashes(&state, nacho.as_ref(), &mut results).await;
+    }
+    let results = merge_duplicates(results);
+    info!("{} results after merging duplicates", results.len());
+
     // Sort by seeders (descending)
     let mut sorted_results = results;
     sorted_results.sort_by(|a, b| b.seeders.cmp(&a.seeders));
@@ -188,122 +309,561 @@ pub async fn search_torrents_by_imdb(
     Ok(sorted_results)
 }
 
-#[tauri::command]
-pub async fn download_torrent_from_prowlarr(
-    state: tauri::State<'_, crate::torrent_server::State>,
-    app: AppHandle,
-    download_url: String,
-    tmdb_id: Option<u64>,
-    media_type: Option<String>,
-    episode_info: Option<(i32, i32)>,
-) -> Result<librqbit::api::ApiAddTorrentResponse, String> {
-    use base64::Engine;
+/// Error for a search where every indexer failed. An unreachable indexer says the least
+/// about what went wrong, so the first other failure is reported, with all messages.
+fn search_error(errors: Vec<CommandError>) -> CommandError {
+    let message = format!(
+        "Search failed. {}",
+        errors
+            .iter()
+            .map(CommandError::message)
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    errors
+        .into_iter()
+        .reduce(|best, e| match best {
+            CommandError::Network(_) if !matches!(e, CommandError::Network(_)) => e,
+            best => best,
+        })
+        .unwrap_or_else(|| CommandError::Internal(String::new()))
+        .with_message(message)
+}
 
-    info!("Starting torrent download");
-    info!("Download URL: {}", download_url);
-    info!("TMDB ID: {:?}", tmdb_id);
+/// The Nacho auth token if `url` points at the Nacho Server, so it is never sent to
+/// third-party indexers
+fn nacho_token_for<'a>(nacho: Option<&'a (String, String)>, url: &reqwest::Url) -> Option<&'a str> {
+    let (server, token) = nacho?;
+    let server = reqwest::Url::parse(server).ok()?;
+    // Compare parsed origins, a string prefix would also match "https://server.evil.org"
+    let same_origin = url.scheme() == server.scheme()
+        && url.host() == server.host()
+        && url.port_or_known_default() == server.port_or_known_default();
+    (same_origin && url.path().starts_with(server.path().trim_end_matches('/')))
+        .then_some(token.as_str())
+}
 
-    // Get Auth Token for authentication
-    let auth_token = crate::settings_manager::get_nacho_auth_token(app).map_err(|e| {
-        error!("Failed to get Nacho Auth Token: {}", e);
-        format!("Failed to get Auth Token: {}", e)
-    })?;
+/// Lowercase hex form of a hex or base32 info hash
+pub(crate) fn normalize_info_hash(hash: &str) -> Option<String> {
+    let hash = hash.trim();
+    if hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(hash.to_ascii_lowercase());
+    }
+    if hash.len() != 32 {
+        return None;
+    }
+    // Base32, 5 bits per character
+    let mut bits = 0u64;
+    let mut nbits = 0;
+    let mut out = String::with_capacity(40);
+    for c in hash.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        bits = (bits << 5) | value;
+        nbits += 5;
+        if nbits >= 8 {
+            nbits -= 8;
+            out.push_str(&format!("{:02x}", (bits >> nbits) & 0xff));
+        }
+    }
+    Some(out)
+}
+
+/// Info hash from the `xt=urn:btih:` parameter of a magnet link
+pub(crate) fn magnet_info_hash(uri: &str) -> Option<String> {
+    let url = tauri::Url::parse(uri)
+        .ok()
+        .filter(|u| u.scheme() == "magnet")?;
+    url.query_pairs().find_map(|(k, v)| {
+        (k == "xt")
+            .then(|| v.strip_prefix("urn:btih:").and_then(normalize_info_hash))
+            .flatten()
+    })
+}
 
-    let auth_token = match auth_token.filter(|k| !k.is_empty()) {
-        Some(token) => token,
-        None => {
-            warn!("No auth token available");
-            return Err("Nacho Auth Token not configured".to_string());
+/// Info hash of a `.torrent` file, the SHA-1 of its raw info dict
+fn torrent_info_hash(bytes: &[u8]) -> Option<String> {
+    let info = crate::torrent_peers::find_info(bytes)?;
+    let mut hasher = Sha1::new();
+    hasher.update(&bytes[info]);
+    Some(
+        hasher
+            .finish()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    )
+}
+
+/// Resolve an info hash by fetching the download URL. Indexers answer with either
+/// a `.torrent` file or a redirect to a magnet link.
+async fn fetch_info_hash(
+    client: &reqwest::Client,
+    auth_token: Option<&str>,
+    download_url: &reqwest::Url,
+) -> Option<String> {
+    let mut request = client.get(download_url.clone()).timeout(HASH_FETCH_TIMEOUT);
+    if let Some(token) = auth_token {
+        request = request.header("X-Nacho-Auth", token);
+    }
+    let response = request.send().await.ok()?;
+    if response.status().is_redirection() {
+        let location = response.headers().get("location")?.to_str().ok()?;
+        return magnet_info_hash(location);
+    }
+    if !response.status().is_success() {
+        return None;
+    }
+    let bytes = response.bytes().await.ok()?;
+    torrent_info_hash(&bytes).or_else(|| magnet_info_hash(std::str::from_utf8(&bytes).ok()?.trim()))
+}
+
+/// Fill in missing info hashes by fetching a bounded number of `.torrent` files, when
+/// enabled in the search settings. Only results whose title is also listed by another
+/// indexer are worth fetching.
+async fn resolve_info_hashes(
+    state: &State,
+    nacho: Option<&(String, String)>,
+    results: &mut [TorrentResult],
+) {
+    let mut title_counts: HashMap<String, usize> = HashMap::new();
+    for result in results.iter() {
+        *title_counts.entry(result.title.to_lowercase()).or_default() += 1;
+    }
+
+    // Magnet redirects can't be followed, so use a client that reports them
+    let client = match crate::proxy::client_builder(state)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to create HTTP client for info hashes: {}", e);
+            return;
         }
     };
 
-    // Fetch the download URL - it will return either a magnet link or torrent file
-    info!("Fetching download from custom endpoint");
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
+    let mut tasks = JoinSet::new();
+    for (idx, result) in results
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.info_hash.is_none() && title_counts[&r.title.to_lowercase()] > 1)
+        .take(MAX_HASH_FETCHES)
+    {
+        let Ok(url) = reqwest::Url::parse(&result.download_url) else {
+            continue;
+        };
+        let client = client.clone();
+        let auth_token = nacho_token_for(nacho, &url).map(str::to_owned);
+        tasks.spawn(async move {
+            (
+                idx,
+                fetch_info_hash(&client, auth_token.as_deref(), &url).await,
+            )
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((idx, Some(hash))) = joined {
+            results[idx].info_hash = Some(hash);
+        }
+    }
+}
+
+/// Merge results sharing an info hash into one entry listing every indexer.
+/// Indexers often scrape the same public trackers, so identical seeder counts are
+/// taken to be the same swarm and only distinct counts are added up.
+fn merge_duplicates(results: Vec<TorrentResult>) -> Vec<TorrentResult> {
+    let mut merged: Vec<TorrentResult> = Vec::with_capacity(results.len());
+    let mut by_hash: HashMap<String, usize> = HashMap::new();
+    let mut seeder_counts: HashMap<usize, Vec<(u32, u32)>> = HashMap::new();
+
+    for result in results {
+        let Some(hash) = result.info_hash.clone() else {
+            merged.push(result);
+            continue;
+        };
+        match by_hash.get(&hash) {
+            Some(&idx) => {
+                let existing = &mut merged[idx];
+                for indexer in result.indexers {
+                    if !existing.indexers.contains(&indexer) {
+                        existing.indexers.push(indexer);
+                    }
+                }
+                if existing.magnet_url.is_none() {
+                    existing.magnet_url = result.magnet_url;
+                }
+                let counts = seeder_counts.entry(idx).or_default();
+                if !counts.contains(&(result.seeders, result.peers)) {
+                    counts.push((result.seeders, result.peers));
+                }
+                // Keep the best-seeded listing as the primary one
+                if result.seeders > existing.seeders {
+                    existing.seeders = result.seeders;
+                    existing.peers = result.peers;
+                    existing.title = result.title;
+                    existing.download_url = result.download_url;
+                    existing.indexer = result.indexer;
+                }
+            }
+            None => {
+                by_hash.insert(hash, merged.len());
+                seeder_counts.insert(merged.len(), vec![(result.seeders, result.peers)]);
+                merged.push(result);
+            }
+        }
+    }
+
+    for (idx, counts) in seeder_counts {
+        if counts.len() > 1 {
+            merged[idx].seeders = counts.iter().map(|(s, _)| s).sum();
+            merged[idx].peers = counts.iter().map(|(_, p)| p).sum();
+        }
+    }
+    merged
+}
+
+/// What a download link returned
+#[derive(Debug, Clone, PartialEq)]
+enum DownloadPayload {
+    Magnet(String),
+    Torrent(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TorrentPreviewFile {
+    pub path: String,
+    pub size: u64,
+}
+
+/// Contents of a download, shown before it is added
+#[derive(Debug, Clone, Serialize)]
+pub struct TorrentPreview {
+    pub name: Option<String>,
+    pub info_hash: Option<String>,
+    /// Unknown for magnets that don't carry it
+    pub total_size: Option<u64>,
+    /// Empty for magnets, their file list is only known once metadata is fetched
+    pub files: Vec<TorrentPreviewFile>,
+    pub is_magnet: bool,
+    /// Reasons the download looks like it isn't what it claims to be
+    pub warnings: Vec<String>,
+}
+
+const EXECUTABLE_EXTENSIONS: &[&str] = &[
+    "exe", "msi", "bat", "cmd", "com", "scr", "pif", "vbs", "js", "jar", "ps1", "apk", "dmg",
+    "pkg", "lnk", "sh", "app",
+];
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "rar", "7z", "tar", "gz", "bz2", "xz"];
+const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "m4v", "avi", "mov", "webm", "ts", "wmv"];
+/// Redirects followed by hand when fetching a download, to catch redirects to magnets
+const MAX_REDIRECTS: usize = 5;
+
+/// Tell apart a `.torrent` file, a magnet link and an error page by their contents,
+/// since indexers often send the wrong content type
+fn sniff_payload(bytes: &[u8], content_type: &str) -> Result<DownloadPayload, String> {
+    if crate::torrent_peers::find_info(bytes).is_some() {
+        return Ok(DownloadPayload::Torrent(bytes.to_vec()));
+    }
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    if text.starts_with("magnet:") {
+        return Ok(DownloadPayload::Magnet(text.to_string()));
+    }
+    let lower = text.to_lowercase();
+    if content_type.contains("html") || lower.starts_with("<!doctype") || lower.starts_with("<html")
+    {
+        let title = lower
+            .find("<title>")
+            .and_then(|start| {
+                let rest = text.get(start + 7..)?;
+                rest.find('<').map(|end| rest[..end].trim().to_string())
+            })
+            .filter(|t| !t.is_empty());
+        return Err(match title {
+            Some(title) => format!(
+                "The indexer returned a web page instead of a torrent: {}",
+                title
+            ),
+            None => "The indexer returned a web page instead of a torrent".to_string(),
+        });
+    }
+    Err(format!(
+        "The download is neither a torrent file nor a magnet link: {}",
+        text.chars().take(50).collect::<String>()
+    ))
+}
+
+/// Fetch a download link, following redirects to other URLs or to a magnet link
+async fn fetch_download(
+    app: &AppHandle,
+    download_url: &str,
+) -> Result<DownloadPayload, CommandError> {
+    if download_url.starts_with("magnet:") {
+        return Ok(DownloadPayload::Magnet(download_url.to_string()));
+    }
+
+    // Downloads through the Nacho Server need its auth token, direct indexer links carry their own key
+    let nacho = crate::indexer::nacho_credentials(app).map_err(|e| {
+        error!("Failed to get Nacho credentials: {}", e);
+        e
+    })?;
+    let client = crate::proxy::client_builder(&app.state::<State>())
+        .timeout(Duration::from_secs(30))
+        .redirect(reqwest::redirect::Policy::none())
         .build()
         .map_err(|e| {
             error!("Failed to create HTTP client: {}", e);
-            format!("Failed to create HTTP client: {}", e)
+            CommandError::request("Failed to create HTTP client", e)
         })?;
 
-    let response = client
-        .get(&download_url)
-        .header("X-Nacho-Auth", &auth_token)
-        .send()
-        .await
-        .map_err(|e| {
+    let mut url = reqwest::Url::parse(download_url)
+        .map_err(|e| CommandError::InvalidInput(format!("Invalid download URL: {}", e)))?;
+    for _ in 0..=MAX_REDIRECTS {
+        let mut request = client.get(url.clone());
+        if let Some(token) = nacho_token_for(nacho.as_ref(), &url) {
+            request = request.header("X-Nacho-Auth", token);
+        }
+        let response = request.send().await.map_err(|e| {
             error!("Failed to fetch download: {}", e);
-            format!("Failed to fetch download: {}", e)
+            CommandError::request("Failed to fetch download", e)
         })?;
 
-    if !response.status().is_success() {
-        error!("Download endpoint returned error: {}", response.status());
-        return Err(format!("Download failed: HTTP {}", response.status()));
-    }
-
-    // Check content type to determine if it's a magnet link or torrent file
-    let content_type = response
-        .headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-
-    let final_source = if content_type.contains("text/plain") {
-        // Likely a magnet link
-        let body = response.text().await.map_err(|e| {
-            error!("Failed to read response body: {}", e);
-            format!("Failed to read response: {}", e)
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get("location")
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| {
+                    CommandError::InvalidResponse(
+                        "Download redirected without a location".to_string(),
+                    )
+                })?;
+            if location.starts_with("magnet:") {
+                info!("Download redirected to a magnet link");
+                return Ok(DownloadPayload::Magnet(location.to_string()));
+            }
+            url = url
+                .join(location)
+                .map_err(|e| CommandError::InvalidResponse(format!("Invalid redirect: {}", e)))?;
+            continue;
+        }
+        if !response.status().is_success() {
+            error!("Download endpoint returned error: {}", response.status());
+            let status = response.status();
+            return Err(CommandError::from_status(
+                status,
+                format!("Download failed: HTTP {}", status),
+            ));
+        }
+
+        let content_type = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase();
+        let bytes = response.bytes().await.map_err(|e| {
+            error!("Failed to read download: {}", e);
+            CommandError::request("Failed to read download", e)
         })?;
+        return sniff_payload(&bytes, &content_type).map_err(CommandError::InvalidResponse);
+    }
+    Err(CommandError::InvalidResponse(
+        "Too many redirects".to_string(),
+    ))
+}
 
-        if body.trim().starts_with("magnet:") {
-            info!("Received magnet link");
-            body.trim().to_string()
-        } else {
-            error!(
-                "Expected magnet link but got: {}",
-                &body[..50.min(body.len())]
-            );
-            return Err("Invalid response: expected magnet link".to_string());
+fn bencode_str(value: &Value) -> Option<String> {
+    match value {
+        Value::Bytes(b) => Some(String::from_utf8_lossy(b).into_owned()),
+        _ => None,
+    }
+}
+
+fn bencode_int(value: &Value) -> Option<u64> {
+    match value {
+        Value::Int(i) => u64::try_from(*i).ok(),
+        _ => None,
+    }
+}
+
+/// Name, info hash and file list of a download
+fn preview_payload(payload: &DownloadPayload) -> Result<TorrentPreview, String> {
+    let bytes = match payload {
+        DownloadPayload::Magnet(uri) => {
+            let url = tauri::Url::parse(uri).map_err(|e| format!("Invalid magnet link: {}", e))?;
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == name)
+                    .map(|(_, v)| v.into_owned())
+            };
+            return Ok(TorrentPreview {
+                name: param("dn"),
+                info_hash: magnet_info_hash(uri),
+                total_size: param("xl").and_then(|xl| xl.parse().ok()),
+                files: Vec::new(),
+                is_magnet: true,
+                warnings: Vec::new(),
+            });
         }
-    } else {
-        // Assume it's a torrent file
-        info!("Received torrent file");
-        let torrent_bytes = response.bytes().await.map_err(|e| {
-            error!("Failed to read torrent file bytes: {}", e);
-            format!("Failed to read torrent file: {}", e)
-        })?;
+        DownloadPayload::Torrent(bytes) => bytes,
+    };
+
+    let info = match serde_bencode::from_bytes::<Value>(bytes) {
+        Ok(Value::Dict(mut meta)) => match meta.remove(&b"info"[..]) {
+            Some(Value::Dict(info)) => info,
+            _ => return Err("Invalid torrent file: missing info".to_string()),
+        },
+        Ok(_) => return Err("Invalid torrent file".to_string()),
+        Err(e) => return Err(format!("Invalid torrent file: {}", e)),
+    };
+    let name = info.get(&b"name"[..]).and_then(bencode_str);
+    let files: Vec<TorrentPreviewFile> = match info.get(&b"files"[..]) {
+        Some(Value::List(files)) => files
+            .iter()
+            .filter_map(|file| {
+                let Value::Dict(file) = file else { return None };
+                let path = match file.get(&b"path"[..]) {
+                    Some(Value::List(parts)) => parts
+                        .iter()
+                        .filter_map(bencode_str)
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                    _ => return None,
+                };
+                Some(TorrentPreviewFile {
+                    path,
+                    size: file.get(&b"length"[..]).and_then(bencode_int)?,
+                })
+            })
+            .collect(),
+        _ => info
+            .get(&b"length"[..])
+            .and_then(bencode_int)
+            .map(|size| TorrentPreviewFile {
+                path: name.clone().unwrap_or_default(),
+                size,
+            })
+            .into_iter()
+            .collect(),
+    };
 
-        // Convert to base64
-        let base64_torrent = base64::engine::general_purpose::STANDARD.encode(&torrent_bytes);
-        info!("Successfully encoded torrent file");
+    Ok(TorrentPreview {
+        name,
+        info_hash: torrent_info_hash(bytes),
+        total_size: Some(files.iter().map(|f| f.size).sum()),
+        warnings: suspicious_contents(&files),
+        files,
+        is_magnet: false,
+    })
+}
 
-        // Use base64 format for librqbit
-        format!("base64:{}", base64_torrent)
+/// Warnings for files that don't belong in a video release
+fn suspicious_contents(files: &[TorrentPreviewFile]) -> Vec<String> {
+    let extension = |path: &str| {
+        path.rsplit_once('.')
+            .map(|(_, ext)| ext.to_lowercase())
+            .unwrap_or_default()
     };
+    let mut warnings = Vec::new();
+
+    let executables: Vec<&str> = files
+        .iter()
+        .filter(|f| EXECUTABLE_EXTENSIONS.contains(&extension(&f.path).as_str()))
+        .map(|f| f.path.as_str())
+        .collect();
+    if !executables.is_empty() {
+        warnings.push(format!(
+            "Contains executable files: {}",
+            executables.join(", ")
+        ));
+    }
+
+    let has_video = files
+        .iter()
+        .any(|f| VIDEO_EXTENSIONS.contains(&extension(&f.path).as_str()));
+    let has_archive = files
+        .iter()
+        .any(|f| ARCHIVE_EXTENSIONS.contains(&extension(&f.path).as_str()));
+    if !has_video && has_archive {
+        warnings.push("Contains only archives, no video files".to_string());
+    } else if !has_video && !files.is_empty() {
+        warnings.push("Contains no video files".to_string());
+    }
+    warnings
+}
+
+/// Fetch a download and show what it contains, without adding it
+#[tauri::command]
+pub async fn preview_torrent_download(
+    app: AppHandle,
+    download_url: String,
+) -> Result<TorrentPreview, CommandError> {
+    preview_payload(&fetch_download(&app, &download_url).await?)
+        .map_err(CommandError::InvalidResponse)
+}
+
+/// Fetch a download and add it. Downloads with suspicious contents (see
+/// [`preview_torrent_download`]) are refused unless `allow_suspicious` is set.
+#[tauri::command]
+pub async fn download_torrent_from_prowlarr(
+    state: tauri::State<'_, crate::torrent_server::State>,
+    app: AppHandle,
+    download_url: String,
+    tmdb_id: Option<u64>,
+    media_type: Option<String>,
+    episode_info: Option<(i32, i32)>,
+    allow_suspicious: Option<bool>,
+) -> Result<librqbit::api::ApiAddTorrentResponse, CommandError> {
+    use base64::Engine;
+
+    info!("Starting torrent download");
+    info!("Download URL: {}", download_url);
+    info!("TMDB ID: {:?}", tmdb_id);
+
+    let payload = fetch_download(&app, &download_url).await?;
+    let preview = preview_payload(&payload).map_err(CommandError::InvalidResponse)?;
+    info!(
+        "Download contains {:?} ({} files, {:?} bytes)",
+        preview.name,
+        preview.files.len(),
+        preview.total_size
+    );
+    if !preview.warnings.is_empty() {
+        warn!("Suspicious download: {}", preview.warnings.join("; "));
+        if !allow_suspicious.unwrap_or(false) {
+            return Err(CommandError::SuspiciousDownload(format!(
+                "Suspicious download: {}",
+                preview.warnings.join("; ")
+            )));
+        }
+    }
 
     info!("Adding torrent to download queue");
 
-    // Add torrent using the appropriate method
-    let response = if final_source.starts_with("base64:") {
-        let base64_content = final_source.strip_prefix("base64:").unwrap();
-        crate::torrent_server::torrent_create_from_base64_file(
+    let response = match payload {
+        DownloadPayload::Torrent(bytes) => crate::torrent_server::torrent_create_from_base64_file(
             &state,
-            base64_content.to_string(),
+            base64::engine::general_purpose::STANDARD.encode(&bytes),
             None,
         )
         .await
         .map_err(|e| {
-            error!("Failed to add torrent from base64: {:?}", e);
-            format!("Failed to add torrent: {:?}", e)
-        })?
-    } else {
-        crate::torrent_server::torrent_create_from_url(&state, final_source, None)
-            .await
-            .map_err(|e| {
-                error!("Failed to add torrent from URL: {:?}", e);
-                format!("Failed to add torrent: {:?}", e)
-            })?
+            error!("Failed to add torrent from file: {:?}", e);
+            CommandError::Internal(format!("Failed to add torrent: {:?}", e))
+        })?,
+        DownloadPayload::Magnet(magnet) => {
+            crate::torrent_server::torrent_create_from_url(&state, magnet, None)
+                .await
+                .map_err(|e| {
+                    error!("Failed to add torrent from magnet: {:?}", e);
+                    CommandError::Internal(format!("Failed to add torrent: {:?}", e))
+                })?
+        }
     };
 
     info!("Torrent added successfully with ID: {:?}", response.id);
@@ -351,3 +911,141 @@ pub async fn download_torrent_from_prowlarr(
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(indexer: &str, hash: Option<&str>, seeders: u32) -> TorrentResult {
+        TorrentResult {
+            title: "Movie 1080p".to_owned(),
+            size: 1,
+            seeders,
+            peers: 0,
+            download_url: format!("https://{}/download", indexer),
+            magnet_url: None,
+            indexer: indexer.to_owned(),
+            publish_date: None,
+            info_hash: hash.map(str::to_owned),
+            indexers: vec![indexer.to_owned()],
+        }
+    }
+
+    #[test]
+    fn test_magnet_info_hash() {
+        let hex = "c9e15763f722f23e98a29decdfae341b98d53056";
+        assert_eq!(
+            magnet_info_hash(&format!("magnet:?xt=urn:btih:{}&dn=x", hex.to_uppercase())),
+            Some(hex.to_owned())
+        );
+        // Base32 of the same hash
+        assert_eq!(
+            magnet_info_hash("magnet:?xt=urn:btih:ZHQVOY7XELZD5GFCTXWN7LRUDOMNKMCW"),
+            Some(hex.to_owned())
+        );
+        assert_eq!(magnet_info_hash("https://example.com/file.torrent"), None);
+    }
+
+    #[test]
+    fn test_sniff_payload() {
+        let torrent = b"d4:infod6:lengthi100e4:name9:movie.mkv12:piece lengthi16384e6:pieces0:ee";
+        // Content types are often wrong
+        assert_eq!(
+            sniff_payload(torrent, "text/plain"),
+            Ok(DownloadPayload::Torrent(torrent.to_vec()))
+        );
+        assert_eq!(
+            sniff_payload(b"  magnet:?xt=urn:btih:abc\n", "application/x-bittorrent"),
+            Ok(DownloadPayload::Magnet(
+                "magnet:?xt=urn:btih:abc".to_owned()
+            ))
+        );
+        let err = sniff_payload(
+            b"<!DOCTYPE html><html><head><title>Rate limited</title></head></html>",
+            "",
+        )
+        .unwrap_err();
+        assert!(err.ends_with("Rate limited"));
+        assert!(sniff_payload(b"{\"error\":1}", "application/json").is_err());
+        // Lengths that overflow or point backwards, and deep nesting, are rejected
+        assert!(sniff_payload(b"d1:a18446744073709551592:", "").is_err());
+        assert!(sniff_payload(b"d1:a18446744073709551615:", "").is_err());
+        let nested = [b"d4:info".as_slice(), &[b'l'; 100_000]].concat();
+        assert!(sniff_payload(&nested, "").is_err());
+    }
+
+    #[test]
+    fn test_preview_payload() {
+        let torrent = b"d4:infod5:filesld6:lengthi1000e4:pathl9:movie.mkveed6:lengthi20e4:pathl9:setup.exeeee4:name5:Movie12:piece lengthi16384e6:pieces0:ee";
+        let preview = preview_payload(&DownloadPayload::Torrent(torrent.to_vec())).unwrap();
+        assert_eq!(preview.name.as_deref(), Some("Movie"));
+        assert_eq!(preview.total_size, Some(1020));
+        assert_eq!(preview.files.len(), 2);
+        assert_eq!(
+            preview.warnings,
+            vec!["Contains executable files: setup.exe"]
+        );
+
+        let archives = [TorrentPreviewFile {
+            path: "Movie/movie.part1.rar".to_owned(),
+            size: 1,
+        }];
+        assert_eq!(
+            suspicious_contents(&archives),
+            vec!["Contains only archives, no video files"]
+        );
+
+        let magnet = "magnet:?xt=urn:btih:c9e15763f722f23e98a29decdfae341b98d53056&dn=Movie&xl=42";
+        let preview = preview_payload(&DownloadPayload::Magnet(magnet.to_owned())).unwrap();
+        assert!(preview.is_magnet);
+        assert_eq!(preview.name.as_deref(), Some("Movie"));
+        assert_eq!(preview.total_size, Some(42));
+    }
+
+    #[test]
+    fn test_nacho_token_for() {
+        let nacho = ("https://nacho.example.com/".to_owned(), "secret".to_owned());
+        let token = |url: &str| nacho_token_for(Some(&nacho), &reqwest::Url::parse(url).unwrap());
+        assert_eq!(token("https://nacho.example.com/api/dl/1"), Some("secret"));
+        assert_eq!(
+            token("https://nacho.example.com:443/api/dl/1"),
+            Some("secret")
+        );
+        assert_eq!(token("https://nacho.example.com.evil.org/api/dl/1"), None);
+        assert_eq!(token("http://nacho.example.com/api/dl/1"), None);
+        assert_eq!(token("https://nacho.example.com:8443/api/dl/1"), None);
+        assert_eq!(token("https://tracker.example.org/dl/1"), None);
+    }
+
+    #[test]
+    fn test_merge_duplicates() {
+        let merged = merge_duplicates(vec![
+            result("a", Some("abc"), 10),
+            result("b", Some("abc"), 10),
+            result("c", Some("abc"), 4),
+            result("d", None, 7),
+        ]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].indexers, vec!["a", "b", "c"]);
+        // The identical count from "b" is the same swarm as "a"
+        assert_eq!(merged[0].seeders, 14);
+        assert_eq!(merged[0].indexer, "a");
+    }
+
+    #[test]
+    fn test_search_error() {
+        let e = search_error(vec![
+            CommandError::Network("A: Failed to connect".to_owned()),
+            CommandError::Unauthorized("B: Returned error: 401".to_owned()),
+            CommandError::NotFound("C: Returned error: 404".to_owned()),
+        ]);
+        assert!(matches!(e, CommandError::Unauthorized(_)));
+        assert_eq!(
+            e.message(),
+            "Search failed. A: Failed to connect; B: Returned error: 401; C: Returned error: 404"
+        );
+
+        let e = search_error(vec![CommandError::Network("A: Timed out".to_owned())]);
+        assert!(e.retryable());
+    }
+}
@@ -1,12 +1,23 @@
 use std::{
+    collections::HashSet,
     fs::{File, OpenOptions},
-    io::{BufReader, BufWriter},
-    path::Path,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
+use crate::bandwidth::BandwidthScheduler;
+use crate::blocklist::BlocklistStatus;
 use crate::config::RqbitDesktopConfig;
+use crate::indexer::CapsCache;
+use crate::network::KillSwitch;
+use crate::rss::RssPoller;
 use crate::torrent_db::TorrentDb;
+use crate::torrent_events::TorrentEventBus;
+use crate::torrent_peers::PeerCounters;
+use crate::torrent_search::SearchCache;
+use crate::watchlist::Watchlist;
+use crate::{disk, transmux};
 use anyhow::Context;
 use http::StatusCode;
 use librqbit::{
@@ -17,17 +28,23 @@ use librqbit::{
         TorrentListResponse, TorrentStats,
     },
     dht::PersistentDhtConfig,
+    limits::LimitsConfig,
     session_stats::snapshot::SessionStatsSnapshot,
     tracing_subscriber_config_utils::InitLoggingResult,
 };
 use librqbit_dualstack_sockets::TcpListener;
-use parking_lot::RwLock;
+use metrics_exporter_prometheus::PrometheusHandle;
+use once_cell::sync::OnceCell;
+use parking_lot::{Mutex, RwLock};
 use serde::Serialize;
-use tracing::{debug_span, warn};
+use tauri::{AppHandle, Emitter};
+use tokio::task::JoinHandle;
+use tracing::{Instrument, debug_span, info, warn};
 
 pub struct StateShared {
     pub config: RqbitDesktopConfig,
     pub api: Option<Api>,
+    pub http_api: Option<JoinHandle<()>>,
 }
 
 pub struct State {
@@ -35,6 +52,22 @@ pub struct State {
     pub shared: Arc<RwLock<Option<StateShared>>>,
     pub init_logging: InitLoggingResult,
     pub torrent_db: Arc<TorrentDb>,
+    pub events: Arc<TorrentEventBus>,
+    pub bandwidth: Arc<BandwidthScheduler>,
+    /// Info hashes the post-download pipeline is currently running for
+    pub post_processing: Mutex<HashSet<String>>,
+    pub peer_counters: PeerCounters,
+    /// Blocklist sources merged into the running session
+    pub blocklist_status: Mutex<Option<BlocklistStatus>>,
+    pub kill_switch: KillSwitch,
+    /// Serializes queue passes from the background task and from commands
+    pub queue_lock: tokio::sync::Mutex<()>,
+    /// Proxy for the app's own HTTP requests, from the current config
+    pub http_proxy: RwLock<Option<reqwest::Proxy>>,
+    pub caps_cache: CapsCache,
+    pub search_cache: SearchCache,
+    pub watchlist: Watchlist,
+    pub rss: RssPoller,
 }
 
 pub fn read_config(path: &str) -> anyhow::Result<RqbitDesktopConfig> {
@@ -60,114 +93,146 @@ pub fn write_config(path: &str, config: &RqbitDesktopConfig) -> anyhow::Result<(
     Ok(())
 }
 
-pub async fn api_from_config(
-    init_logging: &InitLoggingResult,
-    config: &RqbitDesktopConfig,
-) -> anyhow::Result<Api> {
-    config
-        .validate()
-        .context("error validating configuration")?;
-    let persistence = if config.persistence.disable {
-        None
-    } else {
-        Some(SessionPersistenceConfig::Json {
-            folder: if config.persistence.folder == Path::new("") {
-                None
-            } else {
-                Some(config.persistence.folder.clone())
-            },
+/// The prometheus recorder can only be installed once per process, so the
+/// handle is shared by every HTTP API instance.
+static PROMETHEUS_HANDLE: OnceCell<Option<PrometheusHandle>> = OnceCell::new();
+
+fn prometheus_handle() -> Option<PrometheusHandle> {
+    PROMETHEUS_HANDLE
+        .get_or_init(|| {
+            match metrics_exporter_prometheus::PrometheusBuilder::new().install_recorder() {
+                Ok(handle) => Some(handle),
+                Err(e) => {
+                    warn!("error installting prometheus recorder: {e:#}");
+                    None
+                }
+            }
         })
-    };
+        .clone()
+}
 
-    let (listen, connect) = config.connections.as_listener_and_connect_opts();
+/// Start the HTTP API, and the UPnP media server if enabled, for a running session.
+/// They run in their own task so they can be restarted without touching the session.
+pub async fn start_http_api(
+    api: &Api,
+    config: &RqbitDesktopConfig,
+) -> anyhow::Result<Option<JoinHandle<()>>> {
+    if config.http_api.disable {
+        return Ok(None);
+    }
 
-    let mut http_api_opts = librqbit::http_api::HttpApiOptions {
+    let http_api_opts = librqbit::http_api::HttpApiOptions {
         read_only: config.http_api.read_only,
         basic_auth: None,
+        prometheus_handle: prometheus_handle(),
         ..Default::default()
     };
 
-    // We need to start prometheus recorder earlier than session.
-    if !config.http_api.disable {
-        match metrics_exporter_prometheus::PrometheusBuilder::new().install_recorder() {
-            Ok(handle) => {
-                http_api_opts.prometheus_handle = Some(handle);
-            }
-            Err(e) => {
-                warn!("error installting prometheus recorder: {e:#}");
+    // Bind here so a taken port is reported to the caller instead of only logged
+    let listen_addr = config.http_api.listen_addr;
+    let listener = TcpListener::bind_tcp(listen_addr, Default::default())
+        .with_context(|| format!("error listening on {}", listen_addr))?;
+    let api = api.clone();
+    let session = api.session().clone();
+    let upnp_adapter = if config.upnp.enable_server {
+        let friendly_name = config
+            .upnp
+            .server_friendly_name
+            .as_ref()
+            .map(|f| f.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .unwrap_or_else(|| {
+                format!(
+                    "rqbit-desktop@{}",
+                    gethostname::gethostname().to_string_lossy()
+                )
+            });
+
+        let mut upnp_adapter = session
+            .make_upnp_adapter(friendly_name, config.http_api.listen_addr.port())
+            .await
+            .context("error starting UPnP server")?;
+        let router = upnp_adapter.take_router()?;
+        Some((upnp_adapter, router))
+    } else {
+        None
+    };
+
+    let http_api_task = async move {
+        let (upnp_adapter, upnp_router) = match upnp_adapter {
+            Some((adapter, router)) => (Some(adapter), Some(router)),
+            None => (None, None),
+        };
+        let http_api = librqbit::http_api::HttpApi::new(api.clone(), Some(http_api_opts))
+            .make_http_api_and_run(listener, upnp_router);
+        match upnp_adapter {
+            Some(adapter) => tokio::select! {
+                r = http_api => r,
+                r = adapter.run_ssdp_forever().instrument(debug_span!("ssdp")) => r,
+            },
+            None => http_api.await,
+        }
+    };
+
+    Ok(Some(tokio::spawn(
+        async move {
+            if let Err(e) = http_api_task.await {
+                warn!("http api stopped with error: {e:#}");
             }
         }
-    }
+        .instrument(debug_span!("http_api")),
+    )))
+}
 
-    let session = Session::new_with_opts(
-        config.default_download_location.clone(),
-        SessionOptions {
-            disable_dht: config.dht.disable,
-            disable_dht_persistence: config.dht.disable_persistence,
-            dht_config: Some(PersistentDhtConfig {
-                config_filename: Some(config.dht.persistence_filename.clone()),
-                ..Default::default()
-            }),
-            persistence,
-            connect: Some(connect),
-            listen,
-            fastresume: config.persistence.fastresume,
-            ratelimits: config.ratelimits,
-            #[cfg(feature = "disable-upload")]
-            disable_upload: config.disable_upload,
-            ..Default::default()
-        },
-    )
-    .await
-    .context("couldn't set up librqbit session")?;
-
-    let api = Api::new(
-        session.clone(),
-        Some(init_logging.rust_log_reload_tx.clone()),
-        Some(init_logging.line_broadcast.clone()),
-    );
+/// Stop a running HTTP API task and wait until its listener is released
+async fn stop_http_api(handle: Option<JoinHandle<()>>) {
+    if let Some(handle) = handle {
+        handle.abort();
+        let _ = handle.await;
+    }
+}
 
-    if !config.http_api.disable {
-        let listen_addr = config.http_api.listen_addr;
-        let api = api.clone();
-        let upnp_router = if config.upnp.enable_server {
-            let friendly_name = config
-                .upnp
-                .server_friendly_name
-                .as_ref()
-                .map(|f| f.trim())
-                .filter(|s| !s.is_empty())
-                .map(|s| s.to_owned())
-                .unwrap_or_else(|| {
-                    format!(
-                        "rqbit-desktop@{}",
-                        gethostname::gethostname().to_string_lossy()
-                    )
-                });
-
-            let mut upnp_adapter = session
-                .make_upnp_adapter(friendly_name, config.http_api.listen_addr.port())
-                .await
-                .context("error starting UPnP server")?;
-            let router = upnp_adapter.take_router()?;
-            session.spawn(debug_span!("ssdp"), "ssdp", async move {
-                upnp_adapter.run_ssdp_forever().await
-            });
-            Some(router)
-        } else {
-            None
-        };
-        let http_api_task = async move {
-            let listener = TcpListener::bind_tcp(listen_addr, Default::default())
-                .with_context(|| format!("error listening on {}", listen_addr))?;
-            librqbit::http_api::HttpApi::new(api.clone(), Some(http_api_opts))
-                .make_http_api_and_run(listener, upnp_router)
-                .await
-        };
+/// What has to be done to go from one configuration to another
+struct ConfigDiff {
+    /// Listener, DHT, persistence, blocklist, peer proxy or download folder changed; needs a new session
+    restart_session: bool,
+    /// HTTP API or UPnP server settings changed
+    restart_http_api: bool,
+    /// Banned IPs changed. They are only loaded by a new session, which the user starts.
+    banned_ips_changed: bool,
+}
 
-        session.spawn(debug_span!("http_api"), "http_api", http_api_task);
+impl ConfigDiff {
+    fn new(old: &RqbitDesktopConfig, new: &RqbitDesktopConfig) -> Self {
+        // A ban alone must not cut off running streams
+        let mut old_connections = old.connections.clone();
+        old_connections.banned_ips = new.connections.banned_ips.clone();
+        #[allow(unused_mut)]
+        let mut restart_session = old.default_download_location != new.default_download_location
+            || old.dht != new.dht
+            || old_connections != new.connections
+            || old.persistence != new.persistence
+            || old.blocklist.sources != new.blocklist.sources
+            || old.blocklist.enabled != new.blocklist.enabled
+            || old.proxy.peer_proxy_url() != new.proxy.peer_proxy_url();
+        #[cfg(feature = "disable-upload")]
+        {
+            restart_session |= old.disable_upload != new.disable_upload;
+        }
+        Self {
+            restart_session,
+            restart_http_api: old.http_api != new.http_api || old.upnp != new.upnp,
+            banned_ips_changed: old.connections.banned_ips != new.connections.banned_ips,
+        }
     }
-    Ok(api)
+}
+
+/// Change upload and download limits of a running session
+pub fn apply_rate_limits(api: &Api, limits: &LimitsConfig) {
+    let ratelimits = &api.session().ratelimits;
+    ratelimits.set_upload_bps(limits.upload_bps);
+    ratelimits.set_download_bps(limits.download_bps);
 }
 
 impl State {
@@ -199,53 +264,147 @@ impl State {
                 }),
         );
 
-        if let Ok(config) = read_config(&config_filename) {
-            // Ensure download directory exists and is writable
-            if let Err(e) = std::fs::create_dir_all(&config.default_download_location) {
-                warn!(
-                    "Failed to create download directory {:?}: {}. Using default.",
-                    config.default_download_location, e
-                );
+        let state = Self {
+            config_filename,
+            init_logging,
+            shared: Arc::new(RwLock::new(None)),
+            torrent_db,
+            events: Default::default(),
+            bandwidth: Default::default(),
+            post_processing: Default::default(),
+            peer_counters: Default::default(),
+            blocklist_status: Default::default(),
+            kill_switch: Default::default(),
+            queue_lock: Default::default(),
+            http_proxy: Default::default(),
+            caps_cache: Default::default(),
+            search_cache: Default::default(),
+            watchlist: Default::default(),
+            rss: Default::default(),
+        };
+
+        let Ok(config) = read_config(&state.config_filename) else {
+            return state;
+        };
+
+        // Ensure download directory exists and is writable
+        if let Err(e) = std::fs::create_dir_all(&config.default_download_location) {
+            warn!(
+                "Failed to create download directory {:?}: {}. Using default.",
+                config.default_download_location, e
+            );
+        }
+
+        let api = state
+            .api_from_config(&config)
+            .await
+            .map_err(|e| {
+                warn!(error=?e, "error reading configuration");
+                e
+            })
+            .ok();
+
+        // Sync database with current torrents
+        if let Some(ref api) = api {
+            let torrent_list = api.api_torrent_list();
+            let active_hashes: Vec<String> = torrent_list
+                .torrents
+                .iter()
+                .map(|t| t.info_hash.clone())
+                .collect();
+
+            if let Err(e) = state.torrent_db.sync_with_torrent_list(&active_hashes) {
+                warn!(error=?e, "error syncing torrent database");
             }
+        }
 
-            let api = api_from_config(&init_logging, &config)
+        let http_api = match api.as_ref() {
+            Some(api) => start_http_api(api, &config)
                 .await
                 .map_err(|e| {
-                    warn!(error=?e, "error reading configuration");
+                    warn!(error=?e, "error starting HTTP API");
                     e
                 })
-                .ok();
-
-            // Sync database with current torrents
-            if let Some(ref api) = api {
-                let torrent_list = api.api_torrent_list();
-                let active_hashes: Vec<String> = torrent_list
-                    .torrents
-                    .iter()
-                    .map(|t| t.info_hash.clone())
-                    .collect();
-
-                if let Err(e) = torrent_db.sync_with_torrent_list(&active_hashes) {
-                    warn!(error=?e, "error syncing torrent database");
-                }
-            }
+                .ok()
+                .flatten(),
+            None => None,
+        };
+
+        *state.shared.write() = Some(StateShared {
+            config,
+            api,
+            http_api,
+        });
+        state
+    }
 
-            let shared = Arc::new(RwLock::new(Some(StateShared { config, api })));
+    async fn api_from_config(&self, config: &RqbitDesktopConfig) -> anyhow::Result<Api> {
+        config
+            .validate()
+            .context("error validating configuration")?;
+        let persistence = if config.persistence.disable {
+            None
+        } else {
+            Some(SessionPersistenceConfig::Json {
+                folder: if config.persistence.folder == Path::new("") {
+                    None
+                } else {
+                    Some(config.persistence.folder.clone())
+                },
+            })
+        };
 
-            return Self {
-                config_filename,
-                shared,
-                init_logging,
-                torrent_db,
-            };
+        // Web requests must go through the proxy even if the session fails to start below
+        crate::proxy::set_config(self, &config.proxy);
+        let bind = crate::network::resolve_bind(&config.connections)?;
+        self.kill_switch.set_bound_ip(bind.as_ref().map(|b| b.ip));
+        let (listen, mut connect) = config
+            .connections
+            .as_listener_and_connect_opts(bind.as_ref().map(|b| b.ip));
+        // The structured proxy takes precedence over the legacy SOCKS setting
+        if let Some(url) = config.proxy.peer_proxy_url() {
+            connect.proxy_url = Some(url);
         }
+        let (blocklist_url, blocklist_status) = crate::blocklist::write_session_blocklist(config)
+            .context("couldn't write peer blocklist")?;
+        *self.blocklist_status.lock() = Some(blocklist_status);
 
-        Self {
-            config_filename,
-            init_logging,
-            shared: Arc::new(RwLock::new(None)),
-            torrent_db,
+        // We need to start prometheus recorder earlier than session.
+        if !config.http_api.disable {
+            prometheus_handle();
         }
+
+        let session = Session::new_with_opts(
+            config.default_download_location.clone(),
+            SessionOptions {
+                disable_dht: config.dht.disable,
+                disable_dht_persistence: config.dht.disable_persistence,
+                dht_config: Some(PersistentDhtConfig {
+                    config_filename: Some(config.dht.persistence_filename.clone()),
+                    ..Default::default()
+                }),
+                persistence,
+                connect: Some(connect),
+                listen,
+                fastresume: config.persistence.fastresume,
+                ratelimits: config.ratelimits,
+                blocklist_url,
+                bind_device_name: bind.map(|b| b.interface),
+                #[cfg(feature = "disable-upload")]
+                disable_upload: config.disable_upload,
+                ..Default::default()
+            },
+        )
+        .await
+        .context("couldn't set up librqbit session")?;
+
+        let api = Api::new(
+            session.clone(),
+            Some(self.init_logging.rust_log_reload_tx.clone()),
+            Some(self.init_logging.line_broadcast.clone()),
+        );
+
+        Ok(api)
     }
 
     pub fn api(&self) -> Result<Api, ApiError> {
@@ -256,35 +415,179 @@ impl State {
     }
 
     pub async fn configure(&self, config: RqbitDesktopConfig) -> Result<(), ApiError> {
-        {
+        let diff = {
             let g = self.shared.read();
-            if let Some(shared) = g.as_ref()
-                && shared.api.is_some()
-                && shared.config == config
-            {
-                // The config didn't change, and the API is running, nothing to do.
-                return Ok(());
+            if let Some(old) = g.as_ref().map(|s| &s.config.search) {
+                if old.indexers != config.search.indexers {
+                    // Indexers may have been edited in place
+                    self.caps_cache.lock().clear();
+                }
+                if old.indexers != config.search.indexers
+                    || old.use_nacho_server != config.search.use_nacho_server
+                    || old.resolve_info_hashes != config.search.resolve_info_hashes
+                {
+                    self.search_cache.invalidate();
+                }
+            }
+            match g.as_ref() {
+                Some(shared) if shared.api.is_some() => {
+                    if shared.config == config {
+                        // The config didn't change, and the API is running, nothing to do.
+                        return Ok(());
+                    }
+                    Some(ConfigDiff::new(&shared.config, &config))
+                }
+                _ => None,
+            }
+        };
+
+        match diff {
+            Some(diff) if !diff.restart_session => self.reconfigure_in_place(config, diff).await,
+            _ => self.restart_session(config).await,
+        }
+    }
+
+    /// Apply settings that don't need a new session, keeping torrents and streams running
+    async fn reconfigure_in_place(
+        &self,
+        config: RqbitDesktopConfig,
+        diff: ConfigDiff,
+    ) -> Result<(), ApiError> {
+        config
+            .validate()
+            .context("error validating configuration")?;
+        let api = self.api()?;
+
+        let http_api = if diff.restart_http_api {
+            // The old listener may hold the same address, so it has to stop first
+            let (existing, old_config) = self
+                .shared
+                .write()
+                .as_mut()
+                .map(|s| (s.http_api.take(), s.config.clone()))
+                .unzip();
+            stop_http_api(existing.flatten()).await;
+            match start_http_api(&api, &config).await {
+                Ok(http_api) => Some(http_api),
+                Err(e) => {
+                    // Keep streaming available with the previous settings
+                    if let Some(old_config) = old_config {
+                        match start_http_api(&api, &old_config).await {
+                            Ok(http_api) => {
+                                if let Some(shared) = self.shared.write().as_mut() {
+                                    shared.http_api = http_api;
+                                }
+                            }
+                            Err(e) => warn!(error=?e, "error restarting previous HTTP API"),
+                        }
+                    }
+                    return Err(e.into());
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Err(e) = write_config(&self.config_filename, &config) {
+            tracing::error!("error writing config: {:#}", e);
+        }
+        // Web requests pick up the proxy on their next client
+        crate::proxy::set_config(self, &config.proxy);
+
+        if let Some(shared) = self.shared.write().as_mut() {
+            shared.config = config;
+            if let Some(http_api) = http_api {
+                shared.http_api = http_api;
             }
         }
 
-        let existing = self.shared.write().as_mut().and_then(|s| s.api.take());
+        // Rate limits (including scheduled ones) are applied to the live session
+        self.bandwidth.apply(self);
+        if diff.banned_ips_changed
+            && let Some(status) = self.blocklist_status.lock().as_mut()
+        {
+            status.pending = true;
+        }
+        info!("applied configuration without restarting the session");
+        Ok(())
+    }
 
+    /// Restart the session with the current config, e.g. to reload the blocklist
+    pub async fn reload_session(&self) -> Result<(), ApiError> {
+        let config = self
+            .shared
+            .read()
+            .as_ref()
+            .map(|s| s.config.clone())
+            .with_status_error(StatusCode::BAD_REQUEST, "Torrent session not configured")?;
+        self.restart_session(config).await
+    }
+
+    async fn restart_session(&self, config: RqbitDesktopConfig) -> Result<(), ApiError> {
+        let (existing, http_api, old_config) = self
+            .shared
+            .write()
+            .as_mut()
+            .map(|s| (s.api.take(), s.http_api.take(), Some(s.config.clone())))
+            .unwrap_or_default();
+
+        stop_http_api(http_api).await;
+        let was_running = existing.is_some();
         if let Some(api) = existing {
             api.session().stop().await;
         }
 
-        let api = api_from_config(&self.init_logging, &config).await?;
+        let (api, http_api) = match self.start_session(&config).await {
+            Ok(started) => started,
+            Err(e) => {
+                // Bring the previous session back so downloads and streams keep going
+                if let Some(old_config) = old_config.filter(|_| was_running) {
+                    match self.start_session(&old_config).await {
+                        Ok((api, http_api)) => {
+                            *self.shared.write() = Some(StateShared {
+                                config: old_config,
+                                api: Some(api),
+                                http_api,
+                            });
+                            self.bandwidth.reset();
+                            self.bandwidth.apply(self);
+                        }
+                        Err(e) => warn!(error=?e, "error restarting previous session"),
+                    }
+                }
+                return Err(e);
+            }
+        };
         if let Err(e) = write_config(&self.config_filename, &config) {
             tracing::error!("error writing config: {:#}", e);
         }
 
-        let mut g = self.shared.write();
-        *g = Some(StateShared {
+        *self.shared.write() = Some(StateShared {
             config,
             api: Some(api),
+            http_api,
         });
+        // The new session starts with the base limits, not the scheduled ones
+        self.bandwidth.reset();
+        self.bandwidth.apply(self);
         Ok(())
     }
+
+    /// Start a session and its HTTP API. The session is stopped again if the HTTP API
+    /// fails, so it doesn't keep its ports.
+    async fn start_session(
+        &self,
+        config: &RqbitDesktopConfig,
+    ) -> Result<(Api, Option<JoinHandle<()>>), ApiError> {
+        let api = self.api_from_config(config).await?;
+        match start_http_api(&api, config).await {
+            Ok(http_api) => Ok((api, http_api)),
+            Err(e) => {
+                api.session().stop().await;
+                Err(e.into())
+            }
+        }
+    }
 }
 
 #[derive(Default, Serialize)]
@@ -316,15 +619,32 @@ pub fn torrents_list(state: &State) -> Result<TorrentListResponse, ApiError> {
     Ok(state.api()?.api_torrent_list())
 }
 
+/// Add a torrent to the session. While the kill switch is tripped it is added paused
+/// and resumed along with the others once the interface is back.
+pub(crate) async fn add_torrent(
+    state: &State,
+    add: AddTorrent<'_>,
+    mut opts: Option<AddTorrentOptions>,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    let api = state.api()?;
+    let held = state.kill_switch.hold(&mut opts);
+    let response = api.api_add_torrent(add, opts).await?;
+    if held
+        && !state.kill_switch.held(response.details.info_hash.clone())
+        && let Some(id) = response.id
+        && let Err(e) = api.api_torrent_action_start(TorrentIdOrHash::Id(id)).await
+    {
+        warn!(error=?e, "Failed to start torrent added while the kill switch was on");
+    }
+    Ok(response)
+}
+
 pub async fn torrent_create_from_url(
     state: &State,
     url: String,
     opts: Option<AddTorrentOptions>,
 ) -> Result<ApiAddTorrentResponse, ApiError> {
-    let response = state
-        .api()?
-        .api_add_torrent(AddTorrent::Url(url.into()), opts)
-        .await?;
+    let response = add_torrent(state, AddTorrent::Url(url.into()), opts).await?;
 
     // Track in database (no IMDB code for manual URL adds)
     if let Some(id) = response.id {
@@ -352,10 +672,7 @@ pub async fn torrent_create_from_base64_file(
         .decode(&contents)
         .with_status_error(StatusCode::BAD_REQUEST, "invalid base64")?;
 
-    let response = state
-        .api()?
-        .api_add_torrent(AddTorrent::TorrentFileBytes(bytes.into()), opts)
-        .await?;
+    let response = add_torrent(state, AddTorrent::TorrentFileBytes(bytes.into()), opts).await?;
 
     // Track in database (no IMDB code for manual file adds)
     if let Some(id) = response.id {
@@ -389,7 +706,23 @@ pub async fn torrent_action_delete(
     id: TorrentIdOrHash,
 ) -> Result<EmptyJsonResponse, ApiError> {
     // Get torrent details first to obtain info_hash for database removal
-    if let Ok(details) = state.api()?.api_torrent_details(id) {
+    let details = state.api()?.api_torrent_details(id).ok();
+    // Generated files (transmux outputs, subtitles) are not part of the torrent
+    let derived = details.as_ref().map(|details| {
+        let entry = state.torrent_db.get_by_hash(&details.info_hash);
+        crate::storage::derived_files(details, entry.as_ref())
+    });
+
+    let response = state.api()?.api_torrent_action_delete(id).await?;
+
+    for path in derived.into_iter().flatten() {
+        if let Err(e) = std::fs::remove_file(&path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            warn!(error=?e, "Failed to remove generated file {:?}", path);
+        }
+    }
+    if let Some(details) = details {
         if let Some(torrent_id) = details.id {
             if let Err(e) = state.torrent_db.remove_by_id(torrent_id as i32) {
                 warn!(error=?e, "Failed to remove torrent from database by ID");
@@ -401,14 +734,22 @@ pub async fn torrent_action_delete(
         }
     }
 
-    state.api()?.api_torrent_action_delete(id).await
+    Ok(response)
 }
 
 pub async fn torrent_action_pause(
     state: &State,
     id: TorrentIdOrHash,
 ) -> Result<EmptyJsonResponse, ApiError> {
-    state.api()?.api_torrent_action_pause(id).await
+    let api = state.api()?;
+    let response = api.api_torrent_action_pause(id).await?;
+    // Paused on purpose, so the download queue must not resume it
+    if let Ok(details) = api.api_torrent_details(id)
+        && let Err(e) = state.torrent_db.set_queue_paused(&details.info_hash, false)
+    {
+        warn!(error=?e, "Failed to clear queue state in database");
+    }
+    Ok(response)
 }
 
 pub async fn torrent_action_forget(
@@ -422,6 +763,7 @@ pub async fn torrent_action_start(
     state: &State,
     id: TorrentIdOrHash,
 ) -> Result<EmptyJsonResponse, ApiError> {
+    state.kill_switch.check_released()?;
     state.api()?.api_torrent_action_start(id).await
 }
 
@@ -460,6 +802,366 @@ pub fn get_download_path(state: &State) -> Result<String, ApiError> {
         .with_status_error(StatusCode::FAILED_DEPENDENCY, "Configuration not available")
 }
 
+/// Find the session ID of a torrent by its info hash
+pub fn find_torrent_id(state: &State, info_hash: &str) -> Result<usize, ApiError> {
+    state
+        .api()?
+        .api_torrent_list()
+        .torrents
+        .into_iter()
+        .find(|t| t.info_hash == info_hash)
+        .and_then(|t| t.id)
+        .with_status_error(StatusCode::NOT_FOUND, "Torrent not found")
+}
+
+/// Name of the Tauri event carrying [`MoveStorageProgress`] payloads to the frontend
+pub const MOVE_STORAGE_EVENT: &str = "move-storage-progress";
+
+/// Report move progress roughly every this many bytes
+const MOVE_PROGRESS_STEP: u64 = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveStorageProgress {
+    pub info_hash: String,
+    pub moved_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Progress callback for [`relocate_torrent`] forwarding to the frontend
+pub fn emit_move_progress(app: &AppHandle) -> impl Fn(MoveStorageProgress) + Send + 'static {
+    let app = app.clone();
+    move |progress| {
+        if let Err(e) = app.emit(MOVE_STORAGE_EVENT, &progress) {
+            warn!(error=?e, "Failed to emit move progress");
+        }
+    }
+}
+
+/// Move a file, falling back to copy + delete when source and destination
+/// are on different filesystems. `on_bytes` gets the bytes of this file moved so far.
+fn move_file(src: &Path, dst: &Path, on_bytes: &mut dyn FnMut(u64)) -> anyhow::Result<()> {
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("error creating {}", parent.display()))?;
+    }
+    let len = std::fs::metadata(src)
+        .with_context(|| format!("error reading {}", src.display()))?
+        .len();
+    if std::fs::rename(src, dst).is_ok() {
+        on_bytes(len);
+        return Ok(());
+    }
+    if let Err(e) = copy_file(src, dst, on_bytes) {
+        let _ = std::fs::remove_file(dst);
+        return Err(e.context(format!(
+            "error copying {} to {}",
+            src.display(),
+            dst.display()
+        )));
+    }
+    std::fs::remove_file(src).with_context(|| format!("error removing {}", src.display()))?;
+    Ok(())
+}
+
+fn copy_file(src: &Path, dst: &Path, on_bytes: &mut dyn FnMut(u64)) -> anyhow::Result<()> {
+    let mut reader = File::open(src)?;
+    let mut writer = File::create(dst)?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut copied = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+        on_bytes(copied);
+    }
+    writer.sync_all()?;
+    writer.set_permissions(reader.metadata()?.permissions())?;
+    Ok(())
+}
+
+/// Move files given relative to `from` into `to`, keeping their relative layout.
+/// Missing files (e.g. deselected ones) are skipped. On failure, already moved
+/// files are moved back. `on_progress` gets the total bytes moved so far.
+fn move_torrent_files(
+    from: &Path,
+    to: &Path,
+    files: &[PathBuf],
+    on_progress: &mut dyn FnMut(u64),
+) -> anyhow::Result<()> {
+    let mut moved: Vec<&PathBuf> = Vec::new();
+    let mut done = 0u64;
+    for relative in files {
+        let src = from.join(relative);
+        if !src.exists() {
+            continue;
+        }
+        let mut file_bytes = 0;
+        let result = move_file(&src, &to.join(relative), &mut |n| {
+            file_bytes = n;
+            on_progress(done + n);
+        });
+        if let Err(e) = result {
+            for relative in moved {
+                let _ = move_file(&to.join(relative), &from.join(relative), &mut |_| {});
+            }
+            return Err(e);
+        }
+        done += file_bytes;
+        moved.push(relative);
+    }
+
+    // Clean up directories left empty by the move
+    for relative in files {
+        let mut dir = from.join(relative);
+        while dir.pop() && dir.starts_with(from) {
+            if std::fs::remove_dir(&dir).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Move a torrent's files to `new_folder` and re-add it there, so it keeps seeding
+/// without re-downloading. Works across filesystems by copying. Transmux outputs and
+/// other generated files under the old folder are moved along, file selection and
+/// paused state are kept, and the database entry is updated to the new location.
+pub async fn relocate_torrent(
+    state: &State,
+    id: TorrentIdOrHash,
+    new_folder: PathBuf,
+    on_progress: impl Fn(MoveStorageProgress) + Send + 'static,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    if !new_folder.is_absolute() {
+        return Err(ApiError::new_from_text(
+            StatusCode::BAD_REQUEST,
+            "Destination must be an absolute path",
+        ));
+    }
+    let api = state.api()?;
+    let details = api.api_torrent_details(id)?;
+    let torrent_id = details.id.with_status_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Torrent ID not available",
+    )?;
+    let info_hash = details.info_hash.clone();
+    let old_folder = PathBuf::from(&details.output_folder);
+    if old_folder == new_folder {
+        return Err(ApiError::new_from_text(
+            StatusCode::BAD_REQUEST,
+            "Torrent is already in that folder",
+        ));
+    }
+    let paused = api.api_stats_v1(id)?.live.is_none();
+    let only_files: Vec<usize> = details
+        .files
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, f)| f.included)
+        .map(|(i, _)| i)
+        .collect();
+    let torrent_bytes = api.api_export_torrent(TorrentIdOrHash::Id(torrent_id))?;
+
+    // Make sure there is an entry to carry metadata over to the re-added torrent
+    if let Err(e) =
+        state
+            .torrent_db
+            .upsert_torrent(torrent_id as i32, info_hash.clone(), None, None, None)
+    {
+        warn!(error=?e, "Failed to update torrent database");
+    }
+    let derived_files = state
+        .torrent_db
+        .get_by_hash(&info_hash)
+        .map(|e| e.derived_files)
+        .unwrap_or_default();
+
+    // Torrent files, then transmux outputs next to them and other generated files
+    let mut files: Vec<PathBuf> = details
+        .files
+        .iter()
+        .flatten()
+        .map(|f| f.components.iter().collect())
+        .collect();
+    let transmux_outputs: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|f| transmux::generate_output_path(&old_folder.join(f)).ok())
+        .filter(|output| output.exists())
+        .collect();
+    for path in transmux_outputs.iter().chain(&derived_files) {
+        if let Ok(relative) = path.strip_prefix(&old_folder)
+            && !files.iter().any(|f| f == relative)
+        {
+            files.push(relative.to_owned());
+        }
+    }
+
+    let total_bytes: u64 = files
+        .iter()
+        .filter_map(|f| std::fs::metadata(old_folder.join(f)).ok())
+        .map(|m| m.len())
+        .sum();
+    if !disk::same_filesystem(&old_folder, &new_folder)
+        && disk::available_space(&new_folder).is_ok_and(|free| free < total_bytes)
+    {
+        return Err(ApiError::new_from_text(
+            StatusCode::INSUFFICIENT_STORAGE,
+            "Not enough free space at the destination",
+        ));
+    }
+
+    if !paused
+        && let Err(e) = api
+            .api_torrent_action_pause(TorrentIdOrHash::Id(torrent_id))
+            .await
+    {
+        warn!(error=?e, "Failed to pause torrent before moving");
+    }
+    api.api_torrent_action_forget(TorrentIdOrHash::Id(torrent_id))
+        .await?;
+
+    info!(
+        "Moving torrent {} from {:?} to {:?}",
+        info_hash, old_folder, new_folder
+    );
+    let moved = {
+        let (from, to) = (old_folder.clone(), new_folder.clone());
+        let info_hash = info_hash.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut last_report = 0;
+            let mut report = |moved_bytes: u64| {
+                if moved_bytes == total_bytes || moved_bytes - last_report >= MOVE_PROGRESS_STEP {
+                    last_report = moved_bytes;
+                    on_progress(MoveStorageProgress {
+                        info_hash: info_hash.clone(),
+                        moved_bytes,
+                        total_bytes,
+                    });
+                }
+            };
+            report(0);
+            move_torrent_files(&from, &to, &files, &mut report)
+        })
+        .await
+        .context("move task panicked")
+        .and_then(|r| r)
+    };
+
+    // If the move failed the files are back in place, so re-add from the old folder
+    let output_folder = if moved.is_ok() {
+        &new_folder
+    } else {
+        &old_folder
+    };
+    let response = api
+        .api_add_torrent(
+            AddTorrent::TorrentFileBytes(torrent_bytes),
+            Some(AddTorrentOptions {
+                output_folder: Some(output_folder.to_string_lossy().into_owned()),
+                only_files: Some(only_files),
+                paused,
+                overwrite: true,
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    if let Some(id) = response.id
+        && let Err(e) =
+            state
+                .torrent_db
+                .upsert_torrent(id as i32, info_hash.clone(), None, None, None)
+    {
+        warn!(error=?e, "Failed to update torrent database");
+    }
+
+    if let Err(e) = moved {
+        warn!(error=?e, "Failed to move torrent files");
+        return Err(ApiError::new_from_anyhow(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.context("error moving torrent files"),
+        ));
+    }
+
+    let derived_files = derived_files
+        .into_iter()
+        .map(|path| match path.strip_prefix(&old_folder) {
+            Ok(relative) => new_folder.join(relative),
+            Err(_) => path,
+        })
+        .collect();
+
+    if let Err(e) = state
+        .torrent_db
+        .set_output_folder(&info_hash, new_folder)
+        .and_then(|_| {
+            state
+                .torrent_db
+                .set_derived_files(&info_hash, derived_files)
+        })
+    {
+        warn!(error=?e, "Failed to record new torrent location");
+    }
+
+    Ok(response)
+}
+
+/// Swap a torrent in the session for new metainfo with the same info hash (e.g. edited
+/// trackers), keeping its folder, file selection and paused state. The session re-checks
+/// existing files when the torrent is added back.
+pub async fn replace_torrent(
+    state: &State,
+    id: TorrentIdOrHash,
+    torrent_bytes: bytes::Bytes,
+) -> Result<ApiAddTorrentResponse, ApiError> {
+    let api = state.api()?;
+    let details = api.api_torrent_details(id)?;
+    let paused = api.api_stats_v1(id)?.live.is_none();
+    let only_files: Vec<usize> = details
+        .files
+        .iter()
+        .flatten()
+        .enumerate()
+        .filter(|(_, f)| f.included)
+        .map(|(i, _)| i)
+        .collect();
+
+    if !paused && let Err(e) = api.api_torrent_action_pause(id).await {
+        warn!(error=?e, "Failed to pause torrent before replacing it");
+    }
+    api.api_torrent_action_forget(id).await?;
+
+    let response = api
+        .api_add_torrent(
+            AddTorrent::TorrentFileBytes(torrent_bytes),
+            Some(AddTorrentOptions {
+                output_folder: Some(details.output_folder.clone()),
+                only_files: Some(only_files),
+                paused,
+                overwrite: true,
+                ..Default::default()
+            }),
+        )
+        .await?;
+
+    if let Some(new_id) = response.id
+        && let Err(e) = state.torrent_db.upsert_torrent(
+            new_id as i32,
+            details.info_hash.clone(),
+            None,
+            None,
+            None,
+        )
+    {
+        warn!(error=?e, "Failed to update torrent database");
+    }
+    Ok(response)
+}
+
 // Torrent database functions
 
 /// Add or update a torrent with TMDB ID
@@ -471,10 +1173,7 @@ pub async fn torrent_create_with_tmdb(
     episode_info: Option<(i32, i32)>,
     opts: Option<AddTorrentOptions>,
 ) -> Result<ApiAddTorrentResponse, ApiError> {
-    let response = state
-        .api()?
-        .api_add_torrent(AddTorrent::Url(url.into()), opts)
-        .await?;
+    let response = add_torrent(state, AddTorrent::Url(url.into()), opts).await?;
 
     // Track in database with TMDB ID
     if let Some(id) = response.id {
@@ -502,10 +1201,7 @@ pub async fn torrent_create_with_imdb(
     episode_info: Option<(i32, i32)>,
     opts: Option<AddTorrentOptions>,
 ) -> Result<ApiAddTorrentResponse, ApiError> {
-    let response = state
-        .api()?
-        .api_add_torrent(AddTorrent::Url(url.into()), opts)
-        .await?;
+    let response = add_torrent(state, AddTorrent::Url(url.into()), opts).await?;
 
     // Track in database (deprecated)
     if let Some(id) = response.id {
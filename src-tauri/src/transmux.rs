@@ -1,11 +1,11 @@
 use anyhow::{Context, Result, anyhow};
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
 
 /// Transmux a video file (typically MKV) to MP4 format suitable for HTTP streaming
 /// The output file will be saved in the same directory with a .mp4 extension
 #[tauri::command]
-pub async fn transmux_to_mp4(app: tauri::AppHandle, input_path: String) -> Result<String, String> {
+pub async fn transmux_to_mp4(_app: tauri::AppHandle, input_path: String) -> Result<String, String> {
     info!("Starting transmux for file: {}", input_path);
 
     // Validate input path
@@ -28,7 +28,7 @@ pub async fn transmux_to_mp4(app: tauri::AppHandle, input_path: String) -> Resul
     let input_path_clone = input_path.clone();
     let output_path_clone = output_path.clone();
 
-    tokio::task::spawn_blocking(move || transmux_file(&input_path_clone, &output_path_clone, app))
+    tokio::task::spawn_blocking(move || transmux_file(&input_path_clone, &output_path_clone))
         .await
         .map_err(|e| format!("Task join error: {}", e))?
         .map_err(|e| format!("Transmux failed: {}", e))?;
@@ -38,7 +38,7 @@ pub async fn transmux_to_mp4(app: tauri::AppHandle, input_path: String) -> Resul
 }
 
 /// Generate output path by replacing the extension with .mp4
-fn generate_output_path(input: &Path) -> Result<PathBuf> {
+pub(crate) fn generate_output_path(input: &Path) -> Result<PathBuf> {
     let parent = input
         .parent()
         .ok_or_else(|| anyhow!("Cannot get parent directory"))?;
@@ -54,7 +54,7 @@ fn generate_output_path(input: &Path) -> Result<PathBuf> {
 }
 
 /// Perform the actual transmux operation using ffmpeg CLI
-fn transmux_file(input_path: &str, output_path: &Path, _app: tauri::AppHandle) -> Result<()> {
+pub(crate) fn transmux_file(input_path: &str, output_path: &Path) -> Result<()> {
     info!(
         "Starting ffmpeg conversion: {} -> {}",
         input_path,
@@ -94,6 +94,112 @@ fn transmux_file(input_path: &str, output_path: &Path, _app: tauri::AppHandle) -
     Ok(())
 }
 
+/// A subtitle stream found in a media file by ffprobe
+struct SubtitleStream {
+    index: u32,
+    codec: String,
+    language: Option<String>,
+}
+
+/// List subtitle streams of a media file using ffprobe CLI
+fn probe_subtitle_streams(input: &Path) -> Result<Vec<SubtitleStream>> {
+    // -select_streams s: only subtitle streams
+    // -of csv=p=0: one "index,codec_name[,language]" line per stream
+    let output = std::process::Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("s")
+        .arg("-show_entries")
+        .arg("stream=index,codec_name:stream_tags=language")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(input)
+        .output()
+        .context("Failed to execute ffprobe command")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffprobe command failed with status: {}",
+            output.status
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().split(',');
+            let index = parts.next()?.parse().ok()?;
+            let codec = parts.next()?.to_string();
+            let language = parts.next().filter(|l| !l.is_empty()).map(str::to_string);
+            Some(SubtitleStream {
+                index,
+                codec,
+                language,
+            })
+        })
+        .collect())
+}
+
+/// Extract text subtitle streams to WebVTT files next to the input.
+/// Bitmap formats (PGS, VobSub) cannot be converted and are skipped.
+pub(crate) fn extract_subtitles(input: &Path) -> Result<Vec<PathBuf>> {
+    const BITMAP_CODECS: &[&str] = &["hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle"];
+
+    let parent = input
+        .parent()
+        .ok_or_else(|| anyhow!("Cannot get parent directory"))?;
+    let stem = input
+        .file_stem()
+        .ok_or_else(|| anyhow!("Cannot get file stem"))?
+        .to_string_lossy()
+        .to_string();
+
+    let mut outputs = Vec::new();
+    for (n, stream) in probe_subtitle_streams(input)?.into_iter().enumerate() {
+        if BITMAP_CODECS.contains(&stream.codec.as_str()) {
+            info!(
+                "Skipping bitmap subtitle stream {} ({})",
+                stream.index, stream.codec
+            );
+            continue;
+        }
+
+        let language = stream.language.as_deref().unwrap_or("und");
+        let output_path = parent.join(format!("{}.{}.{}.vtt", stem, language, n));
+        if output_path.exists() {
+            outputs.push(output_path);
+            continue;
+        }
+
+        let status = std::process::Command::new("ffmpeg")
+            .arg("-i")
+            .arg(input)
+            .arg("-map")
+            .arg(format!("0:{}", stream.index))
+            .arg("-c:s")
+            .arg("webvtt")
+            .arg("-y")
+            .arg(&output_path)
+            .status()
+            .context("Failed to execute ffmpeg command")?;
+
+        if status.success() {
+            info!("Extracted subtitles to {}", output_path.display());
+            outputs.push(output_path);
+        } else {
+            warn!(
+                "Failed to extract subtitle stream {} from {}: {}",
+                stream.index,
+                input.display(),
+                status
+            );
+        }
+    }
+
+    Ok(outputs)
+}
+
 /// Check if a file needs transmuxing (is not already MP4)
 #[tauri::command]
 pub fn needs_transmux(file_path: String) -> bool {
@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::config::RqbitDesktopConfigWatchFolders;
+use crate::torrent_server::{self, State};
+
+/// Name of the Tauri event carrying [`WatchFolderEvent`] payloads to the frontend
+pub const WATCH_FOLDER_EVENT: &str = "watch-folder-event";
+
+/// Files modified more recently than this may still be being written
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+/// Optional `<file>.json` next to a dropped file, e.g. `Movie.torrent.json`,
+/// tagging the torrent with a TMDB title
+#[derive(Debug, Clone, Deserialize)]
+struct Sidecar {
+    tmdb_id: u64,
+    /// "movie" or "tv"
+    media_type: String,
+    season: Option<i32>,
+    episode: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchFolderEvent {
+    Added {
+        file: PathBuf,
+        info_hash: String,
+        tmdb_id: Option<u64>,
+    },
+    Failed {
+        file: PathBuf,
+        error: String,
+    },
+}
+
+/// Spawn the task polling the configured watch folders
+pub fn spawn_watch_folders(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut stuck = HashMap::new();
+        loop {
+            let config = app
+                .state::<State>()
+                .shared
+                .read()
+                .as_ref()
+                .map(|s| s.config.watch_folders.clone());
+            let interval = match config {
+                Some(config) if config.enabled => {
+                    scan_folders(&app, &config, &mut stuck).await;
+                    config.poll_interval
+                }
+                Some(config) => config.poll_interval,
+                None => RqbitDesktopConfigWatchFolders::default().poll_interval,
+            };
+            tokio::time::sleep(interval.max(Duration::from_secs(1))).await;
+        }
+    });
+}
+
+/// Scan the watch folders once. `stuck` holds files that were handled but couldn't be
+/// moved out of the folder, with their modification time, so they aren't added again.
+async fn scan_folders(
+    app: &AppHandle,
+    config: &RqbitDesktopConfigWatchFolders,
+    stuck: &mut HashMap<PathBuf, SystemTime>,
+) {
+    stuck.retain(|path, _| path.exists());
+    for folder in &config.folders {
+        let entries = match std::fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read watch folder {:?}: {}", folder, e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !is_watched_file(&path) || !is_settled(&entry) {
+                continue;
+            }
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            if modified.is_some() && stuck.get(&path) == modified.as_ref() {
+                continue;
+            }
+
+            let (target, event) = match add_file(app, &path).await {
+                Ok((info_hash, tmdb_id)) => {
+                    info!("Added {:?} from watch folder", path);
+                    (
+                        config
+                            .processed_folder
+                            .clone()
+                            .unwrap_or_else(|| folder.join("processed")),
+                        WatchFolderEvent::Added {
+                            file: path.clone(),
+                            info_hash,
+                            tmdb_id,
+                        },
+                    )
+                }
+                Err(e) => {
+                    warn!("Failed to add {:?} from watch folder: {:#}", path, e);
+                    (
+                        config
+                            .failed_folder
+                            .clone()
+                            .unwrap_or_else(|| folder.join("failed")),
+                        WatchFolderEvent::Failed {
+                            file: path.clone(),
+                            error: format!("{:#}", e),
+                        },
+                    )
+                }
+            };
+
+            for file in [sidecar_path(&path), path.clone()] {
+                if file.exists()
+                    && let Err(e) = move_into(&file, &target)
+                {
+                    warn!("Failed to move {:?} to {:?}: {:#}", file, target, e);
+                }
+            }
+            if let Some(modified) = modified
+                && path.exists()
+            {
+                stuck.insert(path.clone(), modified);
+            }
+            if let Err(e) = app.emit(WATCH_FOLDER_EVENT, &event) {
+                warn!(error=?e, "Failed to emit watch folder event");
+            }
+        }
+    }
+}
+
+fn is_watched_file(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("torrent") || e.eq_ignore_ascii_case("magnet"))
+}
+
+fn is_settled(entry: &std::fs::DirEntry) -> bool {
+    entry
+        .metadata()
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= SETTLE_TIME)
+}
+
+fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+    path.with_file_name(name)
+}
+
+fn read_sidecar(path: &Path) -> anyhow::Result<Option<Sidecar>> {
+    let sidecar = sidecar_path(path);
+    if !sidecar.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&sidecar)
+        .with_context(|| format!("Failed to read sidecar {:?}", sidecar))?;
+    parse_sidecar(&contents)
+        .map(Some)
+        .with_context(|| format!("Invalid sidecar {:?}", sidecar))
+}
+
+fn parse_sidecar(contents: &str) -> anyhow::Result<Sidecar> {
+    let sidecar: Sidecar = serde_json::from_str(contents)?;
+    if !matches!(sidecar.media_type.as_str(), "movie" | "tv") {
+        anyhow::bail!("media_type must be \"movie\" or \"tv\"");
+    }
+    Ok(sidecar)
+}
+
+/// Add a dropped file to the session; returns the info hash and TMDB id it was tagged with
+async fn add_file(app: &AppHandle, path: &Path) -> anyhow::Result<(String, Option<u64>)> {
+    let sidecar = read_sidecar(path)?;
+    let state = app.state::<State>();
+
+    let is_magnet = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("magnet"));
+    let response = if is_magnet {
+        let contents = std::fs::read_to_string(path).context("Failed to read magnet file")?;
+        let url = contents
+            .lines()
+            .map(str::trim)
+            .find(|l| l.starts_with("magnet:"))
+            .context("No magnet link in file")?
+            .to_owned();
+        torrent_server::torrent_create_from_url(&state, url, None).await
+    } else {
+        use base64::{Engine as _, engine::general_purpose};
+        let bytes = std::fs::read(path).context("Failed to read torrent file")?;
+        torrent_server::torrent_create_from_base64_file(
+            &state,
+            general_purpose::STANDARD.encode(bytes),
+            None,
+        )
+        .await
+    }
+    .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+
+    let info_hash = response.details.info_hash;
+    let mut tmdb_id = None;
+    // The torrent is in the session now, so a failed tag doesn't fail the file
+    if let (Some(sidecar), Some(id)) = (sidecar, response.id) {
+        let episode_info = sidecar.season.zip(sidecar.episode);
+        match state.torrent_db.upsert_torrent(
+            id as i32,
+            info_hash.clone(),
+            Some(sidecar.tmdb_id),
+            Some(sidecar.media_type),
+            episode_info,
+        ) {
+            Ok(()) => tmdb_id = Some(sidecar.tmdb_id),
+            Err(e) => warn!("Failed to tag {} with TMDB id: {:#}", info_hash, e),
+        }
+    }
+    Ok((info_hash, tmdb_id))
+}
+
+/// Move a file into a folder, picking a free name if one with the same name exists
+fn move_into(file: &Path, folder: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(folder).with_context(|| format!("Failed to create {:?}", folder))?;
+    let name = file.file_name().context("File has no name")?;
+    let mut target = folder.join(name);
+    let mut n = 1;
+    while target.exists() {
+        let mut numbered = name.to_os_string();
+        numbered.push(format!(".{}", n));
+        target = folder.join(numbered);
+        n += 1;
+    }
+    std::fs::rename(file, &target)
+        .or_else(|_| std::fs::copy(file, &target).and_then(|_| std::fs::remove_file(file)))
+        .with_context(|| format!("Failed to move {:?} to {:?}", file, target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_path() {
+        assert_eq!(
+            sidecar_path(Path::new("/watch/Movie.torrent")),
+            Path::new("/watch/Movie.torrent.json")
+        );
+    }
+
+    #[test]
+    fn test_parse_sidecar() {
+        let episode =
+            parse_sidecar(r#"{"tmdb_id": 1399, "media_type": "tv", "season": 1, "episode": 2}"#)
+                .unwrap();
+        assert_eq!(episode.tmdb_id, 1399);
+        assert_eq!(episode.season.zip(episode.episode), Some((1, 2)));
+
+        let movie = parse_sidecar(r#"{"tmdb_id": 603, "media_type": "movie"}"#).unwrap();
+        assert_eq!(movie.media_type, "movie");
+        assert_eq!(movie.season, None);
+
+        assert!(parse_sidecar(r#"{"tmdb_id": 603}"#).is_err());
+        assert!(parse_sidecar(r#"{"tmdb_id": 603, "media_type": "book"}"#).is_err());
+        assert!(parse_sidecar("not json").is_err());
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Deserializer, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::torrent_server::State;
 
 // Custom deserializer for tmdbID that handles both string and number
 fn deserialize_tmdb_id<'de, D>(deserializer: D) -> Result<u64, D::Error>
@@ -20,36 +23,40 @@ where
 }
 
 // Helper function to get Nacho Server base URL
-fn get_nacho_server_base_url(app: &AppHandle) -> Result<String, String> {
+fn get_nacho_server_base_url(app: &AppHandle) -> Result<String, CommandError> {
     let nacho_server_url = crate::settings_manager::get_nacho_server_url(app.clone())
-        .map_err(|e| format!("Failed to get Nacho Server URL: {}", e))?;
+        .map_err(|e| e.context("Failed to get Nacho Server URL"))?;
 
     match nacho_server_url {
         Some(url) if !url.is_empty() => Ok(url.trim_end_matches('/').to_string()),
-        _ => Err("Nacho Server URL not configured. Please set it in Settings.".to_string()),
+        _ => Err(CommandError::NotConfigured(
+            "Nacho Server URL not configured. Please set it in Settings.".to_string(),
+        )),
     }
 }
 
 // Helper function to get auth token for Nacho Server
-fn get_nacho_auth_token(app: &AppHandle) -> Result<String, String> {
+fn get_nacho_auth_token(app: &AppHandle) -> Result<String, CommandError> {
     let auth_token = crate::settings_manager::get_nacho_auth_token(app.clone())
-        .map_err(|e| format!("Failed to get Nacho Auth Token: {}", e))?;
+        .map_err(|e| e.context("Failed to get Nacho Auth Token"))?;
 
     match auth_token {
         Some(token) if !token.is_empty() => Ok(token),
-        _ => Err("Nacho Auth Token not configured. Please set it in Settings.".to_string()),
+        _ => Err(CommandError::NotConfigured(
+            "Nacho Auth Token not configured. Please set it in Settings.".to_string(),
+        )),
     }
 }
 
 // Helper function to create HTTP client with proper timeouts
-fn create_http_client() -> Result<reqwest::Client, String> {
-    reqwest::Client::builder()
+fn create_http_client(app: &AppHandle) -> Result<reqwest::Client, CommandError> {
+    crate::proxy::client_builder(&app.state::<State>())
         .timeout(std::time::Duration::from_secs(30))
         .connect_timeout(std::time::Duration::from_secs(10))
         .pool_idle_timeout(std::time::Duration::from_secs(90))
         .pool_max_idle_per_host(10)
         .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+        .map_err(|e| CommandError::request("Failed to build HTTP client", e))
 }
 
 // Watch history structures
@@ -165,14 +172,14 @@ pub async fn get_watch_history(
     app: AppHandle,
     limit: Option<u32>,
     since: Option<String>,
-) -> Result<WatchHistoryResponse, String> {
+) -> Result<WatchHistoryResponse, CommandError> {
     println!("[WatchHistory] ========================================");
     println!("[WatchHistory] Fetching watch history...");
 
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = create_http_client()?;
+    let client = create_http_client(&app)?;
 
     // Build query parameters
     let mut url = format!("{}/api/history", base_url);
@@ -200,7 +207,7 @@ pub async fn get_watch_history(
         .header("X-Nacho-Auth", &auth_token)
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch watch history: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to fetch watch history", e))?;
 
     let status = response.status();
     println!("[WatchHistory] Response Status: {}", status);
@@ -209,21 +216,22 @@ pub async fn get_watch_history(
         let error_text = response.text().await.unwrap_or_default();
         println!("[WatchHistory] Response Body (Error): {}", error_text);
         println!("[WatchHistory] ========================================");
-        return Err(format!(
-            "Failed to fetch watch history: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch watch history: {} - {}", status, error_text),
         ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[WatchHistory] Response Body: {}", response_text);
 
-    let history: WatchHistoryResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse watch history response: {}", e))?;
+    let history: WatchHistoryResponse = serde_json::from_str(&response_text).map_err(|e| {
+        CommandError::InvalidResponse(format!("Failed to parse watch history response: {}", e))
+    })?;
 
     println!(
         "[WatchHistory] Successfully fetched {} movies and {} episodes",
@@ -245,7 +253,7 @@ pub async fn add_movie_to_history(
     app: AppHandle,
     tmdb_id: u64,
     watched_at: Option<String>,
-) -> Result<AddWatchHistoryResponse, String> {
+) -> Result<AddWatchHistoryResponse, CommandError> {
     println!("[WatchHistory] ========================================");
     println!("[WatchHistory] Adding movie to watch history...");
     println!("[WatchHistory] TMDB ID: {}", tmdb_id);
@@ -253,7 +261,7 @@ pub async fn add_movie_to_history(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = create_http_client()?;
+    let client = create_http_client(&app)?;
 
     let request_body = AddWatchHistoryRequest {
         movies: Some(vec![MovieWatchEntry {
@@ -282,7 +290,7 @@ pub async fn add_movie_to_history(
         .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("Failed to add movie to watch history: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to add movie to watch history", e))?;
 
     let status = response.status();
     println!("[WatchHistory] Response Status: {}", status);
@@ -291,21 +299,26 @@ pub async fn add_movie_to_history(
         let error_text = response.text().await.unwrap_or_default();
         println!("[WatchHistory] Response Body (Error): {}", error_text);
         println!("[WatchHistory] ========================================");
-        return Err(format!(
-            "Failed to add movie to watch history: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to add movie to watch history: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[WatchHistory] Response Body: {}", response_text);
 
-    let add_response: AddWatchHistoryResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse add history response: {}", e))?;
+    let add_response: AddWatchHistoryResponse =
+        serde_json::from_str(&response_text).map_err(|e| {
+            CommandError::InvalidResponse(format!("Failed to parse add history response: {}", e))
+        })?;
 
     println!("[WatchHistory] Successfully added movie to watch history");
     println!("[WatchHistory] ========================================");
@@ -327,7 +340,7 @@ pub async fn add_episode_to_history(
     season: u32,
     episode: u32,
     watched_at: Option<String>,
-) -> Result<AddWatchHistoryResponse, String> {
+) -> Result<AddWatchHistoryResponse, CommandError> {
     println!("[WatchHistory] ========================================");
     println!("[WatchHistory] Adding episode to watch history...");
     println!(
@@ -338,7 +351,7 @@ pub async fn add_episode_to_history(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = create_http_client()?;
+    let client = create_http_client(&app)?;
 
     let request_body = AddWatchHistoryRequest {
         movies: None,
@@ -369,7 +382,7 @@ pub async fn add_episode_to_history(
         .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("Failed to add episode to watch history: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to add episode to watch history", e))?;
 
     let status = response.status();
     println!("[WatchHistory] Response Status: {}", status);
@@ -378,21 +391,26 @@ pub async fn add_episode_to_history(
         let error_text = response.text().await.unwrap_or_default();
         println!("[WatchHistory] Response Body (Error): {}", error_text);
         println!("[WatchHistory] ========================================");
-        return Err(format!(
-            "Failed to add episode to watch history: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to add episode to watch history: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[WatchHistory] Response Body: {}", response_text);
 
-    let add_response: AddWatchHistoryResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse add history response: {}", e))?;
+    let add_response: AddWatchHistoryResponse =
+        serde_json::from_str(&response_text).map_err(|e| {
+            CommandError::InvalidResponse(format!("Failed to parse add history response: {}", e))
+        })?;
 
     println!("[WatchHistory] Successfully added episode to watch history");
     println!("[WatchHistory] ========================================");
@@ -410,7 +428,7 @@ pub async fn add_batch_to_history(
     app: AppHandle,
     movies: Option<Vec<MovieWatchEntry>>,
     episodes: Option<Vec<EpisodeWatchEntry>>,
-) -> Result<AddWatchHistoryResponse, String> {
+) -> Result<AddWatchHistoryResponse, CommandError> {
     println!("[WatchHistory] ========================================");
     println!("[WatchHistory] Adding batch to watch history...");
     println!(
@@ -422,7 +440,7 @@ pub async fn add_batch_to_history(
     let base_url = get_nacho_server_base_url(&app)?;
     let auth_token = get_nacho_auth_token(&app)?;
 
-    let client = create_http_client()?;
+    let client = create_http_client(&app)?;
 
     let request_body = AddWatchHistoryRequest { movies, episodes };
 
@@ -444,7 +462,7 @@ pub async fn add_batch_to_history(
         .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("Failed to add batch to watch history: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to add batch to watch history", e))?;
 
     let status = response.status();
     println!("[WatchHistory] Response Status: {}", status);
@@ -453,21 +471,26 @@ pub async fn add_batch_to_history(
         let error_text = response.text().await.unwrap_or_default();
         println!("[WatchHistory] Response Body (Error): {}", error_text);
         println!("[WatchHistory] ========================================");
-        return Err(format!(
-            "Failed to add batch to watch history: {} - {}",
-            status, error_text
+        return Err(CommandError::from_status(
+            status,
+            format!(
+                "Failed to add batch to watch history: {} - {}",
+                status, error_text
+            ),
         ));
     }
 
     let response_text = response
         .text()
         .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+        .map_err(|e| CommandError::request("Failed to read response", e))?;
 
     println!("[WatchHistory] Response Body: {}", response_text);
 
-    let add_response: AddWatchHistoryResponse = serde_json::from_str(&response_text)
-        .map_err(|e| format!("Failed to parse add history response: {}", e))?;
+    let add_response: AddWatchHistoryResponse =
+        serde_json::from_str(&response_text).map_err(|e| {
+            CommandError::InvalidResponse(format!("Failed to parse add history response: {}", e))
+        })?;
 
     println!("[WatchHistory] Successfully added batch to watch history");
     if let Some(count) = &add_response.count {
@@ -486,7 +509,7 @@ pub async fn add_batch_to_history(
 /// # Arguments
 /// * `tmdb_id` - The TMDB ID of the movie
 #[tauri::command]
-pub async fn is_movie_watched(app: AppHandle, tmdb_id: u64) -> Result<bool, String> {
+pub async fn is_movie_watched(app: AppHandle, tmdb_id: u64) -> Result<bool, CommandError> {
     let history = get_watch_history(app, None, None).await?;
 
     let is_watched = history.data.movies.iter().any(|m| m.tmdb_id == tmdb_id);
@@ -506,7 +529,7 @@ pub async fn is_episode_watched(
     tmdb_id: u64,
     season: u32,
     episode: u32,
-) -> Result<bool, String> {
+) -> Result<bool, CommandError> {
     let history = get_watch_history(app, None, None).await?;
 
     let is_watched = history
@@ -523,7 +546,7 @@ pub async fn is_episode_watched(
 pub async fn get_watched_movies(
     app: AppHandle,
     limit: Option<u32>,
-) -> Result<Vec<MovieHistoryItem>, String> {
+) -> Result<Vec<MovieHistoryItem>, CommandError> {
     let history = get_watch_history(app, limit, None).await?;
     Ok(history.data.movies)
 }
@@ -533,7 +556,7 @@ pub async fn get_watched_movies(
 pub async fn get_watched_episodes(
     app: AppHandle,
     limit: Option<u32>,
-) -> Result<Vec<EpisodeHistoryItem>, String> {
+) -> Result<Vec<EpisodeHistoryItem>, CommandError> {
     let history = get_watch_history(app, limit, None).await?;
     Ok(history.data.episodes)
 }
@@ -546,7 +569,7 @@ pub async fn get_watched_episodes(
 pub async fn get_show_watched_episodes(
     app: AppHandle,
     tmdb_id: u64,
-) -> Result<Vec<EpisodeHistoryItem>, String> {
+) -> Result<Vec<EpisodeHistoryItem>, CommandError> {
     let history = get_watch_history(app, None, None).await?;
 
     let show_episodes: Vec<EpisodeHistoryItem> = history
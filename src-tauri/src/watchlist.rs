@@ -0,0 +1,637 @@
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::error::CommandError;
+use crate::quality::rank_results;
+use crate::torrent_server::State;
+
+/// Name of the Tauri event carrying [`WatchlistDownload`] payloads to the frontend
+pub const WATCHLIST_DOWNLOAD_EVENT: &str = "watchlist-download";
+
+/// How often the scheduler checks whether a watchlist check is due
+const TICK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Seasons checked per show, counting back from the latest
+const SEASONS_TO_CHECK: u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchlistEntry {
+    pub tmdb_id: u64,
+    /// "movie" or "tv"
+    pub media_type: String,
+    /// Unix timestamp; only episodes airing after this are downloaded automatically
+    pub added_at: i64,
+    /// Download newly aired episodes of this show
+    #[serde(default = "default_true")]
+    pub auto_download: bool,
+    /// Episodes downloaded automatically (season, episode), so they aren't
+    /// downloaded again after the torrent is removed
+    #[serde(default)]
+    pub downloaded: Vec<(u32, u32)>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchlistDownload {
+    pub tmdb_id: u64,
+    pub season: u32,
+    pub episode: u32,
+    /// Release title of the downloaded result
+    pub title: String,
+}
+
+/// An entry's (tmdb_id, media_type)
+type EntryKey = (u64, String);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WatchlistFile {
+    entries: Vec<WatchlistEntry>,
+    /// Unix timestamp of the last completed check
+    last_checked: Option<i64>,
+    /// Entries known to be on the Nacho Server, to tell entries removed on one
+    /// side from entries added on the other
+    #[serde(default)]
+    synced: Vec<EntryKey>,
+}
+
+/// Entry as stored on the Nacho Server
+#[derive(Debug, Serialize, Deserialize)]
+struct RemoteEntry {
+    #[serde(rename = "tmdbID")]
+    tmdb_id: u64,
+    #[serde(rename = "mediaType")]
+    media_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RemoteWatchlistResponse {
+    #[serde(default)]
+    data: Vec<RemoteEntry>,
+}
+
+/// The watchlist, kept on [`State`]
+pub struct Watchlist {
+    file: Mutex<WatchlistFile>,
+    /// Only one check runs at a time, whether scheduled or requested
+    check_lock: tokio::sync::Mutex<()>,
+}
+
+impl Default for Watchlist {
+    fn default() -> Self {
+        Self {
+            file: Mutex::new(load().unwrap_or_else(|e| {
+                warn!(error=?e, "Failed to load watchlist");
+                WatchlistFile::default()
+            })),
+            check_lock: Default::default(),
+        }
+    }
+}
+
+impl Watchlist {
+    fn entries(&self) -> Vec<WatchlistEntry> {
+        self.file.lock().entries.clone()
+    }
+
+    /// Apply a change to the watchlist and persist it
+    fn update<T>(&self, f: impl FnOnce(&mut WatchlistFile) -> T) -> Result<T, CommandError> {
+        let mut watchlist = self.file.lock();
+        let result = f(&mut watchlist);
+        save(&watchlist)
+            .map_err(|e| CommandError::Internal(format!("Failed to save watchlist: {:#}", e)))?;
+        Ok(result)
+    }
+}
+
+fn watchlist_path() -> anyhow::Result<PathBuf> {
+    Ok(directories::ProjectDirs::from("com", "rqbit", "desktop")
+        .context("directories::ProjectDirs::from")?
+        .data_dir()
+        .join("watchlist.json"))
+}
+
+fn load() -> anyhow::Result<WatchlistFile> {
+    let path = watchlist_path()?;
+    if !path.exists() {
+        return Ok(WatchlistFile::default());
+    }
+    let file = File::open(&path).context("Failed to open watchlist file")?;
+    serde_json::from_reader(BufReader::new(file)).context("Failed to deserialize watchlist")
+}
+
+fn save(watchlist: &WatchlistFile) -> anyhow::Result<()> {
+    let path = watchlist_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("Failed to create watchlist directory")?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .context("Failed to create temp watchlist file")?;
+    serde_json::to_writer_pretty(BufWriter::new(file), watchlist)
+        .context("Failed to serialize watchlist")?;
+    std::fs::rename(&tmp_path, &path).context("Failed to rename temp watchlist file")?;
+    Ok(())
+}
+
+fn new_entry(tmdb_id: u64, media_type: String) -> WatchlistEntry {
+    WatchlistEntry {
+        tmdb_id,
+        media_type,
+        added_at: chrono::Utc::now().timestamp(),
+        auto_download: true,
+        downloaded: Vec::new(),
+    }
+}
+
+fn check_media_type(media_type: &str) -> Result<(), CommandError> {
+    match media_type {
+        "movie" | "tv" => Ok(()),
+        other => Err(CommandError::InvalidInput(format!(
+            "Unknown media type: {}",
+            other
+        ))),
+    }
+}
+
+/// Send a change to the Nacho Server, if one is configured. Failures are only logged,
+/// the next sync catches up.
+async fn push_remote(app: &AppHandle, entry: &RemoteEntry, remove: bool) {
+    let Ok(Some((base_url, token))) = crate::indexer::nacho_credentials(app) else {
+        return;
+    };
+    let state = app.state::<State>();
+    let client = match crate::proxy::http_client(&state) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Failed to sync watchlist: {}", e);
+            return;
+        }
+    };
+    let request = if remove {
+        client.delete(format!(
+            "{}/api/watchlist/{}/{}",
+            base_url, entry.media_type, entry.tmdb_id
+        ))
+    } else {
+        client
+            .post(format!("{}/api/watchlist", base_url))
+            .json(entry)
+    };
+    if let Err(e) = request
+        .header("X-Nacho-Auth", token)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+    {
+        warn!("Failed to sync watchlist: {}", e);
+        return;
+    }
+
+    let key = (entry.tmdb_id, entry.media_type.clone());
+    if let Err(e) = state.watchlist.update(|w| {
+        w.synced.retain(|k| *k != key);
+        if !remove {
+            w.synced.push(key);
+        }
+    }) {
+        warn!("Failed to sync watchlist: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_watchlist(state: tauri::State<'_, State>) -> Vec<WatchlistEntry> {
+    state.watchlist.entries()
+}
+
+#[tauri::command]
+pub async fn add_to_watchlist(
+    app: AppHandle,
+    tmdb_id: u64,
+    media_type: String,
+    auto_download: Option<bool>,
+) -> Result<WatchlistEntry, CommandError> {
+    check_media_type(&media_type)?;
+    let entry = app.state::<State>().watchlist.update(|w| {
+        let index = match w
+            .entries
+            .iter()
+            .position(|e| e.tmdb_id == tmdb_id && e.media_type == media_type)
+        {
+            Some(index) => index,
+            None => {
+                w.entries.push(new_entry(tmdb_id, media_type.clone()));
+                w.entries.len() - 1
+            }
+        };
+        if let Some(auto_download) = auto_download {
+            w.entries[index].auto_download = auto_download;
+        }
+        w.entries[index].clone()
+    })?;
+    push_remote(
+        &app,
+        &RemoteEntry {
+            tmdb_id,
+            media_type,
+        },
+        false,
+    )
+    .await;
+    Ok(entry)
+}
+
+#[tauri::command]
+pub async fn remove_from_watchlist(
+    app: AppHandle,
+    tmdb_id: u64,
+    media_type: String,
+) -> Result<(), CommandError> {
+    app.state::<State>().watchlist.update(|w| {
+        w.entries
+            .retain(|e| !(e.tmdb_id == tmdb_id && e.media_type == media_type))
+    })?;
+    push_remote(
+        &app,
+        &RemoteEntry {
+            tmdb_id,
+            media_type,
+        },
+        true,
+    )
+    .await;
+    Ok(())
+}
+
+/// Three-way merge of the Nacho Server's entries into the local watchlist, using the
+/// entries both had at the last sync to tell removals from additions. Returns the
+/// entries to add to and to remove from the server.
+fn merge_remote(w: &mut WatchlistFile, remote: &[EntryKey]) -> (Vec<EntryKey>, Vec<EntryKey>) {
+    let synced: HashSet<EntryKey> = w.synced.drain(..).collect();
+    let local: HashSet<EntryKey> = w
+        .entries
+        .iter()
+        .map(|e| (e.tmdb_id, e.media_type.clone()))
+        .collect();
+
+    // Removed on the server since the last sync
+    w.entries.retain(|e| {
+        let key = (e.tmdb_id, e.media_type.clone());
+        remote.contains(&key) || !synced.contains(&key)
+    });
+    let mut to_remove = Vec::new();
+    for key in remote {
+        if local.contains(key) {
+            continue;
+        }
+        if synced.contains(key) {
+            // Removed here since the last sync
+            to_remove.push(key.clone());
+        } else if !w
+            .entries
+            .iter()
+            .any(|e| e.tmdb_id == key.0 && e.media_type == key.1)
+        {
+            w.entries.push(new_entry(key.0, key.1.clone()));
+        }
+    }
+    let to_add = w
+        .entries
+        .iter()
+        .map(|e| (e.tmdb_id, e.media_type.clone()))
+        .filter(|key| !remote.contains(key))
+        .collect();
+
+    // Pushing the changes updates this as each one succeeds
+    w.synced = remote.to_vec();
+    (to_add, to_remove)
+}
+
+/// Merge the Nacho Server's watchlist with the local one, in both directions
+#[tauri::command]
+pub async fn sync_watchlist(app: AppHandle) -> Result<Vec<WatchlistEntry>, CommandError> {
+    let state = app.state::<State>();
+    let Some((base_url, token)) = crate::indexer::nacho_credentials(&app)? else {
+        return Ok(state.watchlist.entries());
+    };
+    let response = crate::proxy::http_client(&state)?
+        .get(format!("{}/api/watchlist", base_url))
+        .header("X-Nacho-Auth", &token)
+        .send()
+        .await
+        .map_err(|e| CommandError::request("Failed to fetch watchlist", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(CommandError::from_status(
+            status,
+            format!("Failed to fetch watchlist: {} - {}", status, error_text),
+        ));
+    }
+    let remote: RemoteWatchlistResponse = response
+        .json()
+        .await
+        .map_err(|e| CommandError::request("Failed to parse watchlist", e))?;
+
+    let mut remote_keys: Vec<EntryKey> = Vec::new();
+    for entry in remote.data {
+        let key = (entry.tmdb_id, entry.media_type);
+        if check_media_type(&key.1).is_ok() && !remote_keys.contains(&key) {
+            remote_keys.push(key);
+        }
+    }
+    let (to_add, to_remove) = state.watchlist.update(|w| merge_remote(w, &remote_keys))?;
+    for (keys, remove) in [(to_add, false), (to_remove, true)] {
+        for (tmdb_id, media_type) in keys {
+            let entry = RemoteEntry {
+                tmdb_id,
+                media_type,
+            };
+            push_remote(&app, &entry, remove).await;
+        }
+    }
+    Ok(state.watchlist.entries())
+}
+
+/// Look for newly aired episodes of watchlisted shows and download them now
+#[tauri::command]
+pub async fn check_watchlist(app: AppHandle) -> Result<Vec<WatchlistDownload>, CommandError> {
+    check(&app).await
+}
+
+/// Spawn the task that periodically downloads newly aired episodes
+pub fn spawn_watchlist_scheduler(app: AppHandle) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(config) = app
+                .state::<State>()
+                .shared
+                .read()
+                .as_ref()
+                .map(|s| s.config.watchlist.clone())
+            else {
+                continue;
+            };
+            if !config.auto_download {
+                continue;
+            }
+            let last_checked = app
+                .state::<State>()
+                .watchlist
+                .file
+                .lock()
+                .last_checked
+                .unwrap_or(0);
+            let due = chrono::Utc::now().timestamp() - last_checked
+                >= config.check_interval.as_secs() as i64;
+            if due && let Err(e) = check(&app).await {
+                warn!("Watchlist check failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn check(app: &AppHandle) -> Result<Vec<WatchlistDownload>, CommandError> {
+    let state = app.state::<State>();
+    let _guard = state.watchlist.check_lock.lock().await;
+    if let Err(e) = sync_watchlist(app.clone()).await {
+        warn!("Failed to sync watchlist: {}", e);
+    }
+
+    let (config, profile) = state
+        .shared
+        .read()
+        .as_ref()
+        .map(|s| {
+            let config = s.config.watchlist.clone();
+            let profile = s.config.search.profile(config.profile.as_deref());
+            (config, profile)
+        })
+        .ok_or_else(|| CommandError::NotConfigured("Configuration not available".to_string()))?;
+
+    let watched: HashSet<(u64, u32, u32)> = if config.skip_watched {
+        match crate::watch_history::get_watch_history(app.clone(), None, None).await {
+            Ok(history) => history
+                .data
+                .episodes
+                .into_iter()
+                .map(|e| (e.tmdb_id, e.season, e.episode))
+                .collect(),
+            Err(e) => {
+                warn!("Failed to get watch history: {}", e);
+                HashSet::new()
+            }
+        }
+    } else {
+        HashSet::new()
+    };
+
+    let shows: Vec<WatchlistEntry> = state
+        .watchlist
+        .entries()
+        .into_iter()
+        .filter(|e| e.media_type == "tv" && e.auto_download)
+        .collect();
+    let mut downloads = Vec::new();
+    for show in shows {
+        let aired = match aired_episodes(app, &show).await {
+            Ok(aired) => aired,
+            Err(e) => {
+                warn!("Failed to check show {}: {}", show.tmdb_id, e);
+                continue;
+            }
+        };
+        let in_library: HashSet<(u32, u32)> = state
+            .torrent_db
+            .get_by_tmdb_id(show.tmdb_id, "tv")
+            .into_iter()
+            .filter_map(|e| e.episode_info)
+            .map(|(s, e)| (s as u32, e as u32))
+            .collect();
+
+        for (season, episode, runtime) in aired {
+            if show.downloaded.contains(&(season, episode))
+                || in_library.contains(&(season, episode))
+                || watched.contains(&(show.tmdb_id, season, episode))
+            {
+                continue;
+            }
+            match download_episode(app, &profile, show.tmdb_id, season, episode, runtime).await {
+                Ok(Some(download)) => {
+                    info!(
+                        "Downloading {} for show {} S{:02}E{:02}",
+                        download.title, show.tmdb_id, season, episode
+                    );
+                    state.watchlist.update(|w| {
+                        if let Some(e) = w
+                            .entries
+                            .iter_mut()
+                            .find(|e| e.tmdb_id == show.tmdb_id && e.media_type == show.media_type)
+                        {
+                            e.downloaded.push((season, episode));
+                        }
+                    })?;
+                    if let Err(e) = app.emit(WATCHLIST_DOWNLOAD_EVENT, &download) {
+                        warn!("Failed to emit watchlist download: {}", e);
+                    }
+                    downloads.push(download);
+                }
+                Ok(None) => info!(
+                    "No acceptable release yet for show {} S{:02}E{:02}",
+                    show.tmdb_id, season, episode
+                ),
+                Err(e) => warn!(
+                    "Failed to download show {} S{:02}E{:02}: {}",
+                    show.tmdb_id, season, episode, e
+                ),
+            }
+        }
+    }
+
+    state
+        .watchlist
+        .update(|w| w.last_checked = Some(chrono::Utc::now().timestamp()))?;
+    Ok(downloads)
+}
+
+/// Episodes of the latest seasons that aired since the show was added, as
+/// (season, episode, runtime)
+async fn aired_episodes(
+    app: &AppHandle,
+    show: &WatchlistEntry,
+) -> Result<Vec<(u32, u32, Option<u32>)>, CommandError> {
+    let details = crate::tmdb::get_tmdb_show(app.clone(), show.tmdb_id).await?;
+    let latest = details.number_of_seasons.unwrap_or(0);
+    let today = chrono::Utc::now().date_naive();
+    let since = chrono::DateTime::from_timestamp(show.added_at, 0)
+        .map(|t| t.date_naive())
+        .unwrap_or(today);
+
+    let mut aired = Vec::new();
+    for season in latest.saturating_sub(SEASONS_TO_CHECK - 1).max(1)..=latest {
+        let season = crate::tmdb::get_tmdb_season(app.clone(), show.tmdb_id, season).await?;
+        for episode in season.episodes.into_iter().flatten() {
+            let Some(air_date) = episode
+                .air_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            if air_date >= since && air_date <= today {
+                aired.push((
+                    episode.season_number,
+                    episode.episode_number,
+                    episode.runtime,
+                ));
+            }
+        }
+    }
+    Ok(aired)
+}
+
+/// Search for an episode and add the best acceptable result. Returns None if
+/// nothing satisfies the profile yet.
+async fn download_episode(
+    app: &AppHandle,
+    profile: &crate::config::QualityProfile,
+    tmdb_id: u64,
+    season: u32,
+    episode: u32,
+    runtime: Option<u32>,
+) -> Result<Option<WatchlistDownload>, CommandError> {
+    let results = crate::torrent_search::search_torrents_for_show(
+        app.clone(),
+        tmdb_id,
+        Some(season),
+        Some(episode),
+        None,
+    )
+    .await?;
+    let Some(best) = rank_results(results, profile, runtime)
+        .into_iter()
+        .find(|r| r.rejection.is_none() && r.result.seeders > 0)
+    else {
+        return Ok(None);
+    };
+
+    crate::torrent_search::download_torrent_from_prowlarr(
+        app.state::<State>(),
+        app.clone(),
+        best.result.download_url,
+        Some(tmdb_id),
+        Some("tv".to_string()),
+        Some((season as i32, episode as i32)),
+        None,
+    )
+    .await?;
+    Ok(Some(WatchlistDownload {
+        tmdb_id,
+        season,
+        episode,
+        title: best.result.title,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(tmdb_id: u64) -> EntryKey {
+        (tmdb_id, "movie".to_owned())
+    }
+
+    #[test]
+    fn test_merge_remote() {
+        let mut w = WatchlistFile {
+            // 1 is on both sides, 2 was removed on the server, 4 was added here
+            entries: [1, 2, 4]
+                .into_iter()
+                .map(|id| new_entry(id, "movie".to_owned()))
+                .collect(),
+            last_checked: None,
+            // 3 was removed here
+            synced: vec![key(1), key(2), key(3)],
+        };
+        // 5 was added on the server
+        let (to_add, to_remove) = merge_remote(&mut w, &[key(1), key(3), key(5)]);
+
+        let ids: Vec<u64> = w.entries.iter().map(|e| e.tmdb_id).collect();
+        assert_eq!(ids, vec![1, 4, 5]);
+        assert_eq!(to_add, vec![key(4)]);
+        assert_eq!(to_remove, vec![key(3)]);
+        assert_eq!(w.synced, vec![key(1), key(3), key(5)]);
+    }
+
+    #[test]
+    fn test_merge_remote_first_sync() {
+        let mut w = WatchlistFile {
+            entries: vec![new_entry(1, "movie".to_owned())],
+            ..Default::default()
+        };
+        // Nothing was synced before, so nothing counts as removed
+        let (to_add, to_remove) = merge_remote(&mut w, &[key(2)]);
+        assert_eq!(w.entries.len(), 2);
+        assert_eq!(to_add, vec![key(1)]);
+        assert!(to_remove.is_empty());
+    }
+}
@@ -2,6 +2,8 @@ import { Component, Show, createSignal, createEffect } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
 import type { TorrentResult } from "../types/torrent";
 import type { TmdbEpisode } from "../types/tmdb";
+import { downloadFromIndexer } from "../lib/torrentDb";
+import { errorMessage } from "../lib/utils";
 
 interface DownloadProgressPopupProps {
   isOpen: boolean;
@@ -69,12 +71,12 @@ const DownloadProgressPopup: Component<DownloadProgressPopupProps> = (
 
       console.log("Calling download_torrent_from_prowlarr");
 
-      await invoke("download_torrent_from_prowlarr", {
-        downloadUrl: props.torrent.download_url,
-        tmdbId: props.tmdbId,
-        mediaType: "tv",
-        episodeInfo: episodeInfo,
-      });
+      await downloadFromIndexer(
+        props.torrent.download_url,
+        props.tmdbId,
+        "tv",
+        episodeInfo
+      );
 
       console.log("Download command completed successfully");
       updateStep(0, "complete");
@@ -96,11 +98,11 @@ const DownloadProgressPopup: Component<DownloadProgressPopupProps> = (
       checkFileType();
     } catch (e: any) {
       console.error("Download failed:", e);
-      setDownloadError(e?.toString?.() ?? "Download failed");
+      setDownloadError(errorMessage(e, "Download failed"));
 
       const activeIndex = steps().findIndex((s) => s.status === "active");
       if (activeIndex !== -1) {
-        updateStep(activeIndex, "error", e?.toString?.());
+        updateStep(activeIndex, "error", errorMessage(e));
       }
     }
   };
@@ -278,7 +280,7 @@ const DownloadProgressPopup: Component<DownloadProgressPopupProps> = (
       await playHandler(matchingTorrent.id, primaryFile, isMp4);
     } catch (e: any) {
       console.error("Failed to find/play torrent:", e);
-      alert(`Failed to play: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to play: ${errorMessage(e)}`);
     }
   };
 
@@ -11,8 +11,10 @@ import { invoke } from "@tauri-apps/api/core";
 import type { TmdbMovie, TmdbMovieImages } from "../types/tmdb";
 import type { TorrentResult } from "../types/torrent";
 import { getBackdropUrl, getPosterUrl } from "../lib/tmdb";
+import { downloadFromIndexer } from "../lib/torrentDb";
 
 import { openUrl } from "@tauri-apps/plugin-opener";
+import { errorMessage } from "../lib/utils";
 
 interface MovieDetailsProps {
   isOpen: boolean;
@@ -169,7 +171,7 @@ const MovieDetails: Component<MovieDetailsProps> = (props) => {
       }
     } catch (e: any) {
       console.error("Failed to fetch movie details:", e);
-      setError(e?.toString?.() ?? "Failed to load movie details");
+      setError(errorMessage(e, "Failed to load movie details"));
     } finally {
       setLoading(false);
     }
@@ -211,7 +213,7 @@ const MovieDetails: Component<MovieDetailsProps> = (props) => {
       setTorrents(results);
     } catch (e: any) {
       console.error("Failed to search torrents:", e);
-      setTorrentsError(e?.toString?.() ?? "Failed to search torrents");
+      setTorrentsError(errorMessage(e, "Failed to search torrents"));
     } finally {
       setTorrentsLoading(false);
     }
@@ -228,12 +230,12 @@ const MovieDetails: Component<MovieDetailsProps> = (props) => {
       console.log("Starting download for torrent:", torrent.title);
       setDownloadingTorrent(torrent.download_url);
 
-      const response = await invoke("download_torrent_from_prowlarr", {
-        downloadUrl: torrent.download_url,
-        tmdbId: props.tmdbId,
-        mediaType: "movie",
-        episodeInfo: null,
-      });
+      const response = await downloadFromIndexer(
+        torrent.download_url,
+        props.tmdbId ?? null,
+        "movie",
+        null
+      );
 
       console.log("Torrent added successfully:", response);
 
@@ -260,7 +262,7 @@ const MovieDetails: Component<MovieDetailsProps> = (props) => {
       }
       setShowDownloadWaitPopup(false);
 
-      alert(`❌ Failed to download: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`❌ Failed to download: ${errorMessage(e)}`);
     } finally {
       setDownloadingTorrent(null);
     }
@@ -18,12 +18,14 @@ import type {
 } from "../types/tmdb";
 import type { TorrentResult } from "../types/torrent";
 import { getPosterUrl, getBackdropUrl } from "../lib/tmdb";
+import { downloadFromIndexer } from "../lib/torrentDb";
 import {
   getShowHistory,
   getWatchedEpisodes,
   type ShowHistory,
 } from "../lib/userHistoryStore";
 import DownloadProgressPopup from "./DownloadProgressPopup";
+import { errorMessage } from "../lib/utils";
 
 interface SeriesDetailsProps {
   isOpen: boolean;
@@ -32,6 +34,8 @@ interface SeriesDetailsProps {
   imdbId?: string;
   traktSlug?: string;
   showTitle: string;
+  /** Season to open on instead of the one from watch history */
+  initialSeason?: number;
 }
 
 const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
@@ -191,11 +195,11 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
 
       // Determine which season to load based on watch history
       if (showDetails.number_of_seasons && showDetails.number_of_seasons > 0) {
-        let seasonToLoad = 1; // Default to season 1
+        let seasonToLoad = props.initialSeason ?? 1; // Default to season 1
 
         // Check if we have local history to determine the best starting season
         const history = localShowHistory();
-        if (history?.latestEpisode) {
+        if (props.initialSeason === undefined && history?.latestEpisode) {
           // Start on the season of the latest watched episode
           seasonToLoad = history.latestEpisode.season;
           console.log(
@@ -216,7 +220,7 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
       }
     } catch (e: any) {
       console.error("Failed to fetch show details:", e);
-      setError(e?.toString?.() ?? "Failed to load show details");
+      setError(errorMessage(e, "Failed to load show details"));
     } finally {
       setLoading(false);
     }
@@ -431,7 +435,7 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
       setTorrents(results);
     } catch (e: any) {
       console.error("Failed to search torrents:", e);
-      setTorrentError(e?.toString?.() ?? "Failed to search for torrents");
+      setTorrentError(errorMessage(e, "Failed to search for torrents"));
     } finally {
       setLoadingTorrents(false);
     }
@@ -466,12 +470,12 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
         ? [currentEpisode.season_number, currentEpisode.episode_number]
         : null;
 
-      await invoke("download_torrent_from_prowlarr", {
-        downloadUrl: torrent.download_url,
-        tmdbId: tmdbId,
-        mediaType: "tv",
-        episodeInfo: episodeInfo,
-      });
+      await downloadFromIndexer(
+        torrent.download_url,
+        tmdbId,
+        "tv",
+        episodeInfo
+      );
 
       console.log(
         "Torrent added successfully with TMDB ID:",
@@ -500,7 +504,7 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
       }
       setShowDownloadWaitPopup(false);
 
-      alert(`❌ Failed to download: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`❌ Failed to download: ${errorMessage(e)}`);
     } finally {
       setDownloadingTorrent(null);
     }
@@ -747,7 +751,7 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
       await markEpisodeWatched(episode);
     } catch (e: any) {
       console.error("Failed to play episode:", e);
-      alert(`Failed to play episode: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to play episode: ${errorMessage(e)}`);
     }
   };
 
@@ -782,7 +786,7 @@ const SeriesDetails: Component<SeriesDetailsProps> = (props) => {
       console.error("Failed to mark episode as watched:", e);
       alert(
         `❌ Failed to mark episode as watched: ${
-          e?.toString?.() ?? "Unknown error"
+          errorMessage(e)
         }`
       );
     }
@@ -1,11 +1,18 @@
 /* @refresh reload */
 import { render } from "solid-js/web";
-import { Route, Router } from "@solidjs/router";
-import { getCurrent, onOpenUrl } from "@tauri-apps/plugin-deep-link";
+import {
+  Route,
+  Router,
+  useLocation,
+  useNavigate,
+  type RouteSectionProps,
+} from "@solidjs/router";
 import { invoke } from "@tauri-apps/api/core";
-import { fetch } from "@tauri-apps/plugin-http";
+import { listen } from "@tauri-apps/api/event";
 
-import { lazy } from "solid-js";
+import { createEffect, lazy } from "solid-js";
+import { deepLinkTarget, setDeepLinkTarget } from "./lib/deepLink";
+import { errorMessage } from "./lib/utils";
 
 const Home = lazy(() => import("./pages/Home"));
 const Player = lazy(() => import("./pages/Player"));
@@ -73,119 +80,98 @@ function updateDeepLinkSplash(message: string) {
   }
 }
 
-// Function to parse deep link URL and extract auth token
-async function handleAuthDeepLink(url: string) {
-  // Prevent processing the same URL multiple times
-  const lastProcessedUrl = localStorage.getItem("last-deep-link-url");
-  const lastProcessedTime = localStorage.getItem("last-deep-link-time");
-
-  // If we processed this exact URL in the last 10 seconds, ignore it
-  if (lastProcessedUrl === url && lastProcessedTime) {
-    const timeSinceProcessed = Date.now() - parseInt(lastProcessedTime);
-    if (timeSinceProcessed < 10000) {
-      console.log("Deep link already processed recently, ignoring duplicate");
-      return;
+type DeepLinkEvent = { id: number } & (
+  | { type: "magnet_received"; uri: string; name: string | null }
+  | { type: "open_movie"; tmdb_id: number }
+  | {
+      type: "open_show";
+      tmdb_id: number;
+      season: number | null;
+      episode: number | null;
     }
+  | { type: "server_linking"; server: string }
+  | { type: "server_linked"; server: string; username: string | null }
+  | { type: "error"; url: string; error: string }
+);
+
+// Events arriving while the pending ones are fetched come through both paths
+const handledDeepLinks = new Set<number>();
+
+async function addMagnet(uri: string, name: string | null) {
+  // Any web page can open a magnet link, so nothing is added without asking
+  await invoke("show_main");
+  if (
+    !window.confirm(`Add this torrent to your downloads?\n\n${name ?? uri}`)
+  ) {
+    return;
   }
-
-  // Mark this URL as being processed
-  localStorage.setItem("last-deep-link-url", url);
-  localStorage.setItem("last-deep-link-time", Date.now().toString());
-  console.log("Processing deep link:", url);
-
   try {
-    // Parse the URL - expected format: nacho-time://auth?token=xxx&server=yyy
-    const urlObj = new URL(url);
+    await invoke("torrent_create_from_url", { url: uri, opts: null });
+  } catch (e) {
+    console.error("Failed to add magnet link:", e);
+    alert(`Failed to add torrent: ${errorMessage(e)}`);
+  }
+}
 
-    if (urlObj.protocol !== "nacho-time:") {
-      console.warn("Invalid protocol:", urlObj.protocol);
-      return;
-    }
+// Deep links are parsed and handled by the backend, which reports the outcome here
+function handleDeepLinkEvent(event: DeepLinkEvent) {
+  if (handledDeepLinks.has(event.id)) return;
+  handledDeepLinks.add(event.id);
+  console.log("Deep link event:", event.type);
 
-    // Check if this is an auth link
-    if (urlObj.hostname === "auth" || urlObj.pathname.startsWith("//auth")) {
+  switch (event.type) {
+    case "server_linking":
       showDeepLinkSplash("Setting up authentication...");
-
-      // Extract parameters
-      const params = new URLSearchParams(urlObj.search);
-      const token = params.get("token");
-      const server = params.get("server");
-
-      console.log("Auth deep link detected");
-      console.log("Token:", token ? "***" : "missing");
-      console.log("Server:", server);
-
-      if (!token || !server) {
-        console.error("Missing required parameters");
-        alert("Invalid authentication link: missing token or server URL");
-        hideDeepLinkSplash();
-        return;
-      }
-
-      // Decode the server URL (in case it's URL encoded)
-      const decodedServer = decodeURIComponent(server);
-
-      // Save settings
-      const settings = {
-        nacho_server_url: decodedServer,
-        nacho_auth_token: token,
-      };
-
-      console.log("Saving settings:", {
-        nacho_server_url: decodedServer,
-        nacho_auth_token: "***",
+      break;
+    case "server_linked":
+      window.dispatchEvent(
+        new CustomEvent("nacho-auth-success", {
+          detail: { user: { username: event.username } },
+        })
+      );
+      updateDeepLinkSplash(
+        `✓ Successfully authenticated as ${event.username || "user"}`
+      );
+      window.location.href = "/";
+      break;
+    case "magnet_received":
+      addMagnet(event.uri, event.name);
+      break;
+    case "open_movie":
+      setDeepLinkTarget({ type: "movie", tmdbId: event.tmdb_id });
+      break;
+    case "open_show":
+      setDeepLinkTarget({
+        type: "show",
+        tmdbId: event.tmdb_id,
+        season: event.season,
+        episode: event.episode,
       });
+      break;
+    case "error":
+      console.error("Failed to process deep link:", event.error);
+      alert(`Failed to process link: ${event.error}`);
+      hideDeepLinkSplash();
+      break;
+  }
+}
 
-      await invoke("save_settings", { settings });
-
-      // Verify the token
-      try {
-        const response = await fetch(`${decodedServer}/api/user`, {
-          headers: {
-            "X-Nacho-Auth": token,
-          },
-        });
-
-        if (response.ok) {
-          const data = await response.json();
-          console.log("Authentication verified:", data);
-
-          // Emit success event
-          window.dispatchEvent(
-            new CustomEvent("nacho-auth-success", {
-              detail: { user: data.user },
-            })
-          );
-
-          // Update splash with success message
-          updateDeepLinkSplash(
-            `✓ Successfully authenticated as ${data.user?.username || "user"}`
-          );
-
-          // Navigate to home page
-          window.location.href = "/";
-        } else {
-          console.error("Token verification failed:", response.status);
-          alert("Authentication failed: Invalid token");
-          hideDeepLinkSplash();
-        }
-      } catch (e) {
-        console.error("Failed to verify token:", e);
-        alert(`Authentication failed: ${e}`);
-        hideDeepLinkSplash();
-      }
+// Movie and show pages open from the home page, so leave the player for a deep link
+function DeepLinkNavigator(props: RouteSectionProps) {
+  const navigate = useNavigate();
+  const location = useLocation();
+  createEffect(() => {
+    if (deepLinkTarget() && location.pathname !== "/") {
+      navigate("/");
     }
-  } catch (e) {
-    console.error("Failed to process deep link:", e);
-    alert(`Failed to process authentication link: ${e}`);
-    hideDeepLinkSplash();
-  }
+  });
+  return <>{props.children}</>;
 }
 
 if (root) {
   render(
     () => (
-      <Router>
+      <Router root={DeepLinkNavigator}>
         <Route path="/" component={Home} />
         <Route path="/player" component={Player} />
       </Router>
@@ -196,20 +182,14 @@ if (root) {
 
 // Initialize deep link handling
 (async () => {
-  // Handle deep links on startup
-  const startUrls = await getCurrent();
-  if (startUrls) {
-    console.log("App started with deep links:", startUrls);
-    for (const url of startUrls) {
-      await handleAuthDeepLink(url);
-    }
+  // Handle deep links that arrived before the page loaded
+  await listen<DeepLinkEvent>("deep-link-event", (e) =>
+    handleDeepLinkEvent(e.payload)
+  );
+  const pending = await invoke<DeepLinkEvent[]>(
+    "take_pending_deep_link_events"
+  );
+  for (const event of pending) {
+    handleDeepLinkEvent(event);
   }
-
-  // Handle deep links while app is running
-  await onOpenUrl((urls) => {
-    console.log("Deep link received:", urls);
-    for (const url of urls) {
-      handleAuthDeepLink(url);
-    }
-  });
 })();
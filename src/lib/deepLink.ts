@@ -0,0 +1,18 @@
+import { createSignal } from "solid-js";
+
+/** Movie or show a `nacho-time://` link asked to open */
+export type DeepLinkTarget =
+  | { type: "movie"; tmdbId: number }
+  | {
+      type: "show";
+      tmdbId: number;
+      season: number | null;
+      episode: number | null;
+    };
+
+// Set from the deep link handler in index.tsx, which runs outside the router,
+// and picked up by the home page once it is mounted
+const [deepLinkTarget, setDeepLinkTarget] =
+  createSignal<DeepLinkTarget | null>(null);
+
+export { deepLinkTarget, setDeepLinkTarget };
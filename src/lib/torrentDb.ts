@@ -4,6 +4,7 @@
  */
 
 import { invoke } from "@tauri-apps/api/core";
+import { isCommandError } from "./utils";
 
 export type TorrentIdOrHash = number | { InfoHash: string };
 
@@ -124,3 +125,49 @@ export function extractImdbCode(text: string): string | null {
 export function formatImdbCode(code: string): string {
   return code.toLowerCase().replace(/^tt/, "tt");
 }
+
+export interface TorrentPreview {
+  name: string | null;
+  info_hash: string | null;
+  total_size: number | null;
+  files: { path: string; size: number }[];
+  is_magnet: boolean;
+  warnings: string[];
+}
+
+/**
+ * Fetch an indexer download and list its contents without adding it
+ */
+export async function previewTorrentDownload(
+  downloadUrl: string
+): Promise<TorrentPreview> {
+  return await invoke("preview_torrent_download", { downloadUrl });
+}
+
+/**
+ * Add an indexer download. Downloads with suspicious contents are only added
+ * after the user confirms.
+ */
+export async function downloadFromIndexer(
+  downloadUrl: string,
+  tmdbId: number | null,
+  mediaType: string,
+  episodeInfo: [number, number] | null
+): Promise<ApiAddTorrentResponse> {
+  const args = { downloadUrl, tmdbId, mediaType, episodeInfo };
+  try {
+    return await invoke("download_torrent_from_prowlarr", args);
+  } catch (e) {
+    if (
+      !isCommandError(e) ||
+      e.code !== "suspicious_download" ||
+      !window.confirm(`${e.message}\n\nAdd it anyway?`)
+    ) {
+      throw e;
+    }
+    return await invoke("download_torrent_from_prowlarr", {
+      ...args,
+      allowSuspicious: true,
+    });
+  }
+}
@@ -4,3 +4,42 @@ import { twMerge } from "tailwind-merge"
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+/** Error rejected by backend commands that report structured errors */
+export interface CommandError {
+  code:
+    | "not_configured"
+    | "unauthorized"
+    | "not_found"
+    | "rate_limited"
+    | "network"
+    | "server"
+    | "invalid_response"
+    | "invalid_input"
+    | "suspicious_download"
+    | "internal"
+  message: string
+  status: number
+  retryable: boolean
+}
+
+export function isCommandError(e: unknown): e is CommandError {
+  return (
+    typeof e === "object" &&
+    e !== null &&
+    typeof (e as CommandError).code === "string" &&
+    typeof (e as CommandError).message === "string"
+  )
+}
+
+/** Readable message for anything a command rejected with */
+export function errorMessage(e: unknown, fallback = "Unknown error"): string {
+  if (isCommandError(e)) return e.message
+  if (typeof e === "string") return e || fallback
+  if (e instanceof Error) return e.message || fallback
+  if (typeof e === "object" && e !== null) {
+    const message = (e as { message?: unknown }).message
+    if (typeof message === "string" && message) return message
+  }
+  return e == null ? fallback : String(e)
+}
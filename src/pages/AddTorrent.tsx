@@ -1,6 +1,7 @@
 import { Component, createSignal } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
 import { extractImdbCode, isValidImdbCode } from "../lib/torrentDb";
+import { errorMessage } from "../lib/utils";
 
 type AddTorrentProps = {
   onSuccess: () => void;
@@ -103,7 +104,7 @@ const AddTorrent: Component<AddTorrentProps> = (props) => {
       }, 1000);
     } catch (e: any) {
       console.error("Failed to add torrent:", e);
-      setError(e?.toString?.() ?? "Failed to add torrent");
+      setError(errorMessage(e, "Failed to add torrent"));
     } finally {
       setIsUploading(false);
     }
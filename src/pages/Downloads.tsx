@@ -28,6 +28,7 @@ import { getPosterUrl } from "../lib/tmdb";
 import Player from "./Player";
 import CustomAlert from "../components/CustomAlert";
 import { SiVlcmediaplayer } from "solid-icons/si";
+import { errorMessage } from "../lib/utils";
 
 // Register Chart.js components
 try {
@@ -412,7 +413,7 @@ const Downloads: Component = () => {
       setError(null);
     } catch (e: any) {
       console.error("Failed to fetch torrents:", e);
-      setError(e?.toString?.() ?? "Failed to fetch torrents");
+      setError(errorMessage(e, "Failed to fetch torrents"));
     }
   };
 
@@ -448,7 +449,7 @@ const Downloads: Component = () => {
       await invoke("torrent_action_delete", { id });
       await fetchTorrents(false);
     } catch (e: any) {
-      alert(`Failed to delete torrent: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to delete torrent: ${errorMessage(e)}`);
     }
   };
 
@@ -457,7 +458,7 @@ const Downloads: Component = () => {
       await invoke("torrent_action_pause", { id });
       await fetchTorrents(false);
     } catch (e: any) {
-      alert(`Failed to pause torrent: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to pause torrent: ${errorMessage(e)}`);
     }
   };
 
@@ -466,7 +467,7 @@ const Downloads: Component = () => {
       await invoke("torrent_action_start", { id });
       await fetchTorrents(false);
     } catch (e: any) {
-      alert(`Failed to start torrent: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to start torrent: ${errorMessage(e)}`);
     }
   };
 
@@ -556,7 +557,7 @@ const Downloads: Component = () => {
       await opener.openPath(filePath, "vlc");
     } catch (e: any) {
       console.error("Failed to open in VLC:", e);
-      alert(`Failed to open in VLC: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to open in VLC: ${errorMessage(e)}`);
     }
   };
 
@@ -598,7 +599,7 @@ const Downloads: Component = () => {
       }, 100);
     } catch (e: any) {
       console.error("Failed to save TMDB ID:", e);
-      alert(`Failed to save TMDB ID: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to save TMDB ID: ${errorMessage(e)}`);
     }
   };
 
@@ -706,7 +707,7 @@ const Downloads: Component = () => {
       setShowPlayer(true);
     } catch (e: any) {
       console.error("Failed to open in player:", e);
-      alert(`Failed to open in player: ${e?.toString?.() ?? "Unknown error"}`);
+      alert(`Failed to open in player: ${errorMessage(e)}`);
     }
   };
 
@@ -763,7 +764,7 @@ const Downloads: Component = () => {
     } catch (e: any) {
       console.error("Failed to transmux and stream:", e);
       alert(
-        `Failed to prepare playback: ${e?.toString?.() ?? "Unknown error"}`
+        `Failed to prepare playback: ${errorMessage(e)}`
       );
     }
   };
@@ -1,4 +1,10 @@
-import { Component, createSignal, onMount } from "solid-js";
+import {
+  Component,
+  Show,
+  createEffect,
+  createSignal,
+  onMount,
+} from "solid-js";
 import Media from "./Movies";
 import Series from "./Series";
 import Downloads from "./Downloads";
@@ -9,7 +15,12 @@ import Settings from "./Settings";
 import Search from "./Search";
 import Header from "../components/Header";
 import ProfileButton from "../components/ProfileButton";
+import MovieDetails from "../components/MovieDetails";
+import SeriesDetails from "../components/SeriesDetails";
 import { invoke } from "@tauri-apps/api/core";
+import { deepLinkTarget, setDeepLinkTarget } from "../lib/deepLink";
+import type { TmdbMovie, TmdbShow } from "../types/tmdb";
+import { errorMessage } from "../lib/utils";
 
 const Home: Component = () => {
   const [activeTab, setActiveTab] = createSignal<
@@ -23,6 +34,14 @@ const Home: Component = () => {
     | "search"
   >("media");
 
+  // Title opened by a nacho-time:// link, once its name is known
+  const [linkedTitle, setLinkedTitle] = createSignal<{
+    type: "movie" | "show";
+    tmdbId: number;
+    title: string;
+    season: number | null;
+  } | null>(null);
+
   onMount(async () => {
     // Show the window when the component is mounted
     setTimeout(async () => {
@@ -30,6 +49,40 @@ const Home: Component = () => {
     }, 20);
   });
 
+  createEffect(async () => {
+    const target = deepLinkTarget();
+    if (!target) return;
+    setDeepLinkTarget(null);
+    try {
+      if (target.type === "movie") {
+        const movie = await invoke<TmdbMovie>("get_tmdb_movie", {
+          tmdbId: target.tmdbId,
+        });
+        setActiveTab("media");
+        setLinkedTitle({
+          type: "movie",
+          tmdbId: movie.id,
+          title: movie.title,
+          season: null,
+        });
+      } else {
+        const show = await invoke<TmdbShow>("get_tmdb_show", {
+          tmdbId: target.tmdbId,
+        });
+        setActiveTab("series");
+        setLinkedTitle({
+          type: "show",
+          tmdbId: show.id,
+          title: show.name,
+          season: target.season,
+        });
+      }
+    } catch (e) {
+      console.error("Failed to open linked title:", e);
+      alert(`Failed to open link: ${errorMessage(e)}`);
+    }
+  });
+
   return (
     <div class="min-h-screen ">
       <Header
@@ -117,6 +170,32 @@ const Home: Component = () => {
           {activeTab() === "settings" && <Settings />}
         </div>
       </main>
+
+      <Show when={linkedTitle()?.type === "movie" && linkedTitle()}>
+        {(linked) => (
+          <MovieDetails
+            isOpen={true}
+            onClose={() => setLinkedTitle(null)}
+            tmdbId={linked().tmdbId}
+            imdbId={undefined}
+            traktSlug={undefined}
+            movieTitle={linked().title}
+          />
+        )}
+      </Show>
+      <Show when={linkedTitle()?.type === "show" && linkedTitle()}>
+        {(linked) => (
+          <SeriesDetails
+            isOpen={true}
+            onClose={() => setLinkedTitle(null)}
+            tmdbId={linked().tmdbId}
+            imdbId={undefined}
+            traktSlug={undefined}
+            showTitle={linked().title}
+            initialSeason={linked().season ?? undefined}
+          />
+        )}
+      </Show>
     </div>
   );
 };
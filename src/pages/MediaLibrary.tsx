@@ -1,6 +1,7 @@
 import { Component, createSignal, onMount, For, Show } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
 import { getAllTorrentsWithImdb, type TorrentWithImdb } from "../lib/torrentDb";
+import { errorMessage } from "../lib/utils";
 
 interface TorrentInfo {
   id: number;
@@ -56,7 +57,7 @@ const MediaLibrary: Component = () => {
       setError(null);
     } catch (e: any) {
       console.error("Failed to fetch media library:", e);
-      setError(e?.toString?.() ?? "Failed to fetch media library");
+      setError(errorMessage(e, "Failed to fetch media library"));
     } finally {
       setLoading(false);
     }
@@ -15,6 +15,7 @@ import MovieCarousel from "../components/MovieCarousel";
 import { OcDownload2 } from "solid-icons/oc";
 
 import { isNachoAuthenticated } from "../lib/nachoAuth";
+import { errorMessage } from "../lib/utils";
 
 interface MoviesProps {
   isVisible: boolean;
@@ -359,7 +360,7 @@ const Movies: Component<MoviesProps> = (props) => {
       setPage(currentPage);
     } catch (e: any) {
       console.error("Failed to fetch trending movies:", e);
-      setError(e?.toString?.() ?? "Failed to fetch trending movies");
+      setError(errorMessage(e, "Failed to fetch trending movies"));
     } finally {
       setLoading(false);
       setLoadingMore(false);
@@ -401,7 +402,7 @@ const Movies: Component<MoviesProps> = (props) => {
       }
     } catch (e: any) {
       console.error("Failed to fetch trending movies:", e);
-      setError(e?.toString?.() ?? "Failed to fetch trending movies");
+      setError(errorMessage(e, "Failed to fetch trending movies"));
     } finally {
       setLoading(false);
     }
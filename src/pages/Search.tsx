@@ -12,6 +12,7 @@ import MovieDetails from "../components/MovieDetails";
 import SeriesDetails from "../components/SeriesDetails";
 import { BiRegularMovie } from "solid-icons/bi";
 import { HiSolidTv } from "solid-icons/hi";
+import { errorMessage } from "../lib/utils";
 
 interface SearchMovieResult {
   id: number;
@@ -357,7 +358,7 @@ const Search: Component = () => {
       setShows(showsResponse.results);
     } catch (e: any) {
       console.error("Search failed:", e);
-      setError(e?.toString?.() ?? "Search failed");
+      setError(errorMessage(e, "Search failed"));
       setMovies([]);
       setShows([]);
     } finally {
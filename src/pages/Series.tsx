@@ -25,6 +25,7 @@ import {
   syncWatchHistory,
   getLastRefreshTime,
 } from "../lib/userHistoryStore";
+import { errorMessage } from "../lib/utils";
 
 interface SeriesProps {
   isVisible: boolean;
@@ -428,7 +429,7 @@ const Series: Component<SeriesProps> = (props) => {
       setPage(currentPage);
     } catch (e: any) {
       console.error("Failed to fetch trending shows:", e);
-      setError(e?.toString?.() ?? "Failed to fetch trending shows");
+      setError(errorMessage(e, "Failed to fetch trending shows"));
     } finally {
       setLoading(false);
       setLoadingMore(false);
@@ -468,7 +469,7 @@ const Series: Component<SeriesProps> = (props) => {
       }
     } catch (e: any) {
       console.error("Failed to fetch trending shows:", e);
-      setError(e?.toString?.() ?? "Failed to fetch trending shows");
+      setError(errorMessage(e, "Failed to fetch trending shows"));
     } finally {
       setLoading(false);
     }
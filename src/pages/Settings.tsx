@@ -1,19 +1,9 @@
 import { Component, createSignal, onMount, Show } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
 import type { AppSettings } from "../types/settings";
-import { fetch } from "@tauri-apps/plugin-http";
 import { openUrl } from "@tauri-apps/plugin-opener";
-
-interface UserInfo {
-  id: string;
-  username: string;
-  isAdmin: boolean;
-}
-
-interface UserResponse {
-  success: boolean;
-  user: UserInfo;
-}
+import { errorMessage, isCommandError } from "../lib/utils";
+import type { NachoUserInfo as UserInfo } from "../lib/nachoAuth";
 
 const Settings: Component = () => {
   const [nachoServerUrl, setNachoServerUrl] = createSignal<string>("");
@@ -40,7 +30,7 @@ const Settings: Component = () => {
       setIsLinked(!!(settings.nacho_server_url && settings.nacho_auth_token));
     } catch (e: any) {
       console.error("Failed to load settings:", e);
-      setError(e?.toString?.() ?? "Failed to load settings");
+      setError(errorMessage(e, "Failed to load settings"));
     } finally {
       setLoading(false);
     }
@@ -66,7 +56,7 @@ const Settings: Component = () => {
       setTimeout(() => setSuccessMessage(null), 3000);
     } catch (e: any) {
       console.error("Failed to save settings:", e);
-      setError(e?.toString?.() ?? "Failed to save settings");
+      setError(errorMessage(e, "Failed to save settings"));
     } finally {
       setSaving(false);
     }
@@ -91,48 +81,43 @@ const Settings: Component = () => {
         return;
       }
 
-      const response = await fetch(`${url}/api/user`, {
-        headers: {
-          "X-Nacho-Auth": token,
-        },
-      });
-
-      if (!response.ok) {
-        if (response.status === 401) {
+      let user: UserInfo;
+      try {
+        // Requested by the backend so it goes through the configured proxy
+        user = await invoke<UserInfo>("get_nacho_user", {
+          serverUrl: url,
+          authToken: token,
+        });
+      } catch (e) {
+        if (isCommandError(e) && e.code === "unauthorized") {
           setVerifyError("Invalid authentication token");
         } else {
-          setVerifyError(`Failed to verify token: ${response.status}`);
+          setVerifyError(errorMessage(e, "Failed to verify token"));
         }
         return;
       }
 
-      const data: UserResponse = await response.json();
-
-      if (data.success && data.user) {
-        setUserInfo(data.user);
-        setSuccessMessage("Token verified successfully!");
-
-        // Save the settings with the verified token
-        const settings: AppSettings = {
-          nacho_server_url: url,
-          nacho_auth_token: token,
-        };
-        await invoke("save_settings", { settings });
-
-        // Emit a custom event to notify other components about authentication
-        window.dispatchEvent(
-          new CustomEvent("nacho-auth-success", {
-            detail: { user: data.user },
-          })
-        );
-
-        setTimeout(() => setSuccessMessage(null), 3000);
-      } else {
-        setVerifyError("Invalid response from server");
-      }
+      setUserInfo(user);
+      setSuccessMessage("Token verified successfully!");
+
+      // Save the settings with the verified token
+      const settings: AppSettings = {
+        nacho_server_url: url,
+        nacho_auth_token: token,
+      };
+      await invoke("save_settings", { settings });
+
+      // Emit a custom event to notify other components about authentication
+      window.dispatchEvent(
+        new CustomEvent("nacho-auth-success", {
+          detail: { user },
+        })
+      );
+
+      setTimeout(() => setSuccessMessage(null), 3000);
     } catch (e: any) {
       console.error("Failed to verify token:", e);
-      setVerifyError(e?.toString?.() ?? "Failed to connect to Nacho Server");
+      setVerifyError(errorMessage(e, "Failed to connect to Nacho Server"));
     } finally {
       setVerifying(false);
     }
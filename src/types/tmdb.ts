@@ -17,6 +17,7 @@ export interface TmdbGenre {
  */
 export interface TmdbMovie {
   id: number;
+  imdb_id?: string;
   title: string;
   original_title?: string;
   overview?: string;